@@ -8,14 +8,16 @@ mod windows_util;
 
 use bytesize::{ByteSize, GB, GIB, KB, KIB, MB, MIB, PB, PIB, TB, TIB};
 use clap::{arg, crate_version, ArgAction, ArgGroup, ArgMatches, Command};
+use std::cell::Cell;
 use std::env;
 
+use std::fmt::Display;
 #[cfg(target_os = "linux")]
-use std::fs;
+use std::fs::File;
+use std::io::{self, Write};
 #[cfg(target_os = "linux")]
-use std::io::Error;
+use std::io::{Error, Read, Seek, SeekFrom};
 use std::ops::Mul;
-use std::process;
 use std::thread::sleep;
 use std::time::Duration;
 use uucore::{
@@ -32,6 +34,8 @@ struct MemInfo {
     total: u64,
     free: u64,
     available: u64,
+    /// The kernel doesn't report `MemAvailable` (before 3.14), `available` is estimated
+    available_estimated: bool,
     shared: u64,
     buffers: u64,
     cached: u64,
@@ -45,13 +49,43 @@ struct MemInfo {
     high_free: u64,
     commit_limit: u64,
     committed: u64,
+    /// Size of the compressed zswap pool, `None` if the kernel doesn't report it
+    zswap: Option<u64>,
+    /// Uncompressed size of the pages stored in the zswap pool
+    zswapped: Option<u64>,
 }
 
+impl MemInfo {
+    /// Used memory. By default this is `total - available`, `legacy` selects the
+    /// older procps formula `total - free - buffers - cache`.
+    fn used(&self, legacy: bool) -> u64 {
+        if legacy {
+            self.total
+                .saturating_sub(self.free)
+                .saturating_sub(self.buffers)
+                .saturating_sub(self.cached + self.reclaimable)
+        } else {
+            self.total.saturating_sub(self.available)
+        }
+    }
+}
+
+/// Read a sample from `source`, usually an open `/proc/meminfo`.
+///
+/// The source is rewound first, so a single handle can be kept open across samples.
 #[cfg(target_os = "linux")]
-fn parse_meminfo() -> Result<MemInfo, Error> {
+fn parse_meminfo_from<R: Read + Seek>(source: &mut R) -> Result<MemInfo, Error> {
+    let mut contents = String::new();
+    source.seek(SeekFrom::Start(0))?;
+    source.read_to_string(&mut contents)?;
+    parse_meminfo_str(&contents)
+}
+
+#[cfg(target_os = "linux")]
+fn parse_meminfo_str(contents: &str) -> Result<MemInfo, Error> {
     // kernel docs: https://www.kernel.org/doc/html/latest/filesystems/proc.html#meminfo
-    let contents = fs::read_to_string("/proc/meminfo")?;
     let mut mem_info = MemInfo::default();
+    let mut available = None;
 
     for line in contents.lines() {
         if let Some((key, value)) = line.split_once(':') {
@@ -59,7 +93,7 @@ fn parse_meminfo() -> Result<MemInfo, Error> {
             match key.trim() {
                 "MemTotal" => mem_info.total = parsed_value,
                 "MemFree" => mem_info.free = parsed_value,
-                "MemAvailable" => mem_info.available = parsed_value,
+                "MemAvailable" => available = Some(parsed_value),
                 "Shmem" => mem_info.shared = parsed_value,
                 "Buffers" => mem_info.buffers = parsed_value,
                 "Cached" => mem_info.cached = parsed_value,
@@ -72,6 +106,8 @@ fn parse_meminfo() -> Result<MemInfo, Error> {
                 "HighFree" => mem_info.high_free = parsed_value,
                 "CommitLimit" => mem_info.commit_limit = parsed_value,
                 "Committed_AS" => mem_info.committed = parsed_value,
+                "Zswap" => mem_info.zswap = Some(parsed_value),
+                "Zswapped" => mem_info.zswapped = Some(parsed_value),
                 _ => {}
             }
         }
@@ -90,6 +126,13 @@ fn parse_meminfo() -> Result<MemInfo, Error> {
 
     mem_info.swap_used = mem_info.swap_total - mem_info.swap_free;
 
+    // Older kernels lack `MemAvailable`, estimate it like procps did before.
+    mem_info.available = available.unwrap_or_else(|| {
+        (mem_info.free + mem_info.buffers + mem_info.cached + mem_info.reclaimable)
+            .min(mem_info.total)
+    });
+    mem_info.available_estimated = available.is_none();
+
     Ok(mem_info)
 }
 
@@ -116,6 +159,8 @@ fn parse_meminfo() -> Result<MemInfo, Box<dyn std::error::Error>> {
         high_free: 0,
         commit_limit: 0,
         committed: 0,
+        zswap: None,
+        zswapped: None,
     };
 
     Ok(mem_info)
@@ -212,37 +257,52 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let duration = Duration::from_nanos(seconds.mul(1_000_000_000.0).round() as u64);
     let construct_str = parse_output_format(&matches);
 
-    let output_meminfo = || match parse_meminfo() {
-        Ok(mem_info) => {
-            print!("{}", construct_str(&mem_info));
-        }
-        Err(e) => {
-            eprintln!("free: failed to read memory info: {}", e);
-            process::exit(1);
-        }
-    };
+    #[cfg(target_os = "linux")]
+    let mut source = File::open("/proc/meminfo")
+        .map_err(|e| USimpleError::new(1, format!("failed to read memory info: {}", e)))?;
+    #[cfg(target_os = "linux")]
+    let sample = || parse_meminfo_from(&mut source);
+    #[cfg(not(target_os = "linux"))]
+    let sample = parse_meminfo;
+
+    print_samples(
+        &mut io::stdout().lock(),
+        count,
+        duration,
+        !matches.get_flag("line"),
+        sample,
+        construct_str,
+    )
+}
 
-    let do_sleep = || {
-        if !matches.get_flag("line") {
-            println!();
+/// Print `count` samples, or samples forever if `None`, `interval` apart.
+///
+/// With `separate`, an empty line is printed after each sample but the last.
+fn print_samples<E: Display>(
+    out: &mut impl Write,
+    count: Option<u64>,
+    interval: Duration,
+    separate: bool,
+    mut sample: impl FnMut() -> Result<MemInfo, E>,
+    construct_str: impl Fn(&MemInfo) -> String,
+) -> UResult<()> {
+    let mut printed = 0;
+    loop {
+        let mem_info = sample()
+            .map_err(|e| USimpleError::new(1, format!("failed to read memory info: {}", e)))?;
+        write!(out, "{}", construct_str(&mem_info))?;
+        printed += 1;
+
+        if count.is_some_and(|count| printed >= count) {
+            return Ok(());
         }
-        sleep(duration);
-    };
 
-    if let Some(c) = count {
-        for _ in 0..c - 1 {
-            output_meminfo();
-            do_sleep();
-        }
-        output_meminfo();
-    } else {
-        loop {
-            output_meminfo();
-            do_sleep();
+        if separate {
+            writeln!(out)?;
         }
+        out.flush()?;
+        sleep(interval);
     }
-
-    Ok(())
 }
 
 #[allow(clippy::cognitive_complexity)]
@@ -288,6 +348,11 @@ pub fn uu_app() -> Command {
                 .value_parser(clap::value_parser!(u64)),
             arg!(-L --line "show output on a single line").action(ArgAction::SetTrue),
             arg!(-w --wide "wide output").action(ArgAction::SetTrue),
+            arg!(   --zswap "show zswap usage, if the kernel reports it")
+                .action(ArgAction::SetTrue),
+            arg!(   --"legacy-used" "calculate used memory as total - free - buffers - cache")
+                .action(ArgAction::SetTrue),
+            arg!(   --verbose "tell when values are estimated").action(ArgAction::SetTrue),
             arg!(   --help "display this help and exit").action(ArgAction::Help),
         ])
 }
@@ -321,6 +386,12 @@ fn parse_output_format(matches: &ArgMatches) -> impl Fn(&MemInfo) -> String {
     let lohi = matches.get_flag("lohi");
     let committed = matches.get_flag("committed");
     let one_line = matches.get_flag("line");
+    let legacy = matches.get_flag("legacy-used");
+    let zswap = matches.get_flag("zswap");
+    let verbose = matches.get_flag("verbose");
+    // Only tell once that the row is missing, not for every sample.
+    let zswap_missing = Cell::new(false);
+    let available_warned = Cell::new(false);
 
     let convert = detect_unit(matches);
 
@@ -334,14 +405,21 @@ fn parse_output_format(matches: &ArgMatches) -> impl Fn(&MemInfo) -> String {
     };
 
     move |mem_info: &MemInfo| {
+        if verbose && mem_info.available_estimated && !available_warned.replace(true) {
+            eprintln!(
+                "{}: MemAvailable is not reported by the kernel, estimating available memory",
+                uucore::util_name()
+            );
+        }
+
         if one_line {
-            construct_one_line_str(mem_info, &n2s)
+            construct_one_line_str(mem_info, legacy, &n2s)
         } else {
             let mut str = String::new();
             if wide {
-                str += &construct_wide_str(mem_info, &n2s);
+                str += &construct_wide_str(mem_info, legacy, &n2s);
             } else {
-                str += &construct_str(mem_info, &n2s);
+                str += &construct_str(mem_info, legacy, &n2s);
             }
 
             if lohi {
@@ -350,8 +428,19 @@ fn parse_output_format(matches: &ArgMatches) -> impl Fn(&MemInfo) -> String {
 
             str += &construct_swap_str(mem_info, &n2s);
 
+            if zswap {
+                match construct_zswap_str(mem_info, &n2s) {
+                    Some(row) => str += &row,
+                    None if !zswap_missing.replace(true) => eprintln!(
+                        "{}: zswap is not reported by the kernel, omitting the Zswap row",
+                        uucore::util_name()
+                    ),
+                    None => {}
+                }
+            }
+
             if total {
-                str += &construct_total_str(mem_info, &n2s);
+                str += &construct_total_str(mem_info, legacy, &n2s);
             }
 
             if committed {
@@ -363,7 +452,7 @@ fn parse_output_format(matches: &ArgMatches) -> impl Fn(&MemInfo) -> String {
     }
 }
 
-fn construct_one_line_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+fn construct_one_line_str(mem_info: &MemInfo, legacy: bool, n2s: &dyn Fn(u64) -> String) -> String {
     format!(
         "{:8}{:>11} {:8}{:>11}  {:8}{:>10} {:8}{:>11}\n",
         "SwapUse",
@@ -371,13 +460,13 @@ fn construct_one_line_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> St
         "CachUse",
         n2s(mem_info.buffers + mem_info.cached + mem_info.reclaimable),
         "MemUse",
-        n2s(mem_info.total - mem_info.available),
+        n2s(mem_info.used(legacy)),
         "MemFree",
         n2s(mem_info.free)
     )
 }
 
-fn construct_wide_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+fn construct_wide_str(mem_info: &MemInfo, legacy: bool, n2s: &dyn Fn(u64) -> String) -> String {
     format!(
         "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
         " ", "total", "used", "free", "shared", "buffers", "cache", "available",
@@ -385,7 +474,7 @@ fn construct_wide_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String
         "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
         "Mem:",
         n2s(mem_info.total),
-        n2s(mem_info.total - mem_info.available),
+        n2s(mem_info.used(legacy)),
         n2s(mem_info.free),
         n2s(mem_info.shared),
         n2s(mem_info.buffers),
@@ -394,7 +483,7 @@ fn construct_wide_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String
     )
 }
 
-fn construct_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+fn construct_str(mem_info: &MemInfo, legacy: bool, n2s: &dyn Fn(u64) -> String) -> String {
     format!(
         "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
         " ", "total", "used", "free", "shared", "buff/cache", "available",
@@ -402,7 +491,7 @@ fn construct_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
         "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
         "Mem:",
         n2s(mem_info.total),
-        n2s(mem_info.total - mem_info.available),
+        n2s(mem_info.used(legacy)),
         n2s(mem_info.free),
         n2s(mem_info.shared),
         n2s(mem_info.buffers + mem_info.cached + mem_info.reclaimable),
@@ -436,11 +525,22 @@ fn construct_swap_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String
     )
 }
 
-fn construct_total_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+/// The `Zswap` row: the uncompressed size of what is stored as total, the compressed
+/// pool size as used. `None` if the kernel doesn't report zswap.
+fn construct_zswap_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> Option<String> {
+    Some(format!(
+        "{:8}{:>12}{:>12}\n",
+        "Zswap:",
+        n2s(mem_info.zswapped?),
+        n2s(mem_info.zswap?)
+    ))
+}
+
+fn construct_total_str(mem_info: &MemInfo, legacy: bool, n2s: &dyn Fn(u64) -> String) -> String {
     construct_tuf_combo_str(
         "Total:",
         mem_info.total + mem_info.swap_total,
-        mem_info.total - mem_info.available + mem_info.swap_used,
+        mem_info.used(legacy) + mem_info.swap_used,
         (mem_info.free + mem_info.swap_free).into(),
         n2s,
     )
@@ -507,6 +607,12 @@ fn detect_unit(arg: &ArgMatches) -> fn(u64) -> u64 {
 mod test {
     use super::*;
 
+    /// One-off sample like the other platforms' `parse_meminfo`.
+    #[cfg(target_os = "linux")]
+    fn parse_meminfo() -> Result<MemInfo, Error> {
+        parse_meminfo_from(&mut File::open("/proc/meminfo")?)
+    }
+
     #[test]
     fn test_line_wide() {
         let matches_with_line = uu_app()
@@ -530,6 +636,250 @@ mod test {
         }
     }
 
+    fn fixture() -> MemInfo {
+        MemInfo {
+            total: 16000,
+            free: 2000,
+            available: 9000,
+            buffers: 500,
+            cached: 5000,
+            reclaimable: 1000,
+            swap_total: 4000,
+            swap_free: 3000,
+            swap_used: 1000,
+            ..Default::default()
+        }
+    }
+
+    fn columns(line: &str) -> Vec<u64> {
+        line.split_whitespace()
+            .skip(1)
+            .map(|it| it.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_used() {
+        let mem_info = fixture();
+
+        assert_eq!(mem_info.used(false), 7000);
+        assert_eq!(mem_info.used(true), 7500);
+        assert_eq!(MemInfo::default().used(true), 0);
+    }
+
+    #[test]
+    fn test_total_is_sum_of_mem_and_swap() {
+        let mem_info = fixture();
+        let n2s = |x: u64| x.to_string();
+
+        for legacy in [false, true] {
+            let output = construct_str(&mem_info, legacy, &n2s)
+                + &construct_swap_str(&mem_info, &n2s)
+                + &construct_total_str(&mem_info, legacy, &n2s);
+            let lines: Vec<_> = output.lines().collect();
+
+            let mem = columns(lines[1]);
+            let swap = columns(lines[2]);
+            let total = columns(lines[3]);
+
+            assert_eq!(mem[1], mem_info.used(legacy));
+            for i in 0..3 {
+                assert_eq!(total[i], mem[i] + swap[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect_unit() {
+        // 1234567890123 KiB, a bit more than 1 PiB
+        const KIB: u64 = 1_234_567_890_123;
+
+        // (flag, without --si, with --si), the SI variants of the IEC flags are used with --si
+        let table = [
+            (None, 1_234_567_890_123, 1_264_197_519_485),
+            (
+                Some("--bytes"),
+                1_264_197_519_485_952,
+                1_264_197_519_485_952,
+            ),
+            (Some("--kilo"), 1_264_197_519_485, 1_264_197_519_485),
+            (Some("--mega"), 1_264_197_519, 1_264_197_519),
+            (Some("--giga"), 1_264_197, 1_264_197),
+            (Some("--tera"), 1_264, 1_264),
+            (Some("--peta"), 1, 1),
+            (Some("--kibi"), 1_234_567_890_123, 1_264_197_519_485),
+            (Some("--mebi"), 1_205_632_705, 1_264_197_519),
+            (Some("--gibi"), 1_177_375, 1_264_197),
+            (Some("--tebi"), 1_149, 1_264),
+            (Some("--pebi"), 1, 1),
+        ];
+
+        for (flag, expected, expected_si) in table {
+            for (si, expected) in [(false, expected), (true, expected_si)] {
+                let args = ["free"].into_iter().chain(flag).chain(si.then_some("--si"));
+                let matches = uu_app().try_get_matches_from(args).unwrap();
+
+                assert_eq!(detect_unit(&matches)(KIB), expected, "{flag:?} si={si}");
+            }
+        }
+    }
+
+    /// A `/proc/meminfo` stand-in whose contents change every time it is rewound.
+    #[cfg(target_os = "linux")]
+    struct Samples {
+        samples: std::vec::IntoIter<String>,
+        current: io::Cursor<String>,
+        rewinds: usize,
+    }
+
+    #[cfg(target_os = "linux")]
+    impl Read for Samples {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.current.read(buf)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    impl Seek for Samples {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            assert_eq!(pos, SeekFrom::Start(0));
+            self.rewinds += 1;
+            self.current = io::Cursor::new(self.samples.next().unwrap_or_default());
+            Ok(0)
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_samples_reuse_source() {
+        let meminfo = |free: u64| {
+            format!(
+                "MemTotal:       16000 kB\nMemFree:        {free} kB\nMemAvailable:   9000 kB\n\
+                 SwapTotal:       4000 kB\nSwapFree:        3000 kB\n"
+            )
+        };
+        let mut source = Samples {
+            samples: vec![meminfo(1000), meminfo(2000), meminfo(3000)].into_iter(),
+            current: io::Cursor::new(String::new()),
+            rewinds: 0,
+        };
+
+        let matches = uu_app()
+            .try_get_matches_from(["free", "-c", "3", "-s", "0.01"])
+            .unwrap();
+        let mut out = Vec::new();
+        print_samples(
+            &mut out,
+            matches.get_one("count").copied(),
+            Duration::from_millis(10),
+            true,
+            || parse_meminfo_from(&mut source),
+            parse_output_format(&matches),
+        )
+        .unwrap();
+        assert_eq!(source.rewinds, 3);
+
+        let out = String::from_utf8(out).unwrap();
+        let outputs: Vec<_> = out.split("\n\n").collect();
+        assert_eq!(outputs.len(), 3);
+        for (output, free) in outputs.iter().zip([1000, 2000, 3000]) {
+            let mem = columns(output.lines().nth(1).unwrap());
+            assert_eq!(mem[2], free);
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_samples_error() {
+        let mut out = Vec::new();
+        let err = print_samples(
+            &mut out,
+            None,
+            Duration::ZERO,
+            true,
+            || parse_meminfo_str("MemTotal: lots\n"),
+            |_: &MemInfo| String::new(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "failed to read memory info: Invalid memory info format"
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_available_fallback() {
+        // A 3.13 kernel, without MemAvailable.
+        let meminfo = "MemTotal:       16000 kB\nMemFree:         2000 kB\n\
+                       Buffers:          500 kB\nCached:          5000 kB\n\
+                       Shmem:            100 kB\nSReclaimable:    1000 kB\n\
+                       SwapTotal:       4000 kB\nSwapFree:        3000 kB\n";
+        let n2s = |x: u64| x.to_string();
+
+        let mem_info = parse_meminfo_str(meminfo).unwrap();
+        assert!(mem_info.available_estimated);
+        assert_eq!(mem_info.available, 8500);
+        assert_eq!(mem_info.used(false), 7500);
+
+        // total, used, free, shared, buff/cache, available
+        let plain = construct_str(&mem_info, false, &n2s);
+        assert_eq!(
+            columns(plain.lines().nth(1).unwrap()),
+            [16000, 7500, 2000, 100, 6500, 8500]
+        );
+        // total, used, free, shared, buffers, cache, available
+        let wide = construct_wide_str(&mem_info, false, &n2s);
+        assert_eq!(
+            columns(wide.lines().nth(1).unwrap()),
+            [16000, 7500, 2000, 100, 500, 6000, 8500]
+        );
+
+        let mem_info = parse_meminfo_str(&format!("{meminfo}MemAvailable:    9000 kB\n")).unwrap();
+        assert!(!mem_info.available_estimated);
+        assert_eq!(mem_info.available, 9000);
+        assert_eq!(mem_info.used(false), 7000);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_zswap() {
+        let meminfo = "MemTotal:       16000 kB\nMemFree:         2000 kB\n\
+                       SwapTotal:       4000 kB\nSwapFree:        3000 kB\n";
+
+        let mem_info = parse_meminfo_str(meminfo).unwrap();
+        assert_eq!(mem_info.zswap, None);
+        assert_eq!(mem_info.zswapped, None);
+
+        let mem_info = parse_meminfo_str(&format!(
+            "{meminfo}Zswap:            300 kB\nZswapped:        1200 kB\n"
+        ))
+        .unwrap();
+        assert_eq!(mem_info.zswap, Some(300));
+        assert_eq!(mem_info.zswapped, Some(1200));
+        assert_eq!(mem_info.swap_used, 1000);
+    }
+
+    #[test]
+    fn test_zswap_row() {
+        let mut mem_info = fixture();
+        let n2s = |x: u64| x.to_string();
+        assert_eq!(construct_zswap_str(&mem_info, &n2s), None);
+
+        mem_info.zswap = Some(262_144);
+        mem_info.zswapped = Some(1_048_576);
+        assert_eq!(
+            construct_zswap_str(&mem_info, &n2s).unwrap(),
+            "Zswap:       1048576      262144\n"
+        );
+        assert_eq!(
+            construct_zswap_str(&mem_info, &|x| humanized(x, false)).unwrap(),
+            "Zswap:         1.0Gi     256.0Mi\n"
+        );
+    }
+
     #[test]
     fn test_humanized_unit_for_zero() {
         assert_eq!("0B", humanized(0, false));
@@ -0,0 +1,174 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// Pattern matching with a cheap literal pre-check
+
+use regex::{Error, Regex, RegexBuilder};
+
+/// A compiled pgrep pattern.
+///
+/// Most patterns are plain process names like `nginx`. For those a substring search is enough
+/// to rule out the vast majority of processes, so the regex only runs on candidates that contain
+/// the literal. The regex still has the final say, the prefilter never changes the result.
+///
+/// Case insensitive patterns are compiled as such, with Unicode case folding, rather than
+/// lowercasing pattern and haystack: that would turn e.g. `\D` into `\d`.
+#[derive(Debug)]
+pub struct Matcher {
+    regex: Regex,
+    literal: Option<String>,
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<Self, Error> {
+        Ok(Self {
+            regex: RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()?,
+            // A plain substring search can't tell `NGINX` from `nginx`.
+            literal: (!ignore_case).then(|| required_literal(pattern)).flatten(),
+        })
+    }
+
+    /// The literal every match must contain, if the prefilter is in use.
+    pub fn literal(&self) -> Option<&str> {
+        self.literal.as_deref()
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        match &self.literal {
+            Some(literal) if !haystack.contains(literal.as_str()) => false,
+            _ => self.regex.is_match(haystack),
+        }
+    }
+}
+
+/// Returns the pattern itself if it has no metacharacters at all, so that it can only match
+/// where it occurs verbatim. Anything else (alternations, anchors, classes, ...) gets no
+/// prefilter rather than risking a false negative.
+fn required_literal(pattern: &str) -> Option<String> {
+    (!pattern.is_empty() && regex::escape(pattern) == pattern).then(|| pattern.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefilter_literal() {
+        assert_eq!(
+            Matcher::new("nginx", false).unwrap().literal(),
+            Some("nginx")
+        );
+        assert_eq!(
+            Matcher::new("kworker/0:1", false).unwrap().literal(),
+            Some("kworker/0:1")
+        );
+    }
+
+    #[test]
+    fn test_prefilter_bypassed() {
+        for pattern in [
+            "",
+            "nginx|apache",
+            "^nginx",
+            "nginx$",
+            "^nginx$",
+            "(?:a)|(?:b)",
+            "ngin.",
+            "nginx?",
+            "[n]ginx",
+            "(?i)nginx",
+            r"nginx\b",
+        ] {
+            assert_eq!(
+                Matcher::new(pattern, false).unwrap().literal(),
+                None,
+                "{pattern}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        let matcher = Matcher::new("nginx", true).unwrap();
+        assert_eq!(matcher.literal(), None);
+        assert!(matcher.is_match("NGINX"));
+
+        // Classes keep their meaning instead of being lowercased.
+        let matcher = Matcher::new("^[A-Z]+$", true).unwrap();
+        assert!(matcher.is_match("NGINX"));
+        assert!(matcher.is_match("nginx"));
+        let matcher = Matcher::new(r"^\D+$", true).unwrap();
+        assert!(matcher.is_match("nginx"));
+        assert!(!matcher.is_match("nginx2"));
+
+        // Unicode case folding, the capital sharp s folds to ß.
+        assert!(Matcher::new("STRAẞE", true).unwrap().is_match("straße"));
+        assert!(Matcher::new("straße", true).unwrap().is_match("STRAẞE"));
+        assert!(!Matcher::new("straße", false).unwrap().is_match("STRAẞE"));
+
+        // Anchoring for -x still composes.
+        let matcher = Matcher::new("^sshd$", true).unwrap();
+        assert!(matcher.is_match("SSHD"));
+        assert!(!matcher.is_match("sshd-session"));
+    }
+
+    #[test]
+    fn test_prefilter_equivalence() {
+        const WORDS: [&str; 8] = [
+            "nginx",
+            "worker",
+            "sshd",
+            "bash",
+            "-c",
+            "/usr/bin/",
+            "python3",
+            "process",
+        ];
+
+        // Deterministic pseudo random cmdlines, no need for a rand dependency.
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        let corpus: Vec<String> = (0..10_000)
+            .map(|_| {
+                let len = next() % 6;
+                (0..len)
+                    .map(|_| WORDS[(next() % WORDS.len() as u64) as usize])
+                    .collect::<Vec<_>>()
+                    .join(if next() % 2 == 0 { " " } else { "" })
+            })
+            .collect();
+
+        for pattern in [
+            "nginx",
+            "worker",
+            "nginx worker",
+            "/usr/bin/python3",
+            "-c",
+            "nginx|sshd",
+            "^bash",
+            "process$",
+            "py.hon",
+            "",
+        ] {
+            let matcher = Matcher::new(pattern, false).unwrap();
+            let regex = Regex::new(pattern).unwrap();
+
+            let with: Vec<_> = (0..corpus.len())
+                .filter(|i| matcher.is_match(&corpus[*i]))
+                .collect();
+            let without: Vec<_> = (0..corpus.len())
+                .filter(|i| regex.is_match(&corpus[*i]))
+                .collect();
+            assert_eq!(with, without, "{pattern}");
+        }
+    }
+}
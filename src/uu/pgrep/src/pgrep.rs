@@ -4,99 +4,64 @@
 // file that was distributed with this source code.
 
 // Pid utils
+pub mod matcher;
+pub mod pidfile;
 pub mod process;
+pub mod select;
+pub mod tree;
 
+use chrono::{DateTime, Local};
 use clap::{arg, crate_version, Arg, ArgAction, ArgGroup, ArgMatches, Command};
-use process::{walk_process, ProcessInformation, Teletype};
-use regex::Regex;
-use std::{collections::HashSet, sync::OnceLock};
+use pidfile::read_pidfile;
+use process::{boot_time_in, clock_ticks, process_start, Teletype};
+use select::{find_matching_pids, parse_patterns, Settings};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
 use uucore::{
-    error::{UResult, USimpleError},
+    entries::{grp2gid, uid2usr, usr2uid},
+    error::{strip_errno, UResult, USimpleError},
     format_usage, help_about, help_usage,
+    process::{getegid, geteuid, getuid},
 };
 
+/// Exit code procps uses for fatal errors, e.g. an unusable pidfile or proc filesystem.
+pub const EXIT_FATAL: i32 = 3;
+
 const ABOUT: &str = help_about!("pgrep.md");
 const USAGE: &str = help_usage!("pgrep.md");
 
-static REGEX: OnceLock<Regex> = OnceLock::new();
-
-struct Settings {
-    exact: bool,
-    full: bool,
-    ignore_case: bool,
-    inverse: bool,
-    newest: bool,
-    oldest: bool,
-    older: Option<u64>,
-    parent: Option<Vec<u64>>,
-    runstates: Option<String>,
-    terminal: Option<HashSet<Teletype>>,
-}
-
 /// # Conceptual model of `pgrep`
 ///
-/// At first, `pgrep` command will check the patterns is legal.
-/// In this stage, `pgrep` will construct regex if `--exact` argument was passed.
+/// At first, `pgrep` translates the command line into [select::Settings], which checks that
+/// the patterns are legal and combines them into one regex, anchored with `--exact`.
 ///
-/// Then, `pgrep` will collect all *matched* pids, and filtering them.
-/// In this stage `pgrep` command will collect all the pids and its information from __/proc/__
-/// file system. At the same time, `pgrep` will construct filters from command
-/// line arguments to filter the collected pids. Note that the "-o" and "-n" flag filters works
-/// if them enabled and based on general collecting result.
+/// Then, `pgrep` collects all the processes from __/proc/__ which match the pattern and
+/// the filters, see [select::find_matching_pids]. Note that the "-o" and "-n" flags pick
+/// from what the other filters left.
 ///
 /// Last, `pgrep` will construct output format from arguments, and print the processed result.
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
 
-    let pattern = try_get_pattern_from(&matches)?;
-    REGEX
-        .set(Regex::new(&pattern).map_err(|e| USimpleError::new(2, e.to_string()))?)
-        .unwrap();
-
-    let settings = Settings {
-        exact: matches.get_flag("exact"),
-        full: matches.get_flag("full"),
-        ignore_case: matches.get_flag("ignore-case"),
-        inverse: matches.get_flag("inverse"),
-        newest: matches.get_flag("newest"),
-        oldest: matches.get_flag("oldest"),
-        parent: matches
-            .get_many::<u64>("parent")
-            .map(|parents| parents.copied().collect()),
-        runstates: matches.get_one::<String>("runstates").cloned(),
-        older: matches.get_one::<u64>("older").copied(),
-        terminal: matches.get_many::<String>("terminal").map(|ttys| {
-            ttys.cloned()
-                .flat_map(Teletype::try_from)
-                .collect::<HashSet<_>>()
-        }),
-    };
-
-    if (!settings.newest
-        && !settings.oldest
-        && settings.runstates.is_none()
-        && settings.older.is_none()
-        && settings.parent.is_none()
-        && settings.terminal.is_none())
-        && pattern.is_empty()
-    {
+    let settings = settings_from(&matches)?;
+    if !settings.has_criteria() {
         return Err(USimpleError::new(
             2,
             "no matching criteria specified\nTry `pgrep --help' for more information.",
         ));
     }
 
-    // Collect pids
-    let pids = {
-        let mut pids = collect_matched_pids(&settings);
-        if pids.is_empty() {
-            uucore::error::set_exit_code(1);
-            pids
-        } else {
-            process_flag_o_n(&settings, &mut pids)
-        }
-    };
+    check_proc_root(settings.proc_root())?;
+
+    let pids = find_matching_pids(&settings);
+    if pids.is_empty() {
+        uucore::error::set_exit_code(1);
+    }
 
     // Processing output
     let output = if matches.get_flag("count") {
@@ -110,20 +75,58 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
                     // pgrep from procps-ng outputs the process name inside square brackets
                     // if /proc/<PID>/cmdline is empty
                     if it.cmdline.is_empty() {
-                        format!("{} [{}]", it.pid, it.clone().status().get("Name").unwrap())
+                        format!("{} [{}]", it.pid, it.clone().name().unwrap_or_default())
                     } else {
                         format!("{} {}", it.pid, it.cmdline)
                     }
                 })
                 .collect()
+        } else if matches.get_flag("list-start") {
+            let boot_time = boot_time_in(settings.proc_root()).map_err(|e| {
+                USimpleError::new(
+                    EXIT_FATAL,
+                    format!("failed to read the boot time: {}", strip_errno(&e)),
+                )
+            })?;
+            let ticks = clock_ticks();
+
+            pids.into_iter()
+                .map(|mut it| {
+                    let start = it.start_time().map_or_else(
+                        |_| "?".to_string(),
+                        |start| format_start(process_start(boot_time, start, ticks)),
+                    );
+                    let name = it.name().unwrap_or_default();
+                    format!("{} {} {}", it.pid, start, name)
+                })
+                .collect()
+        } else if matches.get_flag("list-user") {
+            let mut user_names = UserNames::default();
+
+            pids.into_iter()
+                .map(|mut it| {
+                    let user = it
+                        .euid()
+                        .map_or_else(|_| "?".to_string(), |uid| user_names.get(uid));
+                    let name = it.name().unwrap_or_default();
+                    format!("{} {} {}", it.pid, user, name)
+                })
+                .collect()
         } else if matches.get_flag("list-name") {
             pids.into_iter()
-                .map(|it| format!("{} {}", it.pid, it.clone().status().get("Name").unwrap()))
+                .map(|it| format!("{} {}", it.pid, it.clone().name().unwrap_or_default()))
                 .collect()
         } else {
             pids.into_iter().map(|it| format!("{}", it.pid)).collect()
         };
 
+        if matches.get_flag("null") {
+            // Every record is terminated, there is no final newline to add.
+            let output: String = formatted.iter().map(|it| format!("{it}\0")).collect();
+            print!("{}", output);
+            return Ok(());
+        }
+
         formatted.join(delimiter)
     };
 
@@ -134,147 +137,192 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     Ok(())
 }
 
-/// Try to get the pattern from the command line arguments. Returns an empty string if no pattern
-/// is specified.
-fn try_get_pattern_from(matches: &ArgMatches) -> UResult<String> {
-    let pattern = match matches.get_many::<String>("pattern") {
-        Some(patterns) if patterns.len() > 1 => {
-            return Err(USimpleError::new(
-                2,
-                "only one pattern can be provided\nTry `pgrep --help' for more information.",
-            ))
-        }
-        Some(mut patterns) => patterns.next().unwrap(),
-        None => return Ok(String::new()),
-    };
-
-    let pattern = if matches.get_flag("ignore-case") {
-        &pattern.to_lowercase()
-    } else {
-        pattern
-    };
-
-    let pattern = if matches.get_flag("exact") {
-        &format!("^{}$", pattern)
-    } else {
-        pattern
-    };
+/// Translate the command line into the process selection.
+fn settings_from(matches: &ArgMatches) -> UResult<Settings> {
+    let mut builder = Settings::builder()
+        .patterns(try_get_patterns_from(matches)?)
+        .exact(matches.get_flag("exact"))
+        .full(matches.get_flag("full"))
+        .ignore_case(matches.get_flag("ignore-case"))
+        .inverse(matches.get_flag("inverse"))
+        .newest(matches.get_flag("newest"))
+        .oldest(matches.get_flag("oldest"))
+        .threads(matches.get_flag("lightweight"))
+        .debug(matches.get_flag("debug"));
+
+    if let Some(parents) = matches.get_many::<u64>("parent") {
+        builder = builder.parents(parents.copied());
+    }
+    if let Some(path) = matches.get_one::<String>("pidfile") {
+        builder = builder.pidfile(read_pidfile(path, matches.get_flag("logpidfile"))?);
+    }
+    if let Some(runstates) = matches.get_one::<String>("runstates") {
+        builder = builder.runstates(runstates);
+    }
+    if let Some(older) = matches.get_one::<f64>("older") {
+        builder = builder.older(*older);
+    }
+    if let Some(younger) = matches.get_one::<f64>("younger") {
+        builder = builder.younger(*younger);
+    }
+    if let Some(ttys) = matches.get_many::<String>("terminal") {
+        builder = builder.terminals(ttys.cloned().flat_map(Teletype::try_from));
+    }
+    if let Some(cgroups) = matches.get_many::<String>("cgroup") {
+        builder = builder.cgroups(cgroups.cloned());
+    }
+    if let Some(euids) = matches.get_many::<u32>("euid") {
+        builder = builder.euids(euids.copied());
+    }
+    if let Some(uids) = matches.get_many::<u32>("uid") {
+        builder = builder.uids(uids.copied());
+    }
+    if let Some(gids) = matches.get_many::<u32>("group") {
+        builder = builder.gids(gids.copied());
+    }
+    if let Some(gids) = matches.get_many::<u32>("supgroup") {
+        builder = builder.supgroups(gids.copied());
+    }
+    if let Some(root) = matches.get_one::<String>("proc-root") {
+        builder = builder.proc_root(root);
+    }
 
-    Ok(pattern.to_string())
+    builder
+        .build()
+        .map_err(|e| USimpleError::new(2, e.to_string()))
 }
 
-/// Collect pids with filter construct from command line arguments
-fn collect_matched_pids(settings: &Settings) -> Vec<ProcessInformation> {
-    // Filtration general parameters
-    let filtered: Vec<ProcessInformation> = {
-        let mut tmp_vec = Vec::new();
-
-        for mut pid in walk_process().collect::<Vec<_>>() {
-            let run_state_matched = match (&settings.runstates, (pid).run_state()) {
-                (Some(arg_run_states), Ok(pid_state)) => {
-                    arg_run_states.contains(&pid_state.to_string())
-                }
-                (_, Err(_)) => false,
-                _ => true,
-            };
-
-            let binding = pid.status();
-            let name = binding.get("Name").unwrap();
-            let name = if settings.ignore_case {
-                name.to_lowercase()
-            } else {
-                name.into()
-            };
-            let pattern_matched = {
-                let want = if settings.exact {
-                    // Equals `Name` in /proc/<pid>/status
-                    // The `unwrap` operation must succeed
-                    // because the REGEX has been verified as correct in `uumain`.
-                    &name
-                } else if settings.full {
-                    // Equals `cmdline` in /proc/<pid>/cmdline
-                    &pid.cmdline
-                } else {
-                    // From manpage:
-                    // The process name used for matching is limited to the 15 characters present in the output of /proc/pid/stat.
-                    &pid.proc_stat()[..15]
-                };
-
-                REGEX.get().unwrap().is_match(want)
-            };
-
-            let tty_matched = match &settings.terminal {
-                Some(ttys) => ttys.contains(&pid.tty()),
-                None => true,
-            };
-
-            let arg_older = settings.older.unwrap_or(0);
-            let older_matched = pid.start_time().unwrap() >= arg_older;
-
-            // the PPID is the fourth field in /proc/<PID>/stat
-            // (https://www.kernel.org/doc/html/latest/filesystems/proc.html#id10)
-            let stat = pid.stat();
-            let ppid = stat.get(3);
-            let parent_matched = match (&settings.parent, ppid) {
-                (Some(parents), Some(ppid)) => parents.contains(&ppid.parse::<u64>().unwrap()),
-                _ => true,
-            };
-
-            if (run_state_matched
-                && pattern_matched
-                && tty_matched
-                && older_matched
-                && parent_matched)
-                ^ settings.inverse
-            {
-                tmp_vec.push(pid);
+/// Try to get the patterns from the command line arguments. Returns no patterns if none is
+/// specified.
+///
+/// Patterns read with `--patterns-from` are later combined into a single alternation, see
+/// [select::combine_patterns].
+fn try_get_patterns_from(matches: &ArgMatches) -> UResult<Vec<String>> {
+    let patterns =
+        if let Some(path) = matches.get_one::<String>("patterns-from") {
+            read_patterns(path)?
+        } else {
+            match matches.get_many::<String>("pattern") {
+                Some(patterns) if patterns.len() > 1 => return Err(USimpleError::new(
+                    2,
+                    "only one pattern can be provided\nTry `pgrep --help' for more information.",
+                )),
+                Some(mut patterns) => vec![patterns.next().unwrap().clone()],
+                None => return Ok(Vec::new()),
             }
-        }
-        tmp_vec
-    };
+        };
 
-    filtered
+    Ok(patterns)
 }
 
-/// Sorting pids for flag `-o` and `-n`.
+/// Read one pattern per line from `path`, or from stdin if `path` is `-`.
 ///
-/// This function can also be used as a filter to filter out process information.
-fn process_flag_o_n(
-    settings: &Settings,
-    pids: &mut [ProcessInformation],
-) -> Vec<ProcessInformation> {
-    if settings.oldest || settings.newest {
-        pids.sort_by(|a, b| {
-            b.clone()
-                .start_time()
-                .unwrap()
-                .cmp(&a.clone().start_time().unwrap())
-        });
+/// Empty lines and lines starting with `#` are skipped.
+fn read_patterns(path: &str) -> UResult<Vec<String>> {
+    let content = if path == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content).map(|_| content)
+    } else {
+        fs::read_to_string(path)
+    }
+    .map_err(|e| {
+        USimpleError::new(
+            EXIT_FATAL,
+            format!("failed to read patterns from '{path}': {e}"),
+        )
+    })?;
 
-        let start_time = if settings.newest {
-            pids.first().cloned().unwrap().start_time().unwrap()
-        } else {
-            pids.last().cloned().unwrap().start_time().unwrap()
-        };
+    Ok(parse_patterns(&content))
+}
 
-        // There might be some process start at same time, so need to be filtered.
-        let mut filtered = pids
-            .iter()
-            .filter(|it| (*it).clone().start_time().unwrap() == start_time)
-            .collect::<Vec<_>>();
+/// Parse a non-negative, possibly fractional, number of seconds.
+fn parse_seconds(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds),
+        _ => Err(format!("invalid number of seconds: '{value}'")),
+    }
+}
 
-        if settings.newest {
-            filtered.sort_by(|a, b| b.pid.cmp(&a.pid));
-        } else {
-            filtered.sort_by(|a, b| a.pid.cmp(&b.pid));
-        }
+/// Value parser for `-u` and `-U`: a user id, a user name, or `self` for the id `own`
+/// returns, i.e. that of whoever runs us.
+pub fn parse_uid_or_username(own: fn() -> u32) -> impl Fn(&str) -> Result<u32, String> + Clone {
+    move |value| match value {
+        "self" => Ok(own()),
+        _ => value
+            .parse()
+            .or_else(|_| usr2uid(value))
+            .map_err(|_| format!("invalid user name: {value}")),
+    }
+}
 
-        vec![filtered.first().cloned().unwrap().clone()]
+/// Value parser for `-G` and `--supgroup`: a group id, a group name, or `self` for our effective group.
+pub fn parse_gid_or_group_name(value: &str) -> Result<u32, String> {
+    match value {
+        "self" => Ok(getegid()),
+        _ => value
+            .parse()
+            .or_else(|_| grp2gid(value))
+            .map_err(|_| format!("invalid group name: {value}")),
+    }
+}
+
+/// Fail with [EXIT_FATAL] unless the processes in the proc filesystem at `root` can be listed.
+///
+/// A missing or unreadable directory is as fatal as one without a single process and no
+/// `self` entry, as found in chroots and containers where proc is not mounted. Without the
+/// check, nothing would match and the user would be told so with the usual exit code 1.
+pub fn check_proc_root(root: &Path) -> UResult<()> {
+    let mounted = fs::read_dir(root).is_ok_and(|mut entries| {
+        root.join("self").exists()
+            || entries.any(|entry| {
+                entry.is_ok_and(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|name| name.parse::<u32>().is_ok())
+                })
+            })
+    });
+
+    if mounted {
+        Ok(())
     } else {
-        pids.to_vec()
+        Err(USimpleError::new(
+            EXIT_FATAL,
+            format!("{} is not mounted or not accessible", root.display()),
+        ))
     }
 }
 
+/// User names by uid, looked up once per run as many matches usually share a few owners.
+#[derive(Default)]
+struct UserNames(HashMap<u32, String>);
+
+impl UserNames {
+    /// The name of the user `uid`, or the uid itself if there is no such user.
+    fn get(&mut self, uid: u32) -> String {
+        self.0
+            .entry(uid)
+            .or_insert_with(|| uid2usr(uid).unwrap_or_else(|_| uid.to_string()))
+            .clone()
+    }
+}
+
+/// Format a start time given in seconds since the epoch as local ISO 8601.
+fn format_start(seconds: u64) -> String {
+    i64::try_from(seconds)
+        .ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .map_or_else(
+            || "?".to_string(),
+            |time| {
+                time.with_timezone(&Local)
+                    .format("%Y-%m-%dT%H:%M:%S%:z")
+                    .to_string()
+            },
+        )
+}
+
 #[allow(clippy::cognitive_complexity)]
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
@@ -289,29 +337,55 @@ pub fn uu_app() -> Command {
                 .hide_default_value(true),
             arg!(-l     --"list-name"           "list PID and process name"),
             arg!(-a     --"list-full"           "list PID and full command line"),
+            arg!(       --"list-start"          "list PID, start time and process name")
+                .conflicts_with_all(["list-name", "list-full", "count"]),
+            arg!(       --"list-user"           "list PID, owner and process name")
+                .conflicts_with_all(["list-name", "list-full", "list-start", "count"]),
+            arg!(       --null                  "terminate each record with a NUL byte")
+                .short('0')
+                .conflicts_with_all(["delimiter", "count"]),
             arg!(-v     --inverse               "negates the matching"),
-            // arg!(-w     --lightweight           "list all TID"),
+            arg!(-w     --lightweight           "list all TID"),
             arg!(-c     --count                 "count of matching processes"),
             arg!(-f     --full                  "use full process name to match"),
             // arg!(-g     --pgroup <PGID>     ... "match listed process group IDs"),
-            // arg!(-G     --group <GID>       ... "match real group IDs"),
+            arg!(-G     --group <GID>           "match real group IDs")
+                .value_delimiter(',')
+                .value_parser(parse_gid_or_group_name),
             arg!(-i     --"ignore-case"         "match case insensitively"),
             arg!(-n     --newest                "select most recently started"),
             arg!(-o     --oldest                "select least recently started"),
             arg!(-O     --older <seconds>       "select where older than seconds")
-                .value_parser(clap::value_parser!(u64)),
+                .value_parser(parse_seconds),
+            arg!(-Y     --younger <seconds>     "select where younger than seconds")
+                .value_parser(parse_seconds),
             arg!(-P     --parent <PPID>         "match only child processes of the given parent")
                 .value_delimiter(',')
                 .value_parser(clap::value_parser!(u64)),
             // arg!(-s     --session <SID>         "match session IDs"),
             arg!(-t     --terminal <tty>        "match by controlling terminal")
                 .value_delimiter(','),
-            // arg!(-u     --euid <ID>         ... "match by effective IDs"),
-            // arg!(-U     --uid <ID>          ... "match by real IDs"),
+            arg!(-u     --euid <ID>             "match by effective IDs")
+                .value_delimiter(',')
+                .value_parser(parse_uid_or_username(geteuid)),
+            arg!(-U     --uid <ID>              "match by real IDs")
+                .value_delimiter(',')
+                .value_parser(parse_uid_or_username(getuid)),
             arg!(-x     --exact                 "match exactly with the command name"),
-            // arg!(-F     --pidfile <file>        "read PIDs from file"),
-            // arg!(-L     --logpidfile            "fail if PID file is not locked"),
+            arg!(-F     --pidfile <file>        "read PIDs from file"),
+            arg!(-L     --logpidfile            "fail if PID file is not locked")
+                .requires("pidfile"),
             arg!(-r     --runstates <state>     "match runstates [D,S,Z,...]"),
+            arg!(       --"patterns-from" <file> "read patterns from file, one per line ('-' for stdin)")
+                .conflicts_with("pattern"),
+            arg!(       --cgroup <grp>          "match by cgroup v2 names")
+                .value_delimiter(','),
+            arg!(       --supgroup <GID>        "match supplementary group IDs")
+                .value_delimiter(',')
+                .value_parser(parse_gid_or_group_name),
+            arg!(       --debug                 "report skipped processes and why similar ones were not selected"),
+            arg!(       --"proc-root" <dir>     "read processes from dir instead of /proc or $PROCPS_PROC")
+                .hide(true),
             // arg!(       --ns <PID>              "match the processes that belong to the same namespace as <pid>"),
             // arg!(       --nslist <ns>       ... "list which namespaces will be considered for the --ns option."),
         ])
@@ -322,3 +396,70 @@ pub fn uu_app() -> Command {
                 .index(1),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_seconds() {
+        assert_eq!(parse_seconds("1"), Ok(1.0));
+        assert_eq!(parse_seconds("0.5"), Ok(0.5));
+        assert!(parse_seconds("-1").is_err());
+        assert!(parse_seconds("inf").is_err());
+        assert!(parse_seconds("NaN").is_err());
+        assert!(parse_seconds("abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_uid_or_username() {
+        let parse = parse_uid_or_username(geteuid);
+        assert_eq!(parse("self"), Ok(geteuid()));
+        assert_eq!(parse("root"), Ok(0));
+        assert_eq!(parse("1234"), Ok(1234));
+        assert_eq!(
+            parse("no-such-user"),
+            Err("invalid user name: no-such-user".into())
+        );
+        assert_eq!(parse_uid_or_username(getuid)("self"), Ok(getuid()));
+    }
+
+    #[test]
+    fn test_parse_gid_or_group_name() {
+        assert_eq!(parse_gid_or_group_name("self"), Ok(getegid()));
+        assert_eq!(parse_gid_or_group_name("0"), Ok(0));
+        assert_eq!(
+            parse_gid_or_group_name("no-such-group"),
+            Err("invalid group name: no-such-group".into())
+        );
+    }
+
+    #[test]
+    fn test_id_lists() {
+        let ids = |args: &[&str], id: &str| {
+            uu_app()
+                .try_get_matches_from(["pgrep"].iter().chain(args))
+                .unwrap()
+                .get_many::<u32>(id)
+                .unwrap()
+                .copied()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(&["-u", "self,root"], "euid"), [geteuid(), 0]);
+        assert_eq!(ids(&["-U", "0,self"], "uid"), [0, getuid()]);
+        assert_eq!(ids(&["-G", "self,0"], "group"), [getegid(), 0]);
+        assert_eq!(ids(&["--supgroup", "0,self"], "supgroup"), [0, getegid()]);
+    }
+
+    #[test]
+    fn test_user_names() {
+        let mut names = UserNames::default();
+
+        assert_eq!(names.get(0), "root");
+        // Nobody has this uid, so the number stands in for the name.
+        assert_eq!(names.get(4_000_000_000), "4000000000");
+        assert_eq!(names.get(4_000_000_000), "4000000000");
+        assert_eq!(names.0.len(), 2);
+    }
+}
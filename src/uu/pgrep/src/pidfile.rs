@@ -0,0 +1,276 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// Pidfile utils shared by pgrep, pkill and pidwait
+
+use crate::EXIT_FATAL;
+use std::{fs::File, io::Read};
+use uucore::error::{strip_errno, UResult, USimpleError};
+
+/// Read the pid stored in `path`.
+///
+/// If `check_lock` is set the pidfile must be locked by some process, with either
+/// `flock(2)` or an `fcntl(2)` lock, like a running daemon would do.
+///
+/// Zero and negative pids are returned as they are, pgrep and pkill take them as
+/// selecting no process.
+pub fn read_pidfile(path: &str, check_lock: bool) -> UResult<i64> {
+    let mut file = File::open(path).map_err(|e| {
+        USimpleError::new(
+            EXIT_FATAL,
+            format!("Failed to open pidfile '{path}': {}", strip_errno(&e)),
+        )
+    })?;
+
+    if check_lock && !is_locked(&file).unwrap_or(false) {
+        return Err(USimpleError::new(
+            EXIT_FATAL,
+            format!("Pidfile {path} is not locked"),
+        ));
+    }
+
+    let mut content = String::new();
+    file.read_to_string(&mut content).map_err(|e| {
+        USimpleError::new(
+            EXIT_FATAL,
+            format!("Failed to read pidfile '{path}': {}", strip_errno(&e)),
+        )
+    })?;
+
+    parse_pidfile_content(&content)
+        .ok_or_else(|| USimpleError::new(EXIT_FATAL, format!("Pidfile {path} not valid")))
+}
+
+/// Parse the pid from the content of a pidfile.
+///
+/// Like procps, only the first line is considered and anything after the number
+/// (e.g. a trailing newline or comment) is ignored.
+pub fn parse_pidfile_content(content: &str) -> Option<i64> {
+    let line = content.lines().next()?.trim_start();
+
+    let end = line
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || (*i == 0 && (*c == '-' || *c == '+'))))
+        .map_or(line.len(), |(i, _)| i);
+
+    line[..end].parse().ok()
+}
+
+/// Check whether any process holds a lock on `file`.
+///
+/// Both lock flavours are probed without disturbing an existing lock: a shared
+/// non-blocking `flock(2)` fails if someone holds an exclusive lock, and `F_GETLK`
+/// reports a conflicting `fcntl(2)` lock without taking one.
+#[cfg(unix)]
+pub fn is_locked(file: &File) -> std::io::Result<bool> {
+    use std::io::Error;
+    use std::os::fd::AsRawFd;
+
+    let fd = file.as_raw_fd();
+
+    // SAFETY: `fd` is a valid open file descriptor for the lifetime of `file`.
+    if unsafe { libc::flock(fd, libc::LOCK_SH | libc::LOCK_NB) } == 0 {
+        // The probe succeeded, so nobody held an exclusive lock. Release it right away.
+        // SAFETY: `fd` is still valid, see above.
+        if unsafe { libc::flock(fd, libc::LOCK_UN) } == -1 {
+            return Err(Error::last_os_error());
+        }
+    } else {
+        let err = Error::last_os_error();
+        if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+            return Ok(true);
+        }
+        return Err(err);
+    }
+
+    // SAFETY: `flock` is plain old data, all zeroes is a valid value.
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as _;
+    lock.l_whence = libc::SEEK_SET as _;
+
+    // SAFETY: `lock` is a valid, initialized `flock` struct.
+    if unsafe { libc::fcntl(fd, libc::F_GETLK, &mut lock) } == -1 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(lock.l_type != libc::F_UNLCK as _)
+}
+
+#[cfg(not(unix))]
+pub fn is_locked(_file: &File) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pidfile_content() {
+        assert_eq!(parse_pidfile_content("1234\n"), Some(1234));
+        assert_eq!(parse_pidfile_content("  42 daemon\n"), Some(42));
+        assert_eq!(parse_pidfile_content("7\n8\n"), Some(7));
+        assert_eq!(parse_pidfile_content("-5"), Some(-5));
+        assert_eq!(parse_pidfile_content(""), None);
+        assert_eq!(parse_pidfile_content("abc"), None);
+        assert_eq!(parse_pidfile_content("\n12"), None);
+    }
+
+    fn error_message(path: &str) -> String {
+        read_pidfile(path, false).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_read_pidfile_errors() {
+        let dir = std::env::temp_dir().join(format!("pgrep-{}-errors", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let missing = dir.join("missing.pid");
+        let missing = missing.to_str().unwrap();
+        assert_eq!(
+            error_message(missing),
+            format!("Failed to open pidfile '{missing}': No such file or directory")
+        );
+
+        // Opening a directory works, reading from it doesn't.
+        let directory = dir.to_str().unwrap();
+        assert_eq!(
+            error_message(directory),
+            format!("Failed to read pidfile '{directory}': Is a directory")
+        );
+
+        let invalid = dir.join("invalid.pid");
+        std::fs::write(&invalid, "pid\n").unwrap();
+        let invalid = invalid.to_str().unwrap();
+        assert_eq!(
+            error_message(invalid),
+            format!("Pidfile {invalid} not valid")
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_read_pidfile_unreadable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Permissions don't apply to root.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+
+        let path = std::env::temp_dir().join(format!("pgrep-{}-unreadable", std::process::id()));
+        std::fs::write(&path, "1\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        let name = path.to_str().unwrap();
+        assert_eq!(
+            error_message(name),
+            format!("Failed to open pidfile '{name}': Permission denied")
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    mod locks {
+        use super::super::is_locked;
+        use std::ffi::CString;
+        use std::fs::File;
+        use std::path::{Path, PathBuf};
+
+        enum Lock {
+            Flock,
+            Fcntl,
+        }
+
+        fn temp_file(name: &str) -> PathBuf {
+            let path = std::env::temp_dir().join(format!("pgrep-{}-{}", std::process::id(), name));
+            std::fs::write(&path, "1\n").unwrap();
+            path
+        }
+
+        /// Lock `path` from a forked child process and return its pid once the lock is held.
+        fn lock_in_child(path: &Path, how: Lock) -> libc::pid_t {
+            let c_path = CString::new(path.to_str().unwrap()).unwrap();
+            let mut fds = [0; 2];
+
+            unsafe {
+                assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+
+                let pid = libc::fork();
+                assert!(pid >= 0);
+
+                if pid == 0 {
+                    // Only async-signal-safe calls from here on.
+                    let fd = libc::open(c_path.as_ptr(), libc::O_RDWR);
+                    match how {
+                        Lock::Flock => {
+                            libc::flock(fd, libc::LOCK_EX);
+                        }
+                        Lock::Fcntl => {
+                            let mut lock: libc::flock = std::mem::zeroed();
+                            lock.l_type = libc::F_WRLCK as _;
+                            lock.l_whence = libc::SEEK_SET as _;
+                            libc::fcntl(fd, libc::F_SETLKW, &lock);
+                        }
+                    }
+                    libc::write(fds[1], [1u8].as_ptr().cast(), 1);
+                    loop {
+                        libc::pause();
+                    }
+                }
+
+                let mut buf = [0u8];
+                libc::read(fds[0], buf.as_mut_ptr().cast(), 1);
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+                pid
+            }
+        }
+
+        fn stop_child(pid: libc::pid_t) {
+            unsafe {
+                libc::kill(pid, libc::SIGKILL);
+                libc::waitpid(pid, std::ptr::null_mut(), 0);
+            }
+        }
+
+        #[test]
+        fn test_is_locked_unlocked() {
+            let path = temp_file("unlocked");
+            assert!(!is_locked(&File::open(&path).unwrap()).unwrap());
+            std::fs::remove_file(path).unwrap();
+        }
+
+        #[test]
+        fn test_is_locked_flock() {
+            let path = temp_file("flock");
+            let child = lock_in_child(&path, Lock::Flock);
+
+            assert!(is_locked(&File::open(&path).unwrap()).unwrap());
+            // Probing must not have taken over or released the lock.
+            assert!(is_locked(&File::open(&path).unwrap()).unwrap());
+
+            stop_child(child);
+            assert!(!is_locked(&File::open(&path).unwrap()).unwrap());
+            std::fs::remove_file(path).unwrap();
+        }
+
+        #[test]
+        fn test_is_locked_fcntl() {
+            let path = temp_file("fcntl");
+            let child = lock_in_child(&path, Lock::Fcntl);
+
+            assert!(is_locked(&File::open(&path).unwrap()).unwrap());
+            assert!(is_locked(&File::open(&path).unwrap()).unwrap());
+
+            stop_child(child);
+            assert!(!is_locked(&File::open(&path).unwrap()).unwrap());
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -6,9 +6,10 @@
 use std::hash::Hash;
 use std::{
     collections::HashMap,
+    env,
     fmt::{self, Display, Formatter},
     fs, io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 use walkdir::{DirEntry, WalkDir};
@@ -77,7 +78,7 @@ impl TryFrom<PathBuf> for Teletype {
         let f = |prefix: &str| {
             value
                 .iter()
-                .last()?
+                .next_back()?
                 .to_str()?
                 .strip_prefix(prefix)?
                 .parse::<u64>()
@@ -96,6 +97,42 @@ impl TryFrom<PathBuf> for Teletype {
     }
 }
 
+/// Major device numbers of the terminals [Teletype] knows, see `devices.txt` in the
+/// kernel docs.
+const TTY_MAJOR: u64 = 4;
+const PTS_MAJOR: u64 = 136;
+/// Serial ports follow the virtual consoles on [TTY_MAJOR].
+const TTYS_MINOR_OFFSET: u64 = 64;
+
+impl Teletype {
+    /// The device number of the terminal, encoded like the `tty_nr` field of
+    /// `/proc/<pid>/stat`. [Teletype::Unknown] stands for no terminal at all, i.e. 0.
+    pub fn device_number(&self) -> u64 {
+        let (major, minor) = match self {
+            Self::Tty(id) => (TTY_MAJOR, *id),
+            Self::TtyS(id) => (TTY_MAJOR, TTYS_MINOR_OFFSET + id),
+            Self::Pts(id) => (PTS_MAJOR, *id),
+            Self::Unknown => return 0,
+        };
+        // The kernel's new_encode_dev(): minor bits 0-7, major bits 8-19, the rest of the minor above.
+        (minor & 0xff) | (major << 8) | ((minor & !0xff) << 12)
+    }
+
+    /// The terminal with the given `tty_nr` device number, [Teletype::Unknown] for 0
+    /// and devices which are no terminal [Teletype] knows.
+    pub fn from_device_number(number: u64) -> Self {
+        let major = (number >> 8) & 0xfff;
+        let minor = (number & 0xff) | ((number >> 12) & !0xff);
+
+        match (major, minor) {
+            (TTY_MAJOR, _) if minor < TTYS_MINOR_OFFSET => Self::Tty(minor),
+            (TTY_MAJOR, _) => Self::TtyS(minor - TTYS_MINOR_OFFSET),
+            (PTS_MAJOR, _) => Self::Pts(minor),
+            _ => Self::Unknown,
+        }
+    }
+}
+
 /// State or process
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RunState {
@@ -109,6 +146,19 @@ pub enum RunState {
     Zombie,
     ///`T`, traced or stopped
     Stopped,
+    ///`t`, stopped by a debugger during tracing
+    TracingStop,
+    ///`X` (or `x` before Linux 3.13), dead
+    Dead,
+    ///`I`, idle kernel thread
+    Idle,
+    ///`P`, parked kernel thread
+    Parked,
+    ///`W`, paging (before Linux 2.6)
+    Paging,
+    /// Any other single letter a kernel may report, kept as is so that the
+    /// process is never dropped because of an unknown state.
+    Other(char),
 }
 
 impl Display for RunState {
@@ -119,6 +169,12 @@ impl Display for RunState {
             Self::UninterruptibleWait => write!(f, "D"),
             Self::Zombie => write!(f, "Z"),
             Self::Stopped => write!(f, "T"),
+            Self::TracingStop => write!(f, "t"),
+            Self::Dead => write!(f, "X"),
+            Self::Idle => write!(f, "I"),
+            Self::Parked => write!(f, "P"),
+            Self::Paging => write!(f, "W"),
+            Self::Other(state) => write!(f, "{}", state),
         }
     }
 }
@@ -133,6 +189,12 @@ impl TryFrom<char> for RunState {
             'D' => Ok(Self::UninterruptibleWait),
             'Z' => Ok(Self::Zombie),
             'T' => Ok(Self::Stopped),
+            't' => Ok(Self::TracingStop),
+            'X' | 'x' => Ok(Self::Dead),
+            'I' => Ok(Self::Idle),
+            'P' => Ok(Self::Parked),
+            'W' => Ok(Self::Paging),
+            _ if value.is_ascii_alphabetic() => Ok(Self::Other(value)),
             _ => Err(io::ErrorKind::InvalidInput.into()),
         }
     }
@@ -177,6 +239,9 @@ pub struct ProcessInformation {
     pub pid: usize,
     pub cmdline: String,
 
+    /// The `/proc/<pid>` directory the information was read from
+    path: PathBuf,
+
     inner_status: String,
     inner_stat: String,
 
@@ -186,6 +251,7 @@ pub struct ProcessInformation {
     cached_stat: Option<Rc<Vec<String>>>,
 
     cached_start_time: Option<u64>,
+    cached_name: Option<String>,
 }
 
 impl ProcessInformation {
@@ -204,7 +270,12 @@ impl ProcessInformation {
         };
 
         let value = if value.is_symlink() {
-            fs::read_link(value)?
+            // `/proc/self` links to a path relative to the proc root.
+            let target = fs::read_link(&value)?;
+            match value.parent() {
+                Some(parent) => parent.join(target),
+                None => target,
+            }
         } else {
             value
         };
@@ -212,23 +283,26 @@ impl ProcessInformation {
         let pid = {
             value
                 .iter()
-                .last()
+                .next_back()
                 .ok_or(io::ErrorKind::Other)?
                 .to_str()
                 .ok_or(io::ErrorKind::InvalidData)?
                 .parse::<usize>()
                 .map_err(|_| io::ErrorKind::InvalidData)?
         };
-        let cmdline = fs::read_to_string(dir_append(value.clone(), "cmdline".into()))?
-            .replace('\0', " ")
-            .trim_end()
-            .into();
+        let cmdline = parse_cmdline(&fs::read(dir_append(value.clone(), "cmdline".into()))?);
+        // Both files hold the raw process name, which doesn't need to be valid UTF-8.
+        let read_lossy = |name: &str| {
+            fs::read(dir_append(value.clone(), name.into()))
+                .map(|raw| String::from_utf8_lossy(&raw).into_owned())
+        };
 
         Ok(Self {
             pid,
             cmdline,
-            inner_status: fs::read_to_string(dir_append(value.clone(), "status".into()))?,
-            inner_stat: fs::read_to_string(dir_append(value, "stat".into()))?,
+            inner_status: read_lossy("status")?,
+            inner_stat: read_lossy("stat")?,
+            path: value,
             ..Default::default()
         })
     }
@@ -259,6 +333,30 @@ impl ProcessInformation {
         Rc::clone(&result)
     }
 
+    /// The process name from `/proc/<pid>/comm`, or from the `Name` line of
+    /// [ProcessInformation::status] if that can't be read.
+    ///
+    /// Names set with `prctl(PR_SET_NAME)` may hold any byte. Like the kernel does for the
+    /// status file, newlines and backslashes are escaped and invalid UTF-8 is replaced, so
+    /// the name always fits on one line.
+    pub fn name(&mut self) -> Result<String, io::Error> {
+        if let Some(name) = &self.cached_name {
+            return Ok(name.clone());
+        }
+
+        let name = match fs::read(self.path.join("comm")) {
+            Ok(raw) => parse_comm(&raw),
+            Err(_) => self
+                .status()
+                .get("Name")
+                .cloned()
+                .ok_or(io::ErrorKind::InvalidData)?,
+        };
+
+        self.cached_name = Some(name.clone());
+        Ok(name)
+    }
+
     /// Collect information from `/proc/<pid>/stat` file
     pub fn stat(&mut self) -> Rc<Vec<String>> {
         if let Some(c) = &self.cached_stat {
@@ -294,6 +392,104 @@ impl ProcessInformation {
         Ok(time)
     }
 
+    /// Fetch the parent pid from [ProcessInformation::cached_stat], 0 for processes
+    /// without a parent like pid 1 and kthreadd.
+    pub fn ppid(&mut self) -> Result<usize, io::Error> {
+        self.stat()
+            .get(3)
+            .ok_or(io::ErrorKind::InvalidData)?
+            .parse()
+            .map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
+    /// Fetch the device number of the controlling terminal from [ProcessInformation::cached_stat],
+    /// 0 if there is none. Cheaper than [ProcessInformation::tty] to compare against a
+    /// [Teletype::device_number].
+    pub fn tty_nr(&mut self) -> Result<u64, io::Error> {
+        self.stat()
+            .get(6)
+            .ok_or(io::ErrorKind::InvalidData)?
+            .parse()
+            .map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
+    /// Fetch the process group id from [ProcessInformation::cached_stat].
+    pub fn pgrp(&mut self) -> Result<usize, io::Error> {
+        self.stat_number(4)
+    }
+
+    /// Fetch the process group in the foreground of the controlling terminal from
+    /// [ProcessInformation::cached_stat], -1 if there is no controlling terminal.
+    pub fn tpgid(&mut self) -> Result<i64, io::Error> {
+        self.stat_number(7)
+    }
+
+    /// Fetch the time spent in user mode from [ProcessInformation::cached_stat],
+    /// in clock ticks.
+    pub fn utime(&mut self) -> Result<u64, io::Error> {
+        self.stat_number(13)
+    }
+
+    /// Fetch the time spent in kernel mode from [ProcessInformation::cached_stat],
+    /// in clock ticks.
+    pub fn stime(&mut self) -> Result<u64, io::Error> {
+        self.stat_number(14)
+    }
+
+    /// Total CPU time of the process in clock ticks, see [clock_ticks].
+    pub fn cpu_time(&mut self) -> Result<u64, io::Error> {
+        Ok(self.utime()? + self.stime()?)
+    }
+
+    fn stat_number<T: std::str::FromStr>(&mut self, index: usize) -> Result<T, io::Error> {
+        self.stat()
+            .get(index)
+            .ok_or(io::ErrorKind::InvalidData)?
+            .parse()
+            .map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
+    /// Fetch the real user id from the `Uid` line of [ProcessInformation::status].
+    pub fn uid(&mut self) -> Result<u32, io::Error> {
+        self.status_id("Uid", 0)
+    }
+
+    /// Fetch the effective user id from the `Uid` line of [ProcessInformation::status].
+    pub fn euid(&mut self) -> Result<u32, io::Error> {
+        self.status_id("Uid", 1)
+    }
+
+    /// Fetch the real group id from the `Gid` line of [ProcessInformation::status].
+    pub fn gid(&mut self) -> Result<u32, io::Error> {
+        self.status_id("Gid", 0)
+    }
+
+    /// Fetch the supplementary group ids from the `Groups` line of
+    /// [ProcessInformation::status], empty for processes without any.
+    pub fn supplementary_groups(&mut self) -> Result<Vec<u32>, io::Error> {
+        self.status()
+            .get("Groups")
+            .ok_or(io::ErrorKind::InvalidData)?
+            .split_whitespace()
+            .map(|id| id.parse().map_err(|_| io::ErrorKind::InvalidData.into()))
+            .collect()
+    }
+
+    /// The `Uid` and `Gid` lines hold the real, effective, saved and filesystem ids.
+    fn status_id(&mut self, key: &str, index: usize) -> Result<u32, io::Error> {
+        self.status()
+            .get(key)
+            .and_then(|ids| ids.split_whitespace().nth(index))
+            .ok_or(io::ErrorKind::InvalidData)?
+            .parse()
+            .map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
+    /// Seconds elapsed since the process started, `now` being the result of [boot_time_now].
+    pub fn age(&mut self, now: f64) -> Result<f64, io::Error> {
+        Ok(process_age(now, self.start_time()?, clock_ticks()))
+    }
+
     /// Fetch run state from [ProcessInformation::cached_stat]
     ///
     /// - [The /proc Filesystem: Table 1-4](https://docs.kernel.org/filesystems/proc.html#id10)
@@ -305,6 +501,52 @@ impl ProcessInformation {
         RunState::try_from(self.stat().get(2).unwrap().as_str())
     }
 
+    /// Path of the process in the cgroup v2 hierarchy, e.g. `/system.slice/cron.service`,
+    /// from the `0::` entry of `/proc/<pid>/cgroup`.
+    ///
+    /// Fails with [io::ErrorKind::NotFound] if the process isn't part of a cgroup v2 hierarchy.
+    pub fn cgroup_v2_path(&self) -> Result<String, io::Error> {
+        fs::read_to_string(self.path.join("cgroup"))?
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .map(String::from)
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    /// Number of open file descriptors, i.e. entries of `/proc/<pid>/fd`.
+    ///
+    /// The entries are only counted, not resolved. Reading another user's process
+    /// usually fails with [io::ErrorKind::PermissionDenied].
+    pub fn fd_count(&self) -> Result<usize, io::Error> {
+        Ok(fs::read_dir(self.path.join("fd"))?.count())
+    }
+
+    /// Path of the running executable, the target of the `/proc/<pid>/exe` link.
+    ///
+    /// Fails for kernel threads, and usually with [io::ErrorKind::PermissionDenied] for
+    /// another user's process.
+    pub fn exe(&self) -> Result<PathBuf, io::Error> {
+        fs::read_link(self.path.join("exe"))
+    }
+
+    /// Soft limit of open files from `/proc/<pid>/limits`, `None` if unlimited.
+    pub fn fd_limit(&self) -> Result<Option<u64>, io::Error> {
+        parse_max_open_files(&fs::read_to_string(self.path.join("limits"))?)
+    }
+
+    /// Inode number of the `kind` namespace of the process, one of [NAMESPACES].
+    ///
+    /// Reading another user's namespaces usually fails with
+    /// [io::ErrorKind::PermissionDenied].
+    pub fn namespace(&self, kind: &str) -> Result<u64, io::Error> {
+        parse_namespace_link(&fs::read_link(self.path.join("ns").join(kind))?)
+    }
+
+    /// Open file descriptors and their soft limit, see [Self::fd_count] and [Self::fd_limit].
+    pub fn fd_usage(&self) -> Result<(usize, Option<u64>), io::Error> {
+        Ok((self.fd_count()?, self.fd_limit()?))
+    }
+
     /// This function will scan the `/proc/<pid>/fd` directory
     ///
     /// If the process does not belong to any terminal and mismatched permission,
@@ -312,7 +554,7 @@ impl ProcessInformation {
     ///
     /// Otherwise [TerminalType::Unknown] does not appear in the result.
     pub fn tty(&self) -> Teletype {
-        let path = PathBuf::from(format!("/proc/{}/fd", self.pid));
+        let path = self.path.join("fd");
 
         let Ok(result) = fs::read_dir(path) else {
             return Teletype::Unknown;
@@ -329,6 +571,76 @@ impl ProcessInformation {
         Teletype::Unknown
     }
 }
+
+/// Namespaces a process can be matched on, see `namespaces(7)`.
+pub const NAMESPACES: [&str; 6] = ["ipc", "mnt", "net", "pid", "user", "uts"];
+
+/// The namespaces of a process, as the inode numbers of its `/proc/<pid>/ns/*` links.
+///
+/// Namespaces which couldn't be read are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Namespace {
+    inodes: [Option<u64>; NAMESPACES.len()],
+}
+
+impl Namespace {
+    /// Read the namespaces of `pid` from [proc_root].
+    ///
+    /// Fails if none of them could be read, e.g. because the process doesn't exist or
+    /// belongs to another user.
+    pub fn from_pid(pid: usize) -> Result<Self, io::Error> {
+        let process = ProcessInformation::try_new(proc_root().join(pid.to_string()))?;
+
+        let mut error = None;
+        let mut namespace = Self::default();
+        for (inode, kind) in namespace.inodes.iter_mut().zip(NAMESPACES) {
+            match process.namespace(kind) {
+                Ok(ino) => *inode = Some(ino),
+                Err(e) => error = Some(e),
+            }
+        }
+
+        match error {
+            Some(e) if namespace.inodes.iter().all(Option::is_none) => Err(e),
+            _ => Ok(namespace),
+        }
+    }
+
+    /// Read the namespaces of `process`, those which can't be read are left out.
+    pub fn from_process(process: &ProcessInformation) -> Self {
+        let mut namespace = Self::default();
+        for (inode, kind) in namespace.inodes.iter_mut().zip(NAMESPACES) {
+            *inode = process.namespace(kind).ok();
+        }
+        namespace
+    }
+
+    pub fn get(&self, kind: &str) -> Option<u64> {
+        let index = NAMESPACES.iter().position(|it| *it == kind)?;
+        self.inodes[index]
+    }
+
+    /// Whether `other` shares all of the `kinds` namespaces with `self`.
+    ///
+    /// A namespace which couldn't be read on either side never matches.
+    pub fn matches(&self, other: &Self, kinds: &[&str]) -> bool {
+        kinds.iter().all(|kind| {
+            self.get(kind)
+                .is_some_and(|ino| other.get(kind) == Some(ino))
+        })
+    }
+}
+
+/// Parse the inode number from a namespace link target like `net:[4026531840]`.
+fn parse_namespace_link(target: &Path) -> Result<u64, io::Error> {
+    target
+        .to_str()
+        .and_then(|target| target.split_once(":["))
+        .and_then(|(_, inode)| inode.strip_suffix(']'))
+        .and_then(|inode| inode.parse().ok())
+        .ok_or_else(|| io::ErrorKind::InvalidData.into())
+}
+
 impl TryFrom<DirEntry> for ProcessInformation {
     type Error = io::Error;
 
@@ -367,17 +679,160 @@ fn stat_split(stat: &str) -> Vec<String> {
     }
 }
 
+/// Parsing the soft limit of the `Max open files` line of `/proc/<pid>/limits`.
+fn parse_max_open_files(limits: &str) -> Result<Option<u64>, io::Error> {
+    let soft_limit = limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .ok_or(io::ErrorKind::InvalidData)?;
+
+    match soft_limit {
+        "unlimited" => Ok(None),
+        limit => limit
+            .parse()
+            .map(Some)
+            .map_err(|_| io::ErrorKind::InvalidData.into()),
+    }
+}
+
+/// Parsing `/proc/<pid>/cmdline` file.
+///
+/// Arguments are separated by NUL bytes. Only the trailing NUL padding is stripped,
+/// so that titles rewritten with `setproctitle` (e.g. `"postgres: walwriter   "`)
+/// keep their spaces, like procps does.
+fn parse_cmdline(raw: &[u8]) -> String {
+    let end = raw.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+
+    String::from_utf8_lossy(&raw[..end]).replace('\0', " ")
+}
+
+/// Parsing `/proc/<pid>/comm` file, the name followed by a newline.
+fn parse_comm(raw: &[u8]) -> String {
+    let raw = raw.strip_suffix(b"\n").unwrap_or(raw);
+
+    String::from_utf8_lossy(raw)
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+}
+
+/// Seconds since boot, including the time the system was suspended.
+///
+/// `starttime` in `/proc/<pid>/stat` is measured against the same clock, unlike the wall
+/// clock which may jump.
+#[cfg(target_os = "linux")]
+pub fn boot_time_now() -> Result<f64, io::Error> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+
+    // SAFETY: `ts` is a valid timespec to write into.
+    if unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut ts) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(ts.tv_sec as f64 + ts.tv_nsec as f64 / 1e9)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn boot_time_now() -> Result<f64, io::Error> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Number of clock ticks per second, the unit of times in `/proc/<pid>/stat`.
+#[cfg(unix)]
+pub fn clock_ticks() -> u64 {
+    // SAFETY: sysconf has no preconditions.
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn clock_ticks() -> u64 {
+    100
+}
+
+/// Age in seconds of a process started `start_ticks` after boot.
+pub fn process_age(now: f64, start_ticks: u64, ticks_per_second: u64) -> f64 {
+    (now - start_ticks as f64 / ticks_per_second.max(1) as f64).max(0.0)
+}
+
+/// Boot time in seconds since the epoch, from the `btime` line of `<root>/stat`.
+pub fn boot_time_in(root: &Path) -> Result<u64, io::Error> {
+    let content = fs::read_to_string(root.join("stat"))?;
+    parse_btime(&content).ok_or_else(|| io::ErrorKind::InvalidData.into())
+}
+
+fn parse_btime(content: &str) -> Option<u64> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Seconds since the epoch at which a process started `start_ticks` after boot.
+pub fn process_start(boot_time: u64, start_ticks: u64, ticks_per_second: u64) -> u64 {
+    boot_time + start_ticks / ticks_per_second.max(1)
+}
+
+/// Environment variable overriding the location of the proc filesystem.
+pub const PROC_ROOT_ENV: &str = "PROCPS_PROC";
+
+/// Root of the proc filesystem: `$PROCPS_PROC` if set, `/proc` otherwise.
+///
+/// Overriding it allows inspecting a bind-mounted proc tree, or a fixture in tests.
+pub fn proc_root() -> PathBuf {
+    env::var_os(PROC_ROOT_ENV)
+        .filter(|root| !root.is_empty())
+        .map_or_else(|| PathBuf::from("/proc"), PathBuf::from)
+}
+
 /// Iterating pid in current system
 pub fn walk_process() -> impl Iterator<Item = ProcessInformation> {
-    WalkDir::new("/proc/")
+    walk_process_in(&proc_root())
+}
+
+/// Iterating pid found in the proc filesystem mounted at `root`
+pub fn walk_process_in(root: &Path) -> impl Iterator<Item = ProcessInformation> {
+    WalkDir::new(root)
         .max_depth(1)
         .follow_links(false)
         .into_iter()
         .flatten()
-        .filter(|it| it.path().is_dir())
+        // Not `path().is_dir()`, which follows the `self` and `thread-self` symlinks.
+        .filter(|it| it.file_type().is_dir())
         .flat_map(ProcessInformation::try_from)
 }
 
+/// Iterating threads found in the proc filesystem mounted at `root`
+///
+/// Every thread is read from its own `/proc/<pid>/task/<tid>` directory, so `pid` holds the
+/// thread id and the status reports the thread's name rather than the one of its process.
+pub fn walk_threads_in(root: &Path) -> impl Iterator<Item = ProcessInformation> {
+    // Links like `self` and `thread-self` are skipped, they would list the threads of the
+    // current process twice.
+    WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+        .filter(|it| it.file_type().is_dir())
+        .flat_map(|process| {
+            WalkDir::new(process.path().join("task"))
+                .min_depth(1)
+                .max_depth(1)
+                .follow_links(false)
+                .into_iter()
+                .flatten()
+                .filter(|it| it.path().is_dir())
+                .flat_map(ProcessInformation::try_from)
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -395,10 +850,33 @@ mod tests {
         assert_eq!(RunState::try_from("T").unwrap(), RunState::Stopped);
         assert_eq!(RunState::try_from("Z").unwrap(), RunState::Zombie);
 
-        assert!(RunState::try_from("G").is_err());
+        assert_eq!(RunState::try_from("t").unwrap(), RunState::TracingStop);
+        assert_eq!(RunState::try_from("X").unwrap(), RunState::Dead);
+        assert_eq!(RunState::try_from("x").unwrap(), RunState::Dead);
+        assert_eq!(RunState::try_from("I").unwrap(), RunState::Idle);
+        assert_eq!(RunState::try_from("P").unwrap(), RunState::Parked);
+        assert_eq!(RunState::try_from("W").unwrap(), RunState::Paging);
+        assert_eq!(RunState::try_from("G").unwrap(), RunState::Other('G'));
+        assert_eq!(RunState::Other('G').to_string(), "G");
+
+        assert!(RunState::try_from("?").is_err());
+        assert!(RunState::try_from("").is_err());
         assert!(RunState::try_from("Rg").is_err());
     }
 
+    #[test]
+    fn test_run_state_from_stat() {
+        let mut parked = ProcessInformation {
+            inner_stat: "28 (cpuhp/2) P 2 0 0 0 -1 69238848 0 0 0 0 0 0 0 0 20 0 1 0 4 0 0".into(),
+            ..Default::default()
+        };
+
+        let state = parked.run_state().unwrap();
+        assert_eq!(state, RunState::Parked);
+        // What `pgrep -r P` compares against
+        assert!("P".contains(&state.to_string()));
+    }
+
     #[cfg(target_os = "linux")]
     fn current_pid() -> usize {
         // Direct read link of /proc/self.
@@ -421,6 +899,91 @@ mod tests {
         assert!(find.is_some());
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_walk_threads() {
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("walk-threads".into())
+            .spawn(move || {
+                ready_tx.send(()).unwrap();
+                rx.recv()
+            })
+            .unwrap();
+        // The thread names itself once running.
+        ready_rx.recv().unwrap();
+
+        let mut found = walk_threads_in(Path::new("/proc"))
+            .filter(|it| it.proc_stat().contains("(walk-threads)"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].status().get("Name").unwrap(), "walk-threads");
+        assert_ne!(found[0].pid, current_pid());
+        // The main thread is listed too, with the process id as thread id.
+        assert!(walk_threads_in(Path::new("/proc")).any(|it| it.pid == current_pid()));
+
+        tx.send(()).unwrap();
+        thread.join().unwrap().unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_cgroup_v2_path() {
+        let pid_entry = ProcessInformation::try_new("/proc/self".into()).unwrap();
+
+        // Systems with only a legacy cgroup v1 hierarchy have no `0::` entry.
+        match pid_entry.cgroup_v2_path() {
+            Ok(path) => assert!(path.starts_with('/')),
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_ids() {
+        let mut pid_entry = ProcessInformation::try_new("/proc/self".into()).unwrap();
+
+        assert_eq!(pid_entry.uid().unwrap(), uucore::process::getuid());
+        assert_eq!(pid_entry.euid().unwrap(), uucore::process::geteuid());
+        assert_eq!(pid_entry.gid().unwrap(), uucore::process::getgid());
+    }
+
+    #[test]
+    fn test_supplementary_groups() {
+        let groups = |status: &str| {
+            ProcessInformation {
+                inner_status: status.into(),
+                ..Default::default()
+            }
+            .supplementary_groups()
+            .ok()
+        };
+
+        assert_eq!(
+            groups("Gid:\t100\t100\t100\t100\nGroups:\t4 27 1000 \n"),
+            Some(vec![4, 27, 1000])
+        );
+        assert_eq!(groups("Groups:\t\n"), Some(vec![]));
+        assert_eq!(groups("Groups:\t4 sudo\n"), None);
+        assert_eq!(groups("Gid:\t100\t100\t100\t100\n"), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_stat_fields() {
+        let mut pid_entry = ProcessInformation::try_new("/proc/self".into()).unwrap();
+        let stat = pid_entry.stat();
+
+        assert_eq!(pid_entry.pgrp().unwrap().to_string(), stat[4]);
+        assert_eq!(pid_entry.tpgid().unwrap().to_string(), stat[7]);
+        assert_eq!(
+            pid_entry.cpu_time().unwrap(),
+            stat[13].parse::<u64>().unwrap() + stat[14].parse::<u64>().unwrap()
+        );
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_pid_entry() {
@@ -446,6 +1009,220 @@ mod tests {
         assert!(result.contains(&pid_entry.tty()));
     }
 
+    #[test]
+    fn test_parse_namespace_link() {
+        assert_eq!(
+            parse_namespace_link(Path::new("net:[4026531840]")).unwrap(),
+            4026531840
+        );
+        assert!(parse_namespace_link(Path::new("net:[]")).is_err());
+        assert!(parse_namespace_link(Path::new("4026531840")).is_err());
+    }
+
+    #[test]
+    fn test_namespace_matches() {
+        let namespace = |inodes| Namespace { inodes };
+        let reference = namespace([Some(1), Some(2), Some(3), Some(4), Some(5), None]);
+        let other = namespace([Some(1), Some(2), Some(9), Some(4), Some(5), None]);
+
+        assert_eq!(reference.get("net"), Some(3));
+        assert_eq!(reference.get("uts"), None);
+        assert_eq!(reference.get("time"), None);
+
+        assert!(reference.matches(&other, &["ipc", "pid"]));
+        assert!(!reference.matches(&other, &["ipc", "net"]));
+        // Unreadable namespaces never match, even if both sides are unreadable.
+        assert!(!reference.matches(&other, &["uts"]));
+        assert!(reference.matches(&other, &[]));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_namespace_from_pid() {
+        let own = Namespace::from_pid(current_pid()).unwrap();
+        let process = ProcessInformation::try_new("/proc/self".into()).unwrap();
+
+        assert_eq!(own, Namespace::from_process(&process));
+        assert!(own.matches(&own, &NAMESPACES));
+        assert!(Namespace::from_pid(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_parse_comm() {
+        assert_eq!(parse_comm(b"sleep\n"), "sleep");
+        assert_eq!(parse_comm(b"sleep"), "sleep");
+        assert_eq!(parse_comm(b"\n"), "");
+        // Escaped like the `Name` line of the status file.
+        assert_eq!(parse_comm(b"two\nlines\n"), "two\\nlines");
+        assert_eq!(parse_comm(b"back\\slash\n"), "back\\\\slash");
+        assert_eq!(parse_comm(b"bad\xffbyte\n"), "bad\u{fffd}byte");
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(parse_cmdline(b"sleep\x0010\x00"), "sleep 10");
+        assert_eq!(parse_cmdline(b"sleep\x00\x00"), "sleep");
+        assert_eq!(parse_cmdline(b""), "");
+        assert_eq!(parse_cmdline(b"\x00\x00"), "");
+
+        // setproctitle: a single argument padded with spaces and NULs
+        let title = parse_cmdline(b"postgres: walwriter   \x00\x00\x00\x00");
+        assert_eq!(title, "postgres: walwriter   ");
+        assert!(regex::Regex::new("walwriter +$").unwrap().is_match(&title));
+    }
+
+    #[test]
+    fn test_teletype_device_number() {
+        // As listed by `ls -l /dev`.
+        assert_eq!(Teletype::Tty(1).device_number(), 4 << 8 | 1);
+        assert_eq!(Teletype::TtyS(0).device_number(), 4 << 8 | 64);
+        assert_eq!(Teletype::Pts(3).device_number(), 136 << 8 | 3);
+        // Minors above 255 continue above the major.
+        assert_eq!(
+            Teletype::Pts(300).device_number(),
+            0x100 << 12 | 136 << 8 | 44
+        );
+        assert_eq!(Teletype::Unknown.device_number(), 0);
+    }
+
+    #[test]
+    fn test_teletype_from_device_number() {
+        let ttys = [
+            Teletype::Tty(0),
+            Teletype::Tty(63),
+            Teletype::TtyS(0),
+            Teletype::TtyS(191),
+            Teletype::Pts(0),
+            Teletype::Pts(255),
+            Teletype::Pts(256),
+            Teletype::Pts(4095),
+            Teletype::Unknown,
+        ];
+        for tty in ttys {
+            assert_eq!(Teletype::from_device_number(tty.device_number()), tty);
+        }
+        // /dev/null and /dev/console aren't terminals a process can be matched on.
+        assert_eq!(Teletype::from_device_number(1 << 8 | 3), Teletype::Unknown);
+        assert_eq!(Teletype::from_device_number(5 << 8 | 1), Teletype::Unknown);
+    }
+
+    #[test]
+    fn test_terminal_fast_path() {
+        // tty_nr of /proc/<pid>/stat next to what the fd links of the process point at.
+        let processes = [
+            (0, "/dev/null"),
+            (0, "pipe:[4242]"),
+            (136 << 8, "/dev/pts/0"),
+            (0x100 << 12 | 136 << 8 | 44, "/dev/pts/300"),
+            (4 << 8 | 1, "/dev/tty1"),
+            (4 << 8 | 65, "/dev/ttyS1"),
+            (5 << 8 | 1, "/dev/console"),
+            (204 << 8 | 64, "/dev/ttyAMA0"),
+        ];
+        let requests: [&[&str]; 6] = [
+            &["pts/0"],
+            &["pts/300", "tty1"],
+            &["ttyS1"],
+            &["tty1", "ttyS1", "pts/0"],
+            &["pts/7", "tty3"],
+            &["?"],
+        ];
+
+        for request in requests {
+            let ttys: HashSet<_> = request
+                .iter()
+                .flat_map(|tty| Teletype::try_from(tty.to_string()))
+                .collect();
+            let numbers: HashSet<_> = ttys.iter().map(Teletype::device_number).collect();
+
+            // What ProcessInformation::tty() makes of the fd links.
+            let mut old: Vec<u64> = processes
+                .iter()
+                .filter(|(_, link)| {
+                    ttys.contains(&Teletype::try_from(*link).unwrap_or(Teletype::Unknown))
+                })
+                .map(|(nr, _)| *nr)
+                .collect();
+            let new: Vec<u64> = processes
+                .iter()
+                .filter(|(nr, _)| numbers.contains(nr))
+                .map(|(nr, _)| *nr)
+                .collect();
+
+            // Unlike the fd links, tty_nr tells terminals Teletype can't name apart from no
+            // terminal at all, so '?' only selects the latter, like procps.
+            if ttys.contains(&Teletype::Unknown) {
+                old.retain(|nr| *nr == 0);
+            }
+            assert_eq!(new, old, "{request:?}");
+        }
+    }
+
+    #[test]
+    fn test_process_age() {
+        assert_eq!(process_age(100.0, 5000, 100), 50.0);
+        assert_eq!(process_age(100.25, 10000, 100), 0.25);
+        assert_eq!(process_age(100.0, 0, 250), 100.0);
+        // Started "after" now, e.g. clocks read in a different order: never negative.
+        assert_eq!(process_age(100.0, 20000, 100), 0.0);
+        // A long suspend shows up in the boot time clock and therefore in the age.
+        assert_eq!(process_age(100.0 + 3600.0, 5000, 100), 3650.0);
+    }
+
+    #[test]
+    fn test_process_start() {
+        assert_eq!(
+            parse_btime("cpu  1 2 3\nbtime 1700000000\nprocesses 5\n"),
+            Some(1700000000)
+        );
+        assert_eq!(parse_btime("cpu  1 2 3\n"), None);
+        assert_eq!(parse_btime("btime soon\n"), None);
+
+        assert_eq!(process_start(1700000000, 1234, 100), 1700000012);
+        assert_eq!(process_start(1700000000, 0, 0), 1700000000);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_boot_time_now() {
+        let uptime = fs::read_to_string("/proc/uptime").unwrap();
+        let uptime: f64 = uptime.split_whitespace().next().unwrap().parse().unwrap();
+        let now = boot_time_now().unwrap();
+
+        assert!((now - uptime).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_parse_max_open_files() {
+        let limits = include_str!("../../../../tests/fixtures/pgrep/limits");
+        assert_eq!(parse_max_open_files(limits).unwrap(), Some(20000));
+
+        let limits = include_str!("../../../../tests/fixtures/pgrep/limits_unlimited");
+        assert_eq!(parse_max_open_files(limits).unwrap(), None);
+
+        assert!(parse_max_open_files("").is_err());
+        assert!(parse_max_open_files("Max open files  many  many  files").is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_fd_usage() {
+        let process = ProcessInformation::try_new(
+            PathBuf::from_str(&format!("/proc/{}", current_pid())).unwrap(),
+        )
+        .unwrap();
+
+        let (count, limit) = process.fd_usage().unwrap();
+        assert!(count >= 3);
+        if let Some(limit) = limit {
+            assert!(limit >= count as u64);
+        }
+
+        let file = fs::File::open("/proc/self/limits").unwrap();
+        assert!(process.fd_count().unwrap() > count);
+        drop(file);
+    }
+
     #[test]
     fn test_stat_split() {
         let case = "32 (idle_inject/3) S 2 0 0 0 -1 69238848 0 0 0 0 0 0 0 0 -51 0 1 0 34 0 0 18446744073709551615 0 0 0 0 0 0 0 2147483647 0 0 0 0 17 3 50 1 0 0 0 0 0 0 0 0 0 0 0";
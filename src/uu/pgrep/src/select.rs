@@ -0,0 +1,695 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// Process selection by pattern and filters, shared by pgrep, pkill and pidwait
+
+use crate::matcher::Matcher;
+use crate::process::{
+    boot_time_now, proc_root, walk_process_in, walk_threads_in, ProcessInformation, Teletype,
+};
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Tolerance when comparing process ages, the start time has a 1/100s resolution anyway.
+const AGE_EPSILON: f64 = 1e-3;
+
+/// Which processes to select, see [Settings::builder].
+///
+/// A process is selected if it matches the pattern and every filter which is set.
+#[derive(Debug)]
+pub struct Settings {
+    /// The patterns combined into one, empty if there are none
+    pattern: String,
+    matcher: Matcher,
+    full: bool,
+    ignore_case: bool,
+    inverse: bool,
+    newest: bool,
+    oldest: bool,
+    older: Option<f64>,
+    younger: Option<f64>,
+    parent: Option<Vec<u64>>,
+    pidfile: Option<i64>,
+    runstates: Option<String>,
+    /// Device numbers of the terminals, resolved once instead of per process
+    terminal: Option<HashSet<u64>>,
+    threads: bool,
+    cgroups: Option<HashSet<String>>,
+    euids: Option<HashSet<u32>>,
+    uids: Option<HashSet<u32>>,
+    gids: Option<HashSet<u32>>,
+    supgroups: Option<HashSet<u32>>,
+    debug: bool,
+    /// The patterns as given, to spot near misses with `debug`
+    patterns: Vec<String>,
+    proc_root: PathBuf,
+}
+
+impl Settings {
+    /// Start building settings, nothing is filtered until asked for.
+    ///
+    /// Select the processes of user 1000 whose name contains `sleep`:
+    ///
+    /// ```
+    /// use uu_pgrep::select::{find_matching_pids, Settings};
+    ///
+    /// let settings = Settings::builder()
+    ///     .patterns(["sleep"])
+    ///     .uids([1000])
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(settings.has_criteria());
+    ///
+    /// for mut process in find_matching_pids(&settings) {
+    ///     assert_eq!(process.uid().unwrap(), 1000);
+    /// }
+    /// ```
+    ///
+    /// Select the process a pidfile names, which is never the running process itself:
+    ///
+    /// ```
+    /// use uu_pgrep::select::{find_matching_pids, Settings};
+    ///
+    /// let settings = Settings::builder()
+    ///     .pidfile(std::process::id().into())
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(settings.only_pidfile());
+    /// assert!(find_matching_pids(&settings).is_empty());
+    /// ```
+    pub fn builder() -> SettingsBuilder {
+        SettingsBuilder::default()
+    }
+
+    /// Whether a pattern or any filter is set. Selecting without one would match everything,
+    /// which the tools refuse to do.
+    pub fn has_criteria(&self) -> bool {
+        self.pidfile.is_some() || self.has_criteria_besides_pidfile()
+    }
+
+    /// Whether the pidfile is all there is to select by, i.e. it names one specific process.
+    pub fn only_pidfile(&self) -> bool {
+        self.pidfile.is_some() && !self.has_criteria_besides_pidfile()
+    }
+
+    fn has_criteria_besides_pidfile(&self) -> bool {
+        !self.pattern.is_empty()
+            || self.newest
+            || self.oldest
+            || self.runstates.is_some()
+            || self.older.is_some()
+            || self.younger.is_some()
+            || self.parent.is_some()
+            || self.terminal.is_some()
+            || self.cgroups.is_some()
+            || self.euids.is_some()
+            || self.uids.is_some()
+            || self.gids.is_some()
+            || self.supgroups.is_some()
+    }
+
+    /// The proc filesystem processes are read from.
+    pub fn proc_root(&self) -> &Path {
+        &self.proc_root
+    }
+}
+
+/// Builds [Settings], see [Settings::builder].
+#[derive(Debug, Default)]
+pub struct SettingsBuilder {
+    patterns: Vec<String>,
+    exact: bool,
+    full: bool,
+    ignore_case: bool,
+    inverse: bool,
+    newest: bool,
+    oldest: bool,
+    older: Option<f64>,
+    younger: Option<f64>,
+    parent: Option<Vec<u64>>,
+    pidfile: Option<i64>,
+    runstates: Option<String>,
+    terminal: Option<HashSet<u64>>,
+    threads: bool,
+    cgroups: Option<HashSet<String>>,
+    euids: Option<HashSet<u32>>,
+    uids: Option<HashSet<u32>>,
+    gids: Option<HashSet<u32>>,
+    supgroups: Option<HashSet<u32>>,
+    debug: bool,
+    proc_root: Option<PathBuf>,
+}
+
+impl SettingsBuilder {
+    /// Regular expressions, a process matching any of them is selected.
+    pub fn patterns<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.patterns = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Anchor the patterns, so that they have to match the whole name or command line.
+    pub fn exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Match the patterns against the command line instead of the process name.
+    pub fn full(mut self, full: bool) -> Self {
+        self.full = full;
+        self
+    }
+
+    pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Select the processes which don't match instead.
+    pub fn inverse(mut self, inverse: bool) -> Self {
+        self.inverse = inverse;
+        self
+    }
+
+    /// Only keep the most recently started of the matches.
+    pub fn newest(mut self, newest: bool) -> Self {
+        self.newest = newest;
+        self
+    }
+
+    /// Only keep the least recently started of the matches.
+    pub fn oldest(mut self, oldest: bool) -> Self {
+        self.oldest = oldest;
+        self
+    }
+
+    /// Processes running for at least `seconds`.
+    pub fn older(mut self, seconds: f64) -> Self {
+        self.older = Some(seconds);
+        self
+    }
+
+    /// Processes running for less than `seconds`.
+    pub fn younger(mut self, seconds: f64) -> Self {
+        self.younger = Some(seconds);
+        self
+    }
+
+    /// Children of these processes.
+    pub fn parents(mut self, parents: impl IntoIterator<Item = u64>) -> Self {
+        self.parent = Some(parents.into_iter().collect());
+        self
+    }
+
+    /// The pid read from a pidfile. Zero and negative pids select no process.
+    pub fn pidfile(mut self, pid: i64) -> Self {
+        self.pidfile = Some(pid);
+        self
+    }
+
+    /// Processes in any of the states, given by their letters like `DS`.
+    pub fn runstates(mut self, runstates: impl Into<String>) -> Self {
+        self.runstates = Some(runstates.into());
+        self
+    }
+
+    /// Processes with one of these controlling terminals.
+    pub fn terminals(mut self, terminals: impl IntoIterator<Item = Teletype>) -> Self {
+        self.terminal = Some(
+            terminals
+                .into_iter()
+                .map(|tty| tty.device_number())
+                .collect(),
+        );
+        self
+    }
+
+    /// Match every thread on its own, including its name.
+    pub fn threads(mut self, threads: bool) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Processes in one of these cgroup v2 paths.
+    pub fn cgroups<S: Into<String>>(mut self, cgroups: impl IntoIterator<Item = S>) -> Self {
+        self.cgroups = Some(cgroups.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Processes with one of these effective user ids.
+    pub fn euids(mut self, euids: impl IntoIterator<Item = u32>) -> Self {
+        self.euids = Some(euids.into_iter().collect());
+        self
+    }
+
+    /// Processes with one of these real user ids.
+    pub fn uids(mut self, uids: impl IntoIterator<Item = u32>) -> Self {
+        self.uids = Some(uids.into_iter().collect());
+        self
+    }
+
+    /// Processes with one of these real group ids.
+    pub fn gids(mut self, gids: impl IntoIterator<Item = u32>) -> Self {
+        self.gids = Some(gids.into_iter().collect());
+        self
+    }
+
+    /// Processes with one of these supplementary group ids.
+    pub fn supgroups(mut self, gids: impl IntoIterator<Item = u32>) -> Self {
+        self.supgroups = Some(gids.into_iter().collect());
+        self
+    }
+
+    /// Report to stderr why processes similar to the patterns weren't selected, and how
+    /// many couldn't be read.
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Read processes from `root` instead of [proc_root].
+    pub fn proc_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.proc_root = Some(root.into());
+        self
+    }
+
+    /// Compile the patterns, failing if one isn't a valid regular expression.
+    pub fn build(self) -> Result<Settings, regex::Error> {
+        let pattern = combine_patterns(self.patterns.clone(), self.exact);
+
+        Ok(Settings {
+            matcher: Matcher::new(&pattern, self.ignore_case)?,
+            pattern,
+            full: self.full,
+            ignore_case: self.ignore_case,
+            inverse: self.inverse,
+            newest: self.newest,
+            oldest: self.oldest,
+            older: self.older,
+            younger: self.younger,
+            parent: self.parent,
+            pidfile: self.pidfile,
+            runstates: self.runstates,
+            terminal: self.terminal,
+            threads: self.threads,
+            cgroups: self.cgroups,
+            euids: self.euids,
+            uids: self.uids,
+            gids: self.gids,
+            supgroups: self.supgroups,
+            debug: self.debug,
+            patterns: self.patterns,
+            proc_root: self.proc_root.unwrap_or_else(proc_root),
+        })
+    }
+}
+
+/// Combine the patterns into a single alternation, so that matching stays one regex
+/// evaluation per process regardless of the number of patterns.
+pub fn combine_patterns(patterns: Vec<String>, exact: bool) -> String {
+    let patterns: Vec<_> = patterns
+        .into_iter()
+        .map(|pattern| {
+            if exact {
+                format!("^(?:{})$", pattern)
+            } else {
+                pattern
+            }
+        })
+        .collect();
+
+    match patterns.as_slice() {
+        [pattern] => pattern.clone(),
+        _ => patterns
+            .iter()
+            .map(|pattern| format!("(?:{pattern})"))
+            .collect::<Vec<_>>()
+            .join("|"),
+    }
+}
+
+/// Parse the content of a patterns file, one pattern per line.
+///
+/// Empty lines and lines starting with `#` are skipped.
+pub fn parse_patterns(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// The selected processes by ascending pid. The running process itself is never selected.
+pub fn find_matching_pids(settings: &Settings) -> Vec<ProcessInformation> {
+    find_matching_pids_with(settings, |_| true)
+}
+
+/// Like [find_matching_pids], but matches for which `keep` returns false are left out
+/// before the newest or oldest one is picked, e.g. processes which must not be signalled.
+pub fn find_matching_pids_with(
+    settings: &Settings,
+    keep: impl FnMut(&ProcessInformation) -> bool,
+) -> Vec<ProcessInformation> {
+    // All other filters apply first, newest and oldest then pick from what is left, like
+    // procps: `-n --older 10` is the newest process which is at least 10 seconds old.
+    let matched = collect_matched_pids(settings).into_iter().filter(keep);
+    let mut pids = process_flag_o_n(settings, matched.collect());
+    // Like procps, list in ascending pid order rather than /proc iteration order.
+    pids.sort_by_key(|it| it.pid);
+    pids
+}
+
+/// Collect pids with filter construct from command line arguments
+///
+/// Processes may exit while they are inspected. Those whose files can't be read are skipped
+/// rather than matched, and counted for `--debug`.
+fn collect_matched_pids(settings: &Settings) -> Vec<ProcessInformation> {
+    // A pidfile holding 0 or a negative pid selects no process, no need to look further.
+    if settings.pidfile.is_some_and(|pid| pid <= 0) {
+        return Vec::new();
+    }
+
+    let now = boot_time_now().unwrap_or_default();
+
+    // With `--lightweight` every thread is matched on its own, including its name.
+    let processes: Vec<_> = if settings.threads {
+        walk_threads_in(&settings.proc_root).collect()
+    } else {
+        walk_process_in(&settings.proc_root).collect()
+    };
+
+    let mut filtered = Vec::new();
+    let mut skipped = 0;
+
+    // Like procps, never report ourselves: our own command line contains the pattern.
+    let own_pid = std::process::id() as usize;
+
+    for mut pid in processes.into_iter().filter(|it| it.pid != own_pid) {
+        match process_matched(settings, &mut pid, now) {
+            Ok(true) => filtered.push(pid),
+            Ok(false) if settings.debug => {
+                if let Some(explanation) = explain_exclusion(settings, &mut pid, now) {
+                    eprintln!("{}: {}", uucore::util_name(), explanation);
+                }
+            }
+            Ok(false) => {}
+            Err(_) => skipped += 1,
+        }
+    }
+
+    if settings.debug {
+        eprintln!(
+            "{}: {} processes skipped due to read errors",
+            uucore::util_name(),
+            skipped
+        );
+    }
+
+    filtered
+}
+
+/// Whether the process matches all the filters, taking `--inverse` into account.
+fn process_matched(
+    settings: &Settings,
+    pid: &mut ProcessInformation,
+    now: f64,
+) -> Result<bool, io::Error> {
+    Ok(unmatched_filters(settings, pid, now)?.is_empty() ^ settings.inverse)
+}
+
+/// The filters set up on the command line, see [unmatched_filters].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    Pattern,
+    RunState,
+    Terminal,
+    Older,
+    Younger,
+    Parent,
+    Pidfile,
+    Cgroup,
+    Euid,
+    Uid,
+    Group,
+    SupGroup,
+}
+
+/// The filters the process doesn't match, ignoring `--inverse`.
+fn unmatched_filters(
+    settings: &Settings,
+    pid: &mut ProcessInformation,
+    now: f64,
+) -> Result<Vec<Filter>, io::Error> {
+    let run_state_matched = match &settings.runstates {
+        Some(arg_run_states) => arg_run_states.contains(&pid.run_state()?.to_string()),
+        None => true,
+    };
+
+    let name = pid.name()?;
+    let pattern_matched = {
+        let want = if settings.full {
+            // Equals `cmdline` in /proc/<pid>/cmdline, the arguments joined by single
+            // spaces. With `--exact` the whole command line must match.
+            &pid.cmdline
+        } else {
+            // Equals /proc/<pid>/comm, which is anchored with `--exact`.
+            // From manpage:
+            // The process name used for matching is limited to the 15 characters present in the output of /proc/pid/stat.
+            // The comm file holds the same name, and for a thread its own name.
+            &name
+        };
+
+        settings.matcher.is_match(want)
+    };
+
+    let tty_matched = match &settings.terminal {
+        Some(ttys) => ttys.contains(&pid.tty_nr()?),
+        None => true,
+    };
+
+    let age = if settings.older.is_some() || settings.younger.is_some() {
+        pid.age(now)?
+    } else {
+        0.0
+    };
+    let older_matched = match settings.older {
+        Some(older) => age >= older - AGE_EPSILON,
+        None => true,
+    };
+    let younger_matched = match settings.younger {
+        Some(younger) => age < younger + AGE_EPSILON,
+        None => true,
+    };
+
+    // the PPID is the fourth field in /proc/<PID>/stat
+    // (https://www.kernel.org/doc/html/latest/filesystems/proc.html#id10)
+    let stat = pid.stat();
+    let ppid = stat.get(3);
+    let parent_matched = match (&settings.parent, ppid) {
+        (Some(parents), Some(ppid)) => parents.contains(&ppid.parse::<u64>().unwrap()),
+        _ => true,
+    };
+
+    let pidfile_matched = match settings.pidfile {
+        Some(pid_from_file) => pid.pid as i64 == pid_from_file,
+        None => true,
+    };
+
+    let cgroup_matched = match &settings.cgroups {
+        Some(cgroups) => cgroups.contains(&pid.cgroup_v2_path()?),
+        None => true,
+    };
+
+    let euid_matched = match &settings.euids {
+        Some(euids) => euids.contains(&pid.euid()?),
+        None => true,
+    };
+    let uid_matched = match &settings.uids {
+        Some(uids) => uids.contains(&pid.uid()?),
+        None => true,
+    };
+    let gid_matched = match &settings.gids {
+        Some(gids) => gids.contains(&pid.gid()?),
+        None => true,
+    };
+    let supgroup_matched = match &settings.supgroups {
+        Some(gids) => pid
+            .supplementary_groups()?
+            .iter()
+            .any(|gid| gids.contains(gid)),
+        None => true,
+    };
+
+    Ok([
+        (Filter::RunState, run_state_matched),
+        (Filter::Pattern, pattern_matched),
+        (Filter::Pidfile, pidfile_matched),
+        (Filter::Terminal, tty_matched),
+        (Filter::Older, older_matched),
+        (Filter::Younger, younger_matched),
+        (Filter::Parent, parent_matched),
+        (Filter::Cgroup, cgroup_matched),
+        (Filter::Euid, euid_matched),
+        (Filter::Uid, uid_matched),
+        (Filter::Group, gid_matched),
+        (Filter::SupGroup, supgroup_matched),
+    ]
+    .into_iter()
+    .filter(|(_, matched)| !matched)
+    .map(|(filter, _)| filter)
+    .collect())
+}
+
+/// Explain why a process which looks like the one asked for wasn't selected, for `--debug`.
+///
+/// Only processes whose name or command line contains one of the patterns literally are
+/// explained, everything else is simply not what the user is after.
+fn explain_exclusion(
+    settings: &Settings,
+    pid: &mut ProcessInformation,
+    now: f64,
+) -> Option<String> {
+    let fold = |text: &str| {
+        if settings.ignore_case {
+            text.to_lowercase()
+        } else {
+            text.to_owned()
+        }
+    };
+    let name = pid.name().ok()?;
+    let (folded_name, folded_cmdline) = (fold(&name), fold(&pid.cmdline));
+    let patterns: Vec<_> = settings.patterns.iter().map(|it| fold(it)).collect();
+
+    let in_name = patterns.iter().any(|it| folded_name.contains(it.as_str()));
+    let in_cmdline = patterns
+        .iter()
+        .any(|it| folded_cmdline.contains(it.as_str()));
+    if !in_name && !in_cmdline {
+        return None;
+    }
+
+    let unmatched = unmatched_filters(settings, pid, now).ok()?;
+    let reasons: Vec<_> = if unmatched.is_empty() {
+        vec!["matches, but -v/--inverse is set"]
+    } else {
+        unmatched
+            .into_iter()
+            .map(|filter| match filter {
+                Filter::Pattern if settings.full => "command line doesn't match the pattern",
+                // The kernel keeps 15 characters of the name, see `proc_pid_comm(5)`.
+                Filter::Pattern if name.chars().count() == 15 && in_cmdline => {
+                    "name truncated to 15 chars; try -f"
+                }
+                Filter::Pattern if in_cmdline && !in_name => {
+                    "pattern only found in the command line; try -f"
+                }
+                Filter::Pattern => "name doesn't match the pattern",
+                Filter::RunState => "state not in -r/--runstates",
+                Filter::Terminal => "terminal not in -t/--terminal",
+                Filter::Older => "younger than -O/--older",
+                Filter::Younger => "older than -Y/--younger",
+                Filter::Parent => "parent not in -P/--parent",
+                Filter::Pidfile => "pid not in -F/--pidfile",
+                Filter::Cgroup => "cgroup not in --cgroup",
+                Filter::Euid => "effective user not in -u/--euid",
+                Filter::Uid => "real user not in -U/--uid",
+                Filter::Group => "real group not in -G/--group",
+                Filter::SupGroup => "no supplementary group in --supgroup",
+            })
+            .collect()
+    };
+
+    Some(format!(
+        "pid {} ({}) not selected: {}",
+        pid.pid,
+        name,
+        reasons.join("; ")
+    ))
+}
+
+/// Select the single newest or oldest process if asked for, all of `pids` otherwise.
+///
+/// Processes started within the same clock tick are told apart by pid, the highest pid
+/// counting as the newest. Processes without a readable start time are never selected.
+fn process_flag_o_n(settings: &Settings, pids: Vec<ProcessInformation>) -> Vec<ProcessInformation> {
+    if !settings.oldest && !settings.newest {
+        return pids;
+    }
+
+    let by_start = pids
+        .into_iter()
+        .filter_map(|mut it| Some(((it.start_time().ok()?, it.pid), it)));
+    let selected = if settings.newest {
+        by_start.max_by_key(|(key, _)| *key)
+    } else {
+        by_start.min_by_key(|(key, _)| *key)
+    };
+
+    selected.map(|(_, it)| it).into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_patterns() {
+        let content = "sshd\n\n# comment\nnginx  \n  cron\n";
+        assert_eq!(parse_patterns(content), ["sshd", "nginx", "  cron"]);
+        assert!(parse_patterns("").is_empty());
+    }
+
+    #[test]
+    fn test_combine_patterns() {
+        let patterns = || vec!["sshd".to_string(), "Nginx".to_string()];
+
+        assert_eq!(combine_patterns(vec![], false), "");
+        assert_eq!(combine_patterns(vec!["sshd".into()], true), "^(?:sshd)$");
+        assert_eq!(combine_patterns(patterns(), false), "(?:sshd)|(?:Nginx)");
+        assert_eq!(
+            combine_patterns(patterns(), true),
+            "(?:^(?:sshd)$)|(?:^(?:Nginx)$)"
+        );
+        // An alternation within a pattern has to match the whole name as well.
+        let exact = regex::Regex::new(&combine_patterns(vec!["foo|bar".into()], true)).unwrap();
+        assert!(exact.is_match("bar"));
+        assert!(!exact.is_match("foobar"));
+        assert!(!exact.is_match("barfoo"));
+    }
+
+    #[test]
+    fn test_criteria() {
+        let settings = Settings::builder().build().unwrap();
+        assert!(!settings.has_criteria());
+        assert!(!settings.only_pidfile());
+
+        let settings = Settings::builder().pidfile(42).build().unwrap();
+        assert!(settings.has_criteria());
+        assert!(settings.only_pidfile());
+
+        let settings = Settings::builder()
+            .pidfile(42)
+            .patterns(["sleep"])
+            .build()
+            .unwrap();
+        assert!(settings.has_criteria());
+        assert!(!settings.only_pidfile());
+
+        // Flags which only change how to match are no criteria on their own.
+        let settings = Settings::builder()
+            .exact(true)
+            .ignore_case(true)
+            .full(true)
+            .inverse(true)
+            .build()
+            .unwrap();
+        assert!(!settings.has_criteria());
+
+        assert!(Settings::builder().patterns(["("]).build().is_err());
+    }
+}
@@ -0,0 +1,303 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// Parent/child relations between processes, e.g. for forests or ancestor checks
+
+use crate::process::ProcessInformation;
+use std::collections::HashMap;
+
+/// The processes of a snapshot arranged by their parent pids.
+///
+/// Processes whose parent is not part of the snapshot become roots, e.g. pid 1 or
+/// processes reparented while the snapshot was taken. A parent chain looping back on
+/// itself, which pid reuse can produce, is cut at its lowest pid so that every query
+/// terminates.
+#[derive(Debug, Default)]
+pub struct ProcessTree {
+    /// Pids in ascending order, nodes are referred to by their index in here.
+    pids: Vec<usize>,
+    /// Node index of the parent of each node, `None` for roots.
+    parents: Vec<Option<usize>>,
+    /// Children of node `i` are `children[offsets[i]..offsets[i + 1]]`, by ascending pid.
+    children: Vec<usize>,
+    offsets: Vec<usize>,
+    index: HashMap<usize, usize>,
+}
+
+impl ProcessTree {
+    /// Build the tree from `(pid, ppid)` pairs. Duplicate pids keep their first parent.
+    pub fn from_pairs(pairs: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let mut pairs: Vec<_> = pairs.into_iter().collect();
+        pairs.sort_by_key(|(pid, _)| *pid);
+        pairs.dedup_by_key(|(pid, _)| *pid);
+
+        let pids: Vec<_> = pairs.iter().map(|(pid, _)| *pid).collect();
+        let index: HashMap<_, _> = pids.iter().enumerate().map(|(i, pid)| (*pid, i)).collect();
+        let mut parents: Vec<_> = pairs
+            .iter()
+            .map(|(pid, ppid)| index.get(ppid).copied().filter(|_| ppid != pid))
+            .collect();
+        break_cycles(&mut parents);
+
+        // Group the children by parent, counting sort keeps them in ascending pid order.
+        let mut offsets = vec![0; pids.len() + 1];
+        for parent in parents.iter().flatten() {
+            offsets[parent + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        let mut next = offsets.clone();
+        let mut children = vec![0; offsets[pids.len()]];
+        for (node, parent) in parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                children[next[*parent]] = node;
+                next[*parent] += 1;
+            }
+        }
+
+        Self {
+            pids,
+            parents,
+            children,
+            offsets,
+            index,
+        }
+    }
+
+    /// Build the tree from processes, those without a readable parent pid become roots.
+    pub fn from_processes(processes: impl IntoIterator<Item = ProcessInformation>) -> Self {
+        Self::from_pairs(
+            processes
+                .into_iter()
+                .map(|mut process| (process.pid, process.ppid().unwrap_or(0))),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.pids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pids.is_empty()
+    }
+
+    pub fn contains(&self, pid: usize) -> bool {
+        self.index.contains_key(&pid)
+    }
+
+    /// The parent of `pid`, `None` for roots and unknown pids.
+    pub fn parent(&self, pid: usize) -> Option<usize> {
+        let node = *self.index.get(&pid)?;
+        self.parents[node].map(|parent| self.pids[parent])
+    }
+
+    /// The direct children of `pid` by ascending pid, empty for unknown pids.
+    pub fn children(&self, pid: usize) -> impl Iterator<Item = usize> + '_ {
+        self.child_nodes(self.index.get(&pid).copied())
+            .iter()
+            .map(|child| self.pids[*child])
+    }
+
+    /// The parent of `pid`, its parent and so on up to the root.
+    pub fn ancestors(&self, pid: usize) -> Vec<usize> {
+        let mut ancestors = Vec::new();
+        let mut node = self.index.get(&pid).copied();
+        while let Some(parent) = node.and_then(|node| self.parents[node]) {
+            ancestors.push(self.pids[parent]);
+            node = Some(parent);
+        }
+        ancestors
+    }
+
+    /// Everything below `pid` in depth-first order, not including `pid` itself.
+    pub fn descendants(&self, pid: usize) -> Vec<usize> {
+        let Some(&node) = self.index.get(&pid) else {
+            return Vec::new();
+        };
+        Walk::new(self, vec![(node, 0)])
+            .skip(1)
+            .map(|(pid, _)| pid)
+            .collect()
+    }
+
+    /// All processes in depth-first order with their depth, roots having depth 0.
+    /// Roots and siblings come by ascending pid.
+    pub fn walk(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let roots = (0..self.len())
+            .rev()
+            .filter(|node| self.parents[*node].is_none())
+            .map(|node| (node, 0))
+            .collect();
+        Walk::new(self, roots)
+    }
+
+    fn child_nodes(&self, node: Option<usize>) -> &[usize] {
+        match node {
+            Some(node) => &self.children[self.offsets[node]..self.offsets[node + 1]],
+            None => &[],
+        }
+    }
+}
+
+/// Cut every parent chain that loops back on itself, at the lowest node of the loop.
+fn break_cycles(parents: &mut [Option<usize>]) {
+    const UNSEEN: u8 = 0;
+    const ON_PATH: u8 = 1;
+    const DONE: u8 = 2;
+
+    let mut state = vec![UNSEEN; parents.len()];
+    let mut path = Vec::new();
+
+    for start in 0..parents.len() {
+        let mut node = start;
+        while state[node] == UNSEEN {
+            state[node] = ON_PATH;
+            path.push(node);
+            match parents[node] {
+                Some(parent) => node = parent,
+                None => break,
+            }
+        }
+
+        if state[node] == ON_PATH && parents[node].is_some() {
+            // `node` was reached again, the loop is the part of the path starting there.
+            let first = path.iter().position(|it| *it == node).unwrap();
+            let lowest = *path[first..].iter().min().unwrap();
+            parents[lowest] = None;
+        }
+
+        for node in path.drain(..) {
+            state[node] = DONE;
+        }
+    }
+}
+
+/// Depth-first traversal yielding `(pid, depth)`.
+struct Walk<'a> {
+    tree: &'a ProcessTree,
+    /// Nodes still to visit with their depth, the next one last.
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a> Walk<'a> {
+    fn new(tree: &'a ProcessTree, stack: Vec<(usize, usize)>) -> Self {
+        Self { tree, stack }
+    }
+}
+
+impl Iterator for Walk<'_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.stack.pop()?;
+        let children = self.tree.child_nodes(Some(node));
+        self.stack
+            .extend(children.iter().rev().map(|child| (*child, depth + 1)));
+        Some((self.tree.pids[node], depth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two trees rooted at 1 and 2, 50 is an orphan whose parent 40 is gone and
+    /// 1 -> 10 -> 11 -> 12 -> 13 is four levels deep.
+    fn forest() -> ProcessTree {
+        ProcessTree::from_pairs([
+            (13, 12),
+            (1, 0),
+            (2, 0),
+            (10, 1),
+            (11, 10),
+            (12, 11),
+            (20, 1),
+            (30, 2),
+            (31, 2),
+            (50, 40),
+        ])
+    }
+
+    #[test]
+    fn test_children() {
+        let tree = forest();
+
+        assert_eq!(tree.children(1).collect::<Vec<_>>(), [10, 20]);
+        assert_eq!(tree.children(2).collect::<Vec<_>>(), [30, 31]);
+        assert_eq!(tree.children(13).count(), 0);
+        assert_eq!(tree.children(40).count(), 0);
+    }
+
+    #[test]
+    fn test_parent_and_ancestors() {
+        let tree = forest();
+
+        assert_eq!(tree.parent(13), Some(12));
+        assert_eq!(tree.parent(1), None);
+        assert_eq!(tree.parent(50), None);
+        assert_eq!(tree.ancestors(13), [12, 11, 10, 1]);
+        assert!(tree.ancestors(1).is_empty());
+        assert!(tree.ancestors(50).is_empty());
+        assert!(tree.ancestors(99).is_empty());
+    }
+
+    #[test]
+    fn test_descendants() {
+        let tree = forest();
+
+        assert_eq!(tree.descendants(1), [10, 11, 12, 13, 20]);
+        assert_eq!(tree.descendants(11), [12, 13]);
+        assert!(tree.descendants(50).is_empty());
+        assert!(tree.descendants(99).is_empty());
+    }
+
+    #[test]
+    fn test_walk() {
+        let tree = forest();
+
+        assert_eq!(
+            tree.walk().collect::<Vec<_>>(),
+            [
+                (1, 0),
+                (10, 1),
+                (11, 2),
+                (12, 3),
+                (13, 4),
+                (20, 1),
+                (2, 0),
+                (30, 1),
+                (31, 1),
+                (50, 0),
+            ]
+        );
+        assert_eq!(tree.len(), 10);
+    }
+
+    #[test]
+    fn test_cycles() {
+        // 5 -> 6 -> 7 -> 5 after pid reuse, 8 hangs off the loop, 9 is its own parent.
+        let tree = ProcessTree::from_pairs([(5, 7), (6, 5), (7, 6), (8, 6), (9, 9)]);
+
+        assert_eq!(tree.parent(5), None);
+        assert_eq!(tree.ancestors(8), [6, 5]);
+        assert_eq!(tree.descendants(5), [6, 7, 8]);
+        assert_eq!(
+            tree.walk().collect::<Vec<_>>(),
+            [(5, 0), (6, 1), (7, 2), (8, 2), (9, 0)]
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_from_processes() {
+        let tree = ProcessTree::from_processes(crate::process::walk_process());
+        let pid = std::process::id() as usize;
+
+        assert!(!tree.descendants(1).is_empty());
+        assert!(tree.descendants(1).contains(&pid));
+        assert_eq!(tree.ancestors(pid).last(), Some(&1));
+    }
+}
@@ -3,7 +3,10 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use std::path::PathBuf;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 use clap::{crate_version, Arg, ArgAction, ArgMatches, Command};
 use uu_pgrep::process::{walk_process, ProcessInformation};
@@ -61,6 +64,60 @@ fn get_executable_name(process: &mut ProcessInformation) -> String {
         .to_string()
 }
 
+/// What a program argument is compared with.
+#[derive(Debug, PartialEq, Eq)]
+enum Target {
+    /// The executable at this canonical path, compared with `/proc/<pid>/exe` so that
+    /// unrelated programs sharing the name are left out. The name of the argument is the
+    /// fallback for processes whose executable can't be read.
+    Executable { path: PathBuf, name: String },
+    /// A name not found as a file of its own, compared with the name the process was
+    /// started as.
+    Name(String),
+}
+
+impl Target {
+    /// Arguments with a `/` name a file, bare names are looked up in `search_path`.
+    fn resolve(program: &str, search_path: Option<&std::ffi::OsStr>) -> Self {
+        // What the argument calls the program, not what a symlink resolves to: `sh` may be
+        // dash, and `sleep` a busybox applet.
+        let name = Path::new(program)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(program)
+            .to_string();
+        let path = if program.contains('/') {
+            Some(PathBuf::from(program))
+        } else {
+            search_path.and_then(|search_path| {
+                env::split_paths(search_path)
+                    .map(|dir| dir.join(program))
+                    .find(|path| path.is_file())
+            })
+        };
+
+        match path.map(|path| fs::canonicalize(&path).unwrap_or(path)) {
+            // A symlink to a file of another name, like a multicall binary, would match every
+            // program sharing that file.
+            Some(path) if path.file_name() == Some(name.as_ref()) => {
+                Self::Executable { path, name }
+            }
+            _ => Self::Name(name),
+        }
+    }
+
+    fn matches(&self, process: &mut ProcessInformation) -> bool {
+        match self {
+            Self::Executable { path, name } => match process.exe() {
+                Ok(exe) => &exe == path,
+                // Another user's process or a kernel thread, all we have is the name.
+                Err(_) => *name == get_executable_name(process),
+            },
+            Self::Name(name) => *name == get_executable_name(process),
+        }
+    }
+}
+
 fn collect_matched_pids(matches: &ArgMatches) -> Vec<ProcessInformation> {
     let program_names: Vec<_> = matches
         .get_many::<String>("program-name")
@@ -75,12 +132,15 @@ fn collect_matched_pids(matches: &ArgMatches) -> Vec<ProcessInformation> {
         .copied()
         .collect::<Vec<_>>();
 
+    let search_path = env::var_os("PATH");
+
     program_names
         .into_iter()
         .flat_map(|program| {
+            let target = Target::resolve(&program, search_path.as_deref());
             let mut processed = Vec::new();
             for mut process in collected.clone() {
-                let contains = program == get_executable_name(&mut process);
+                let contains = target.matches(&mut process);
                 let should_omit = arg_omit_pid.contains(&process.pid);
 
                 if contains && !should_omit {
@@ -88,7 +148,7 @@ fn collect_matched_pids(matches: &ArgMatches) -> Vec<ProcessInformation> {
                 }
             }
 
-            processed.sort_by(|a, b| b.pid.cmp(&a.pid));
+            processed.sort_by_key(|it| std::cmp::Reverse(it.pid));
 
             let flag_s = matches.get_flag("s");
             if flag_s {
@@ -171,3 +231,67 @@ pub fn uu_app() -> Command {
     //         .action(ArgAction::SetTrue),
     // )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_target() {
+        let dir = env::temp_dir().join(format!("pidof-{}-resolve", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("daemon"), "").unwrap();
+        let dir = fs::canonicalize(dir).unwrap();
+        let search_path = env::join_paths(["/nonexistent".into(), dir.clone()]).unwrap();
+
+        let daemon = || Target::Executable {
+            path: dir.join("daemon"),
+            name: "daemon".into(),
+        };
+
+        assert_eq!(Target::resolve("daemon", Some(&search_path)), daemon());
+        assert_eq!(
+            Target::resolve(dir.join("daemon").to_str().unwrap(), None),
+            daemon()
+        );
+        assert_eq!(
+            Target::resolve("daemon", None),
+            Target::Name("daemon".into())
+        );
+        assert_eq!(
+            Target::resolve("kthreadd", Some(&search_path)),
+            Target::Name("kthreadd".into())
+        );
+        // Directories in PATH are not programs.
+        fs::create_dir_all(dir.join("subdir")).unwrap();
+        assert_eq!(
+            Target::resolve("subdir", Some(&search_path)),
+            Target::Name("subdir".into())
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+
+            // Links to a file of the same name, as with a merged /usr, are that file.
+            fs::create_dir_all(dir.join("bin")).unwrap();
+            symlink(dir.join("daemon"), dir.join("bin/daemon")).unwrap();
+            assert_eq!(
+                Target::resolve(dir.join("bin/daemon").to_str().unwrap(), None),
+                daemon()
+            );
+            // Links to a multicall binary only tell the applet by its name.
+            symlink(dir.join("daemon"), dir.join("applet")).unwrap();
+            assert_eq!(
+                Target::resolve("applet", Some(&search_path)),
+                Target::Name("applet".into())
+            );
+            assert_eq!(
+                Target::resolve(dir.join("applet").to_str().unwrap(), None),
+                Target::Name("applet".into())
+            );
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -4,34 +4,27 @@
 // file that was distributed with this source code.
 
 use clap::{arg, crate_version, value_parser, Arg, ArgAction, ArgMatches, Command};
-use regex::Regex;
-use std::{collections::HashSet, env, sync::OnceLock};
-use uu_pgrep::process::{walk_process, ProcessInformation, RunState, Teletype};
+use std::io::{self, Write};
+use uu_pgrep::check_proc_root;
+use uu_pgrep::process::{ProcessInformation, Teletype};
+use uu_pgrep::select::{self, find_matching_pids};
 use uucore::{
     error::{UResult, USimpleError},
     format_usage, help_about, help_usage,
 };
-use wait::wait;
+use wait::{install_sigint_handler, wait, WaitOutcome};
 
 mod wait;
 
 const ABOUT: &str = help_about!("pidwait.md");
 const USAGE: &str = help_usage!("pidwait.md");
 
-static REGEX: OnceLock<Regex> = OnceLock::new();
-
 #[derive(Debug)]
 struct Settings {
     echo: bool,
     count: bool,
-    full: bool,
-    ignore_case: bool,
     newest: bool,
     oldest: bool,
-    older: Option<u64>,
-    terminal: Option<HashSet<Teletype>>,
-    exact: bool,
-    runstates: Option<HashSet<RunState>>,
 }
 
 #[uucore::main]
@@ -41,41 +34,21 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let settings = Settings {
         echo: matches.get_flag("echo"),
         count: matches.get_flag("count"),
-        full: matches.get_flag("full"),
-        ignore_case: matches.get_flag("ignore-case"),
         newest: matches.get_flag("newest"),
         oldest: matches.get_flag("oldest"),
-        older: matches.get_one::<u64>("older").copied(),
-        terminal: matches.get_many::<String>("terminal").map(|ttys| {
-            ttys.cloned()
-                .flat_map(Teletype::try_from)
-                .collect::<HashSet<_>>()
-        }),
-        exact: matches.get_flag("exact"),
-        runstates: matches
-            .get_many::<String>("runstates")
-            .map(|it| it.cloned().flat_map(RunState::try_from).collect()),
     };
+    let selection = selection_from(&matches)?;
 
-    let pattern = initialize_pattern(&matches, &settings)?;
-    REGEX
-        .set(Regex::new(&pattern).map_err(|e| USimpleError::new(2, e.to_string()))?)
-        .unwrap();
-
-    if (!settings.newest
-        && !settings.oldest
-        && settings.runstates.is_none()
-        && settings.older.is_none()
-        && settings.terminal.is_none())
-        && pattern.is_empty()
-    {
+    if !selection.has_criteria() {
         return Err(USimpleError::new(
             2,
             "no matching criteria specified\nTry `pidwait --help' for more information.",
         ));
     }
 
-    let mut proc_infos = collect_proc_infos(&settings);
+    check_proc_root(selection.proc_root())?;
+
+    let mut proc_infos = find_matching_pids(&selection);
 
     // For empty result
     if proc_infos.is_empty() {
@@ -99,12 +72,62 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         }
     }
 
-    wait(&proc_infos);
+    install_sigint_handler();
+
+    let on_exit = |proc_info: &mut ProcessInformation| {
+        if settings.echo {
+            println!(
+                "{} (pid {}) exited",
+                proc_info.status()["Name"],
+                proc_info.pid
+            );
+            // Show the exits as they happen, even when stdout is a pipe.
+            io::stdout().flush().ok();
+        }
+    };
+
+    if let WaitOutcome::Interrupted(remaining) = wait(&proc_infos, on_exit) {
+        for mut proc_info in remaining {
+            eprintln!(
+                "{} (pid {}) still running",
+                proc_info.status()["Name"],
+                proc_info.pid
+            );
+        }
+        // 128 + SIGINT, as a shell would report it.
+        uucore::error::set_exit_code(130);
+    }
 
     Ok(())
 }
 
-fn initialize_pattern(matches: &ArgMatches, settings: &Settings) -> UResult<String> {
+/// Translate the command line into the process selection.
+fn selection_from(matches: &ArgMatches) -> UResult<select::Settings> {
+    let full = matches.get_flag("full");
+    let mut builder = select::Settings::builder()
+        .patterns(initialize_pattern(matches, full)?)
+        .exact(matches.get_flag("exact"))
+        .full(full)
+        .ignore_case(matches.get_flag("ignore-case"))
+        .newest(matches.get_flag("newest"))
+        .oldest(matches.get_flag("oldest"));
+
+    if let Some(older) = matches.get_one::<u64>("older") {
+        builder = builder.older(*older as f64);
+    }
+    if let Some(ttys) = matches.get_many::<String>("terminal") {
+        builder = builder.terminals(ttys.cloned().flat_map(Teletype::try_from));
+    }
+    if let Some(runstates) = matches.get_one::<String>("runstates") {
+        builder = builder.runstates(runstates);
+    }
+
+    builder
+        .build()
+        .map_err(|e| USimpleError::new(2, e.to_string()))
+}
+
+fn initialize_pattern(matches: &ArgMatches, full: bool) -> UResult<Option<String>> {
     let pattern = match matches.get_many::<String>("pattern") {
         Some(patterns) if patterns.len() > 1 => {
             return Err(USimpleError::new(
@@ -113,121 +136,16 @@ fn initialize_pattern(matches: &ArgMatches, settings: &Settings) -> UResult<Stri
             ))
         }
         Some(mut patterns) => patterns.next().unwrap(),
-        None => return Ok(String::new()),
-    };
-
-    let pattern = if settings.ignore_case {
-        &pattern.to_lowercase()
-    } else {
-        pattern
-    };
-
-    let pattern = if settings.exact {
-        &format!("^{}$", pattern)
-    } else {
-        pattern
+        None => return Ok(None),
     };
 
-    if !settings.full && pattern.len() >= 15 {
+    if !full && pattern.len() >= 15 {
         const MSG_0: &str= "pidwait: pattern that searches for process name longer than 15 characters will result in zero matches";
         const MSG_1: &str = "Try `pidwait -f' option to match against the complete command line.";
         return Err(USimpleError::new(1, format!("{MSG_0}\n{MSG_1}")));
     }
 
-    Ok(pattern.to_string())
-}
-
-fn collect_proc_infos(settings: &Settings) -> Vec<ProcessInformation> {
-    // Process pattern
-    let proc_infos = {
-        let mut temp = Vec::new();
-        for mut it in walk_process() {
-            let matched = {
-                let binding = it.status();
-                let name = binding.get("Name").unwrap();
-                let name = if settings.ignore_case {
-                    name.to_lowercase()
-                } else {
-                    name.into()
-                };
-
-                let want = if settings.exact {
-                    &name
-                } else if settings.full {
-                    &it.cmdline
-                } else {
-                    &it.proc_stat()[..15]
-                };
-
-                REGEX.get().unwrap().is_match(want)
-            };
-            if matched {
-                temp.push(it);
-            }
-        }
-        temp
-    };
-
-    // Process `-O`
-    let proc_infos = {
-        let mut temp: Vec<ProcessInformation> = Vec::new();
-        let older = settings.older.unwrap_or_default();
-        for mut proc_info in proc_infos {
-            if proc_info.start_time().unwrap() >= older {
-                temp.push(proc_info);
-            }
-        }
-        temp
-    };
-
-    let mut proc_infos = {
-        if let Some(terminals) = &settings.terminal {
-            proc_infos
-                .into_iter()
-                .filter(|it| terminals.contains(&it.tty()))
-                .collect()
-        } else {
-            proc_infos
-        }
-    };
-
-    if proc_infos.is_empty() {
-        return proc_infos;
-    }
-
-    // Sorting oldest and newest
-    let proc_infos = if settings.oldest || settings.newest {
-        proc_infos.sort_by(|a, b| {
-            b.clone()
-                .start_time()
-                .unwrap()
-                .cmp(&a.clone().start_time().unwrap())
-        });
-
-        let start_time = if settings.newest {
-            proc_infos.first().cloned().unwrap().start_time().unwrap()
-        } else {
-            proc_infos.last().cloned().unwrap().start_time().unwrap()
-        };
-
-        // There might be some process start at same time, so need to be filtered.
-        let mut filtered = proc_infos
-            .iter()
-            .filter(|it| (*it).clone().start_time().unwrap() == start_time)
-            .collect::<Vec<_>>();
-
-        if settings.newest {
-            filtered.sort_by(|a, b| b.pid.cmp(&a.pid));
-        } else {
-            filtered.sort_by(|a, b| a.pid.cmp(&b.pid));
-        }
-
-        vec![filtered.first().cloned().unwrap().clone()]
-    } else {
-        proc_infos
-    };
-
-    proc_infos
+    Ok(Some(pattern.to_string()))
 }
 
 #[allow(clippy::cognitive_complexity)]
@@ -236,9 +154,10 @@ pub fn uu_app() -> Command {
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
+        .args_override_self(true)
         .infer_long_args(true)
         .args([
-            arg!(-e --echo                      "display PIDs before waiting"),
+            arg!(-e --echo                      "display PIDs before waiting and as they exit"),
             arg!(-c --count                     "count of matching processes"),
             arg!(-f --full                      "use full process name to match"),
             // arg!(-g --pgroup        <PGID>      "match listed process group IDs"),
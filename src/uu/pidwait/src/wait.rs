@@ -3,26 +3,70 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use uu_pgrep::process::ProcessInformation;
 
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Result of waiting for a set of processes.
+#[derive(Debug)]
+pub(crate) enum WaitOutcome {
+    /// All processes terminated.
+    Finished,
+    /// SIGINT was received, contains the processes which were still alive.
+    Interrupted(Vec<ProcessInformation>),
+}
+
+#[cfg(unix)]
+extern "C" fn handle_sigint(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Catch SIGINT so that the wait loop can report the processes still being waited on.
+#[cfg(unix)]
+pub(crate) fn install_sigint_handler() {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+    let action = SigAction::new(
+        SigHandler::Handler(handle_sigint),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    // SAFETY: the handler only touches an atomic flag, which is async-signal-safe.
+    unsafe { sigaction(Signal::SIGINT, &action) }.ok();
+}
+
+#[cfg(not(unix))]
+pub(crate) fn install_sigint_handler() {}
+
 // Dirty, but it works.
 // TODO: Use better implementation instead
+/// Wait for `procs` to terminate, `on_exit` is called for each one as soon as it is gone.
 #[cfg(target_os = "linux")]
-pub(crate) fn wait(procs: &[ProcessInformation]) {
+pub(crate) fn wait(
+    procs: &[ProcessInformation],
+    mut on_exit: impl FnMut(&mut ProcessInformation),
+) -> WaitOutcome {
     use std::{thread::sleep, time::Duration};
 
     let mut list = procs.to_vec();
 
     loop {
-        for proc in &list.clone() {
+        list.retain_mut(|proc| {
             // Check is running
-            if !is_running(proc.pid) {
-                list.retain(|it| it.pid != proc.pid);
+            let running = is_running(proc.pid);
+            if !running {
+                on_exit(proc);
             }
-        }
+            running
+        });
 
         if list.is_empty() {
-            return;
+            return WaitOutcome::Finished;
+        }
+
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            return WaitOutcome::Interrupted(list);
         }
 
         sleep(Duration::from_millis(50));
@@ -50,4 +94,13 @@ fn is_running(pid: usize) -> bool {
 
 // Just for passing compile on other system.
 #[cfg(not(target_os = "linux"))]
-pub(crate) fn wait(_procs: &[ProcessInformation]) {}
+pub(crate) fn wait(
+    procs: &[ProcessInformation],
+    _on_exit: impl FnMut(&mut ProcessInformation),
+) -> WaitOutcome {
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        WaitOutcome::Interrupted(procs.to_vec())
+    } else {
+        WaitOutcome::Finished
+    }
+}
@@ -0,0 +1,205 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// Signalling through a pidfd, which can't be redirected to a recycled pid
+
+use std::io;
+
+/// The system calls needed by [signal_pid], behind a trait so the logic can be tested
+/// without racing real processes.
+pub(crate) trait Syscalls {
+    type Pidfd;
+
+    fn pidfd_open(&self, pid: i32) -> io::Result<Self::Pidfd>;
+    fn pidfd_send_signal(&self, pidfd: &Self::Pidfd, sig: i32) -> io::Result<()>;
+    fn kill(&self, pid: i32, sig: i32) -> io::Result<()>;
+    /// Start time of the process in clock ticks after boot, see `proc_pid_stat(5)`.
+    fn start_time(&self, pid: i32) -> io::Result<u64>;
+}
+
+/// Send `sig` to `pid`, provided it is still the process that started at `start_time`.
+///
+/// A pid read from a pidfile may be reused by an unrelated process before it is signalled.
+/// Once a pidfd is open it refers to one process for good, so checking the start time after
+/// opening it proves that the process which was matched is the one being signalled. Kernels
+/// without pidfd support (before 5.3) fall back to a plain `kill(2)`.
+pub(crate) fn signal_pid<S: Syscalls>(
+    syscalls: &S,
+    pid: i32,
+    start_time: u64,
+    sig: i32,
+) -> io::Result<()> {
+    let pidfd = match syscalls.pidfd_open(pid) {
+        Ok(pidfd) => pidfd,
+        Err(e) if e.raw_os_error() == Some(libc::ENOSYS) => return syscalls.kill(pid, sig),
+        Err(e) => return Err(e),
+    };
+
+    if syscalls.start_time(pid)? != start_time {
+        // The pid has been reused, the process we matched is gone.
+        return Err(io::Error::from_raw_os_error(libc::ESRCH));
+    }
+
+    match syscalls.pidfd_send_signal(&pidfd, sig) {
+        Err(e) if e.raw_os_error() == Some(libc::ENOSYS) => syscalls.kill(pid, sig),
+        result => result,
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) struct Linux;
+
+#[cfg(target_os = "linux")]
+impl Syscalls for Linux {
+    type Pidfd = std::os::fd::OwnedFd;
+
+    fn pidfd_open(&self, pid: i32) -> io::Result<Self::Pidfd> {
+        use std::os::fd::FromRawFd;
+
+        // SAFETY: pidfd_open has no memory arguments.
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: `fd` is a freshly opened file descriptor nobody else owns.
+        Ok(unsafe { std::os::fd::OwnedFd::from_raw_fd(fd as i32) })
+    }
+
+    fn pidfd_send_signal(&self, pidfd: &Self::Pidfd, sig: i32) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        // SAFETY: a null siginfo is allowed and makes the call equivalent to kill(2).
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                pidfd.as_raw_fd(),
+                sig,
+                std::ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn kill(&self, pid: i32, sig: i32) -> io::Result<()> {
+        // SAFETY: kill has no memory arguments.
+        if unsafe { libc::kill(pid, sig) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn start_time(&self, pid: i32) -> io::Result<u64> {
+        uu_pgrep::process::ProcessInformation::try_new(format!("/proc/{pid}").into())?.start_time()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    const PID: i32 = 4242;
+    const STARTED: u64 = 1000;
+
+    #[derive(Default)]
+    struct Mock {
+        no_pidfd: bool,
+        no_send_signal: bool,
+        /// The start time after the pidfd was opened, i.e. of the process it refers to
+        start_time: Option<u64>,
+        calls: RefCell<Vec<&'static str>>,
+    }
+
+    impl Syscalls for Mock {
+        type Pidfd = ();
+
+        fn pidfd_open(&self, pid: i32) -> io::Result<()> {
+            self.calls.borrow_mut().push("pidfd_open");
+            match (self.no_pidfd, pid) {
+                (true, _) => Err(io::Error::from_raw_os_error(libc::ENOSYS)),
+                (false, PID) => Ok(()),
+                (false, _) => Err(io::Error::from_raw_os_error(libc::ESRCH)),
+            }
+        }
+
+        fn pidfd_send_signal(&self, _pidfd: &(), _sig: i32) -> io::Result<()> {
+            self.calls.borrow_mut().push("pidfd_send_signal");
+            if self.no_send_signal {
+                return Err(io::Error::from_raw_os_error(libc::ENOSYS));
+            }
+            Ok(())
+        }
+
+        fn kill(&self, _pid: i32, _sig: i32) -> io::Result<()> {
+            self.calls.borrow_mut().push("kill");
+            Ok(())
+        }
+
+        fn start_time(&self, _pid: i32) -> io::Result<u64> {
+            self.calls.borrow_mut().push("start_time");
+            Ok(self.start_time.unwrap_or(STARTED))
+        }
+    }
+
+    fn calls(mock: Mock) -> Vec<&'static str> {
+        mock.calls.into_inner()
+    }
+
+    #[test]
+    fn test_signal_pid() {
+        let mock = Mock::default();
+        signal_pid(&mock, PID, STARTED, libc::SIGTERM).unwrap();
+        assert_eq!(
+            calls(mock),
+            ["pidfd_open", "start_time", "pidfd_send_signal"]
+        );
+    }
+
+    #[test]
+    fn test_signal_pid_reused() {
+        let mock = Mock {
+            start_time: Some(STARTED + 1),
+            ..Default::default()
+        };
+        let e = signal_pid(&mock, PID, STARTED, libc::SIGTERM).unwrap_err();
+        assert_eq!(e.raw_os_error(), Some(libc::ESRCH));
+        assert_eq!(calls(mock), ["pidfd_open", "start_time"]);
+    }
+
+    #[test]
+    fn test_signal_pid_gone() {
+        let mock = Mock::default();
+        let e = signal_pid(&mock, PID + 1, STARTED, libc::SIGTERM).unwrap_err();
+        assert_eq!(e.raw_os_error(), Some(libc::ESRCH));
+        assert_eq!(calls(mock), ["pidfd_open"]);
+    }
+
+    #[test]
+    fn test_signal_pid_fallback() {
+        let mock = Mock {
+            no_pidfd: true,
+            ..Default::default()
+        };
+        signal_pid(&mock, PID, STARTED, libc::SIGTERM).unwrap();
+        assert_eq!(calls(mock), ["pidfd_open", "kill"]);
+
+        let mock = Mock {
+            no_send_signal: true,
+            ..Default::default()
+        };
+        signal_pid(&mock, PID, STARTED, libc::SIGTERM).unwrap();
+        assert_eq!(
+            calls(mock),
+            ["pidfd_open", "start_time", "pidfd_send_signal", "kill"]
+        );
+    }
+}
@@ -4,44 +4,43 @@
 // file that was distributed with this source code.
 
 // Pid utils
+#[cfg(target_os = "linux")]
+mod pidfd;
+
 use clap::{arg, crate_version, Arg, ArgAction, ArgGroup, ArgMatches, Command};
-#[cfg(unix)]
-use nix::{
-    sys::signal::{self, Signal},
-    unistd::Pid,
-};
-use regex::Regex;
+use std::collections::HashSet;
 #[cfg(unix)]
 use std::io::Error;
-use std::{collections::HashSet, sync::OnceLock};
-use uu_pgrep::process::{walk_process, ProcessInformation, Teletype};
+use std::path::Path;
+use uu_pgrep::pidfile::read_pidfile;
+use uu_pgrep::process::{ProcessInformation, Teletype};
+use uu_pgrep::select::{self, find_matching_pids_with};
+use uu_pgrep::{check_proc_root, parse_gid_or_group_name, parse_uid_or_username};
 #[cfg(unix)]
 use uucore::{
-    display::Quotable,
-    error::FromIo,
-    show,
-    signals::{signal_by_name_or_value, signal_name_by_value},
+    display::Quotable, error::FromIo, show_error, show_warning, signals::signal_by_name_or_value,
 };
 use uucore::{
     error::{UResult, USimpleError},
     format_usage, help_about, help_usage,
+    process::{geteuid, getuid},
 };
 
 const ABOUT: &str = help_about!("pkill.md");
 const USAGE: &str = help_usage!("pkill.md");
 
-static REGEX: OnceLock<Regex> = OnceLock::new();
+/// Exit code when `--max-matches` is exceeded, beyond the codes procps defines.
+const EXIT_TOO_MANY: i32 = 4;
+
+/// Options which restrict `--inverse` to some processes, see [check_inverse].
+const NARROWING: [&str; 7] = [
+    "euid", "uid", "group", "terminal", "parent", "cgroup", "pidfile",
+];
 
 struct Settings {
-    exact: bool,
-    full: bool,
-    ignore_case: bool,
-    newest: bool,
-    oldest: bool,
-    older: Option<u64>,
-    parent: Option<Vec<u64>>,
-    runstates: Option<String>,
-    terminal: Option<HashSet<Teletype>>,
+    /// Allow signalling pid 1 and our own ancestors
+    force: bool,
+    ignore_ancestors: bool,
 }
 
 #[uucore::main]
@@ -54,38 +53,15 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let obs_signal = handle_obsolete(&mut args);
 
     let matches = uu_app().try_get_matches_from(&args)?;
+    check_inverse(&matches)?;
 
-    let pattern = try_get_pattern_from(&matches)?;
-    REGEX
-        .set(Regex::new(&pattern).map_err(|e| USimpleError::new(2, e.to_string()))?)
-        .unwrap();
-
+    let selection = selection_from(&matches)?;
     let settings = Settings {
-        exact: matches.get_flag("exact"),
-        full: matches.get_flag("full"),
-        ignore_case: matches.get_flag("ignore-case"),
-        newest: matches.get_flag("newest"),
-        oldest: matches.get_flag("oldest"),
-        parent: matches
-            .get_many::<u64>("parent")
-            .map(|parents| parents.copied().collect()),
-        runstates: matches.get_one::<String>("runstates").cloned(),
-        older: matches.get_one::<u64>("older").copied(),
-        terminal: matches.get_many::<String>("terminal").map(|ttys| {
-            ttys.cloned()
-                .flat_map(Teletype::try_from)
-                .collect::<HashSet<_>>()
-        }),
+        force: matches.get_flag("force"),
+        ignore_ancestors: matches.get_flag("ignore-ancestors"),
     };
 
-    if (!settings.newest
-        && !settings.oldest
-        && settings.runstates.is_none()
-        && settings.older.is_none()
-        && settings.parent.is_none()
-        && settings.terminal.is_none())
-        && pattern.is_empty()
-    {
+    if !selection.has_criteria() {
         return Err(USimpleError::new(
             2,
             "no matching criteria specified\nTry `pkill --help' for more information.",
@@ -102,45 +78,68 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         15_usize //SIGTERM
     };
 
+    // EXIT is signal 0, which only checks that the process could be signalled
     #[cfg(unix)]
-    let sig_name = signal_name_by_value(sig_num);
-    // Signal does not support converting from EXIT
-    // Instead, nix::signal::kill expects Option::None to properly handle EXIT
-    #[cfg(unix)]
-    let sig: Option<Signal> = if sig_name.is_some_and(|name| name == "EXIT") {
-        None
-    } else {
-        let sig = (sig_num as i32)
-            .try_into()
-            .map_err(|e| std::io::Error::from_raw_os_error(e as i32))?;
-        Some(sig)
-    };
+    let sig = sig_num as i32;
 
-    // Collect pids
-    let pids = {
-        let mut pids = collect_matched_pids(&settings);
+    check_proc_root(selection.proc_root())?;
+
+    // Collect pids, protected ones and those without a handler are left out before
+    // -n and -o pick from the matches.
+    let ancestors = ancestors(selection.proc_root(), std::process::id() as usize);
+    #[cfg(unix)]
+    let require_handler = matches.get_flag("require-handler");
+    let pids = find_matching_pids_with(&selection, |pid| {
         #[cfg(unix)]
-        if matches.get_flag("require-handler") {
-            pids.retain(|pid| {
-                let mask =
-                    u32::from_str_radix(pid.clone().status().get("SigCgt").unwrap(), 16).unwrap();
-                mask & (1 << sig_num) != 0
-            });
+        if require_handler {
+            let mask =
+                u64::from_str_radix(pid.clone().status().get("SigCgt").unwrap(), 16).unwrap();
+            // Bit n - 1 of the mask stands for signal n
+            let handled = sig_num
+                .checked_sub(1)
+                .is_some_and(|bit| mask & (1 << bit) != 0);
+            if !handled {
+                return false;
+            }
         }
-        if pids.is_empty() {
-            uucore::error::set_exit_code(1);
-            pids
-        } else {
-            process_flag_o_n(&settings, &mut pids)
+        !is_protected(&settings, pid.pid, &ancestors)
+    });
+    if pids.is_empty() {
+        uucore::error::set_exit_code(1);
+    }
+
+    if let Some(&max) = matches.get_one::<usize>("max-matches") {
+        if pids.len() > max {
+            return Err(USimpleError::new(
+                EXIT_TOO_MANY,
+                format!(
+                    "pattern matched {} processes, exceeding --max-matches",
+                    pids.len()
+                ),
+            ));
         }
-    };
+    }
 
     // Send signal
     // TODO: Implement -q
     #[cfg(unix)]
-    let echo = matches.get_flag("echo");
-    #[cfg(unix)]
-    kill(&pids, sig, echo);
+    if matches.get_flag("dry-run") {
+        for pid in &pids {
+            println!(
+                "{} would be {} (pid {})",
+                process_name(pid),
+                outcome(sig),
+                pid.pid
+            );
+        }
+    } else {
+        // A pidfile alone names one specific process, make sure that is the one being signalled.
+        let pidfd = selection.only_pidfile();
+        // Like procps, succeed if at least one of the matches could be signalled.
+        if kill(&pids, sig, matches.get_flag("echo"), pidfd) == 0 {
+            uucore::error::set_exit_code(1);
+        }
+    }
 
     if matches.get_flag("count") {
         println!("{}", pids.len());
@@ -149,144 +148,120 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     Ok(())
 }
 
-/// Try to get the pattern from the command line arguments. Returns an empty string if no pattern
-/// is specified.
-fn try_get_pattern_from(matches: &ArgMatches) -> UResult<String> {
-    let pattern = match matches.get_many::<String>("pattern") {
-        Some(patterns) if patterns.len() > 1 => {
-            return Err(USimpleError::new(
-                2,
-                "only one pattern can be provided\nTry `pgrep --help' for more information.",
-            ))
-        }
-        Some(mut patterns) => patterns.next().unwrap(),
-        None => return Ok(String::new()),
-    };
+/// Translate the command line into the process selection.
+fn selection_from(matches: &ArgMatches) -> UResult<select::Settings> {
+    let mut builder = select::Settings::builder()
+        .patterns(try_get_pattern_from(matches)?)
+        .exact(matches.get_flag("exact"))
+        .full(matches.get_flag("full"))
+        .ignore_case(matches.get_flag("ignore-case"))
+        .inverse(matches.get_flag("inverse"))
+        .newest(matches.get_flag("newest"))
+        .oldest(matches.get_flag("oldest"));
+
+    if let Some(parents) = matches.get_many::<u64>("parent") {
+        builder = builder.parents(parents.copied());
+    }
+    if let Some(path) = matches.get_one::<String>("pidfile") {
+        builder = builder.pidfile(read_pidfile(path, matches.get_flag("logpidfile"))?);
+    }
+    if let Some(runstates) = matches.get_one::<String>("runstates") {
+        builder = builder.runstates(runstates);
+    }
+    if let Some(older) = matches.get_one::<u64>("older") {
+        builder = builder.older(*older as f64);
+    }
+    if let Some(ttys) = matches.get_many::<String>("terminal") {
+        builder = builder.terminals(ttys.cloned().flat_map(Teletype::try_from));
+    }
+    if let Some(cgroups) = matches.get_many::<String>("cgroup") {
+        builder = builder.cgroups(cgroups.cloned());
+    }
+    if let Some(euids) = matches.get_many::<u32>("euid") {
+        builder = builder.euids(euids.copied());
+    }
+    if let Some(uids) = matches.get_many::<u32>("uid") {
+        builder = builder.uids(uids.copied());
+    }
+    if let Some(gids) = matches.get_many::<u32>("group") {
+        builder = builder.gids(gids.copied());
+    }
 
-    let pattern = if matches.get_flag("ignore-case") {
-        &pattern.to_lowercase()
-    } else {
-        pattern
-    };
+    builder
+        .build()
+        .map_err(|e| USimpleError::new(2, e.to_string()))
+}
 
-    let pattern = if matches.get_flag("exact") {
-        &format!("^{}$", pattern)
-    } else {
-        pattern
-    };
+/// Refuse `--inverse` without a narrowing filter or `--force`: it would signal about every
+/// process on the system.
+fn check_inverse(matches: &ArgMatches) -> UResult<()> {
+    if matches.get_flag("inverse")
+        && !matches.get_flag("force")
+        && !NARROWING.iter().any(|id| matches.contains_id(id))
+    {
+        return Err(USimpleError::new(
+            2,
+            "refusing to signal all processes not matching\n\
+             Narrow --inverse down with -u, -U, -G, -t, -P, -F or --cgroup, or use --force.",
+        ));
+    }
+    Ok(())
+}
 
-    Ok(pattern.to_string())
+/// Try to get the pattern from the command line arguments. Returns no pattern if none is
+/// specified.
+fn try_get_pattern_from(matches: &ArgMatches) -> UResult<Option<String>> {
+    match matches.get_many::<String>("pattern") {
+        Some(patterns) if patterns.len() > 1 => Err(USimpleError::new(
+            2,
+            "only one pattern can be provided\nTry `pgrep --help' for more information.",
+        )),
+        Some(mut patterns) => Ok(patterns.next().cloned()),
+        None => Ok(None),
+    }
 }
 
-/// Collect pids with filter construct from command line arguments
-fn collect_matched_pids(settings: &Settings) -> Vec<ProcessInformation> {
-    // Filtration general parameters
-    let filtered: Vec<ProcessInformation> = {
-        let mut tmp_vec = Vec::new();
+/// Pids of the parent of `pid`, its parent and so on up to pid 1, as seen in the same proc
+/// tree the processes are matched in.
+fn ancestors(proc_root: &Path, pid: usize) -> HashSet<usize> {
+    let mut ancestors = HashSet::new();
+    let mut current = pid;
 
-        for mut pid in walk_process().collect::<Vec<_>>() {
-            let run_state_matched = match (&settings.runstates, pid.run_state()) {
-                (Some(arg_run_states), Ok(pid_state)) => {
-                    arg_run_states.contains(&pid_state.to_string())
-                }
-                (_, Err(_)) => false,
-                _ => true,
-            };
-
-            let binding = pid.status();
-            let name = binding.get("Name").unwrap();
-            let name = if settings.ignore_case {
-                name.to_lowercase()
-            } else {
-                name.into()
-            };
-            let pattern_matched = {
-                let want = if settings.exact {
-                    // Equals `Name` in /proc/<pid>/status
-                    // The `unwrap` operation must succeed
-                    // because the REGEX has been verified as correct in `uumain`.
-                    &name
-                } else if settings.full {
-                    // Equals `cmdline` in /proc/<pid>/cmdline
-                    &pid.cmdline
-                } else {
-                    // From manpage:
-                    // The process name used for matching is limited to the 15 characters present in the output of /proc/pid/stat.
-                    &pid.proc_stat()[..15]
-                };
-
-                REGEX.get().unwrap().is_match(want)
-            };
-
-            let tty_matched = match &settings.terminal {
-                Some(ttys) => ttys.contains(&pid.tty()),
-                None => true,
-            };
-
-            let arg_older = settings.older.unwrap_or(0);
-            let older_matched = pid.start_time().unwrap() >= arg_older;
-
-            // the PPID is the fourth field in /proc/<PID>/stat
-            // (https://www.kernel.org/doc/html/latest/filesystems/proc.html#id10)
-            let stat = pid.stat();
-            let ppid = stat.get(3);
-            let parent_matched = match (&settings.parent, ppid) {
-                (Some(parents), Some(ppid)) => parents.contains(&ppid.parse::<u64>().unwrap()),
-                _ => true,
-            };
-
-            if run_state_matched
-                && pattern_matched
-                && tty_matched
-                && older_matched
-                && parent_matched
-            {
-                tmp_vec.push(pid);
-            }
+    while current > 1 {
+        let Ok(mut info) = ProcessInformation::try_new(proc_root.join(current.to_string())) else {
+            break;
+        };
+        let Some(ppid) = info.stat().get(3).and_then(|it| it.parse::<usize>().ok()) else {
+            break;
+        };
+
+        // Guard against cycles if a pid got reused while walking.
+        if ppid == 0 || !ancestors.insert(ppid) {
+            break;
         }
-        tmp_vec
-    };
+        current = ppid;
+    }
 
-    filtered
+    ancestors
 }
 
-/// Sorting pids for flag `-o` and `-n`.
-///
-/// This function can also be used as a filter to filter out process information.
-fn process_flag_o_n(
-    settings: &Settings,
-    pids: &mut [ProcessInformation],
-) -> Vec<ProcessInformation> {
-    if settings.oldest || settings.newest {
-        pids.sort_by(|a, b| {
-            b.clone()
-                .start_time()
-                .unwrap()
-                .cmp(&a.clone().start_time().unwrap())
-        });
-
-        let start_time = if settings.newest {
-            pids.first().cloned().unwrap().start_time().unwrap()
-        } else {
-            pids.last().cloned().unwrap().start_time().unwrap()
-        };
+/// Whether the process must be left alone: pkill itself always, pid 1 and our
+/// ancestors unless `--force` is given.
+fn is_protected(settings: &Settings, pid: usize, ancestors: &HashSet<usize>) -> bool {
+    if pid == std::process::id() as usize {
+        return true;
+    }
 
-        // There might be some process start at same time, so need to be filtered.
-        let mut filtered = pids
-            .iter()
-            .filter(|it| (*it).clone().start_time().unwrap() == start_time)
-            .collect::<Vec<_>>();
+    if pid == 1 && !settings.force {
+        show_warning!("skipping pid 1, use --force to signal it");
+        return true;
+    }
 
-        if settings.newest {
-            filtered.sort_by(|a, b| b.pid.cmp(&a.pid));
-        } else {
-            filtered.sort_by(|a, b| a.pid.cmp(&b.pid));
-        }
+    (!settings.force || settings.ignore_ancestors) && ancestors.contains(&pid)
+}
 
-        vec![filtered.first().cloned().unwrap().clone()]
-    } else {
-        pids.to_vec()
-    }
+fn process_name(pid: &ProcessInformation) -> &str {
+    pid.cmdline.split(" ").next().unwrap_or("")
 }
 
 #[cfg(unix)]
@@ -297,7 +272,7 @@ fn handle_obsolete(args: &mut Vec<String>) -> Option<usize> {
         let slice = args[1].as_str();
         if let Some(signal) = slice.strip_prefix('-') {
             // Check if it is a valid signal
-            let opt_signal = signal_by_name_or_value(signal);
+            let opt_signal = signal_by_name(signal);
             if opt_signal.is_some() {
                 // remove the signal before return
                 args.remove(1);
@@ -310,30 +285,122 @@ fn handle_obsolete(args: &mut Vec<String>) -> Option<usize> {
 
 #[cfg(unix)]
 fn parse_signal_value(signal_name: &str) -> UResult<usize> {
-    let optional_signal_value = signal_by_name_or_value(signal_name);
+    let optional_signal_value = signal_by_name(signal_name);
     match optional_signal_value {
         Some(x) => Ok(x),
         None => Err(USimpleError::new(
-            1,
+            2,
             format!("Unknown signal {}", signal_name.quote()),
         )),
     }
 }
 
+/// Look up a signal by name or number, including the real-time signals.
 #[cfg(unix)]
-fn kill(pids: &Vec<ProcessInformation>, sig: Option<Signal>, echo: bool) {
+fn signal_by_name(signal_name: &str) -> Option<usize> {
+    signal_by_name_or_value(signal_name).or_else(|| parse_rt_signal(signal_name))
+}
+
+/// Parse the real-time signal names `RTMIN`, `RTMIN+n`, `RTMAX` and `RTMAX-n`.
+///
+/// Their numbers are only known at runtime, as the C library reserves some real-time
+/// signals for itself.
+#[cfg(target_os = "linux")]
+fn parse_rt_signal(signal_name: &str) -> Option<usize> {
+    let offset = |offset: &str, sign: char| match offset.strip_prefix(sign) {
+        None if offset.is_empty() => Some(0),
+        Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+            n.parse::<u8>().ok().map(i32::from)
+        }
+        _ => None,
+    };
+
+    let name = signal_name.to_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    let (min, max) = (libc::SIGRTMIN(), libc::SIGRTMAX());
+
+    let value = if let Some(rest) = name.strip_prefix("RTMIN") {
+        min + offset(rest, '+')?
+    } else if let Some(rest) = name.strip_prefix("RTMAX") {
+        max - offset(rest, '-')?
+    } else {
+        return None;
+    };
+
+    (min..=max).contains(&value).then_some(value as usize)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn parse_rt_signal(_signal_name: &str) -> Option<usize> {
+    None
+}
+
+/// What sending `sig` does to a process, for `--echo` and `--dry-run`. Signal 0 only
+/// checks that the process exists and may be signalled.
+#[cfg(unix)]
+fn outcome(sig: i32) -> &'static str {
+    if sig == 0 {
+        "checked"
+    } else {
+        "killed"
+    }
+}
+
+/// Signal the processes and return how many of them could be signalled.
+#[cfg(unix)]
+fn kill(pids: &Vec<ProcessInformation>, sig: i32, echo: bool, pidfd: bool) -> usize {
+    let mut signalled = 0;
+
     for pid in pids {
-        if let Err(e) = signal::kill(Pid::from_raw(pid.pid as i32), sig) {
-            show!(Error::from_raw_os_error(e as i32)
-                .map_err_context(|| format!("killing pid {} failed", pid.pid)));
-        } else if echo {
-            println!(
-                "{} killed (pid {})",
-                pid.cmdline.split(" ").next().unwrap_or(""),
-                pid.pid
-            );
+        let result = if pidfd {
+            signal_pidfd(pid, sig)
+        } else {
+            send_signal(pid.pid as i32, sig)
+        };
+
+        match result {
+            Ok(()) => {
+                signalled += 1;
+                if echo {
+                    println!("{} {} (pid {})", process_name(pid), outcome(sig), pid.pid);
+                }
+            }
+            // The process exited since it was matched, there is nothing left to signal.
+            Err(e) if e.raw_os_error() == Some(libc::ESRCH) => {}
+            Err(e) => {
+                let action = if sig == 0 { "checking" } else { "killing" };
+                show_error!(
+                    "{}",
+                    e.map_err_context(|| format!("{action} pid {} failed", pid.pid))
+                );
+            }
         }
     }
+
+    signalled
+}
+
+/// Signal `pid` through a pidfd, see [pidfd::signal_pid].
+#[cfg(target_os = "linux")]
+fn signal_pidfd(pid: &ProcessInformation, sig: i32) -> Result<(), Error> {
+    let start_time = pid.clone().start_time()?;
+
+    pidfd::signal_pid(&pidfd::Linux, pid.pid as i32, start_time, sig)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn signal_pidfd(pid: &ProcessInformation, sig: i32) -> Result<(), Error> {
+    send_signal(pid.pid as i32, sig)
+}
+
+#[cfg(unix)]
+fn send_signal(pid: i32, sig: i32) -> Result<(), Error> {
+    // SAFETY: kill has no memory arguments.
+    if unsafe { libc::kill(pid, sig) } < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(())
 }
 
 #[allow(clippy::cognitive_complexity)]
@@ -356,7 +423,7 @@ pub fn uu_app() -> Command {
                 .value_parser(clap::value_parser!(u64)),
             arg!(-G --group <GID>          "match real group IDs")
                 .value_delimiter(',')
-                .value_parser(clap::value_parser!(u64)),
+                .value_parser(parse_gid_or_group_name),
             arg!(-i --"ignore-case"        "match case insensitively"),
             arg!(-n --newest               "select most recently started"),
             arg!(-o --oldest               "select least recently started"),
@@ -373,15 +440,21 @@ pub fn uu_app() -> Command {
                 .value_delimiter(','),
             arg!(-u --euid <ID>            "match by effective IDs")
                 .value_delimiter(',')
-                .value_parser(clap::value_parser!(u64)),
+                .value_parser(parse_uid_or_username(geteuid)),
             arg!(-U --uid <ID>             "match by real IDs")
                 .value_delimiter(',')
-                .value_parser(clap::value_parser!(u64)),
+                .value_parser(parse_uid_or_username(getuid)),
+            arg!(-v --inverse              "negates the matching, requires a narrowing filter or --force"),
             arg!(-x --exact                "match exactly with the command name"),
             arg!(-F --pidfile <file>       "read PIDs from file"),
-            arg!(-L --logpidfile           "fail if PID file is not locked"),
+            arg!(-L --logpidfile           "fail if PID file is not locked")
+                .requires("pidfile"),
             arg!(-r --runstates <state>    "match runstates [D,S,Z,...]"),
-            arg!(-A --"ignore-ancestors"   "exclude our ancestors from results"),
+            arg!(-A --"ignore-ancestors"   "exclude our ancestors from results, even with --force"),
+            arg!(   --force                "also signal pid 1 and our ancestors, which are skipped by default"),
+            arg!(   --"dry-run"            "only print what would be signalled"),
+            arg!(   --"max-matches" <N>    "signal nothing and fail if more than N processes match")
+                .value_parser(clap::value_parser!(usize)),
             arg!(--cgroup <grp>            "match by cgroup v2 names")
                 .value_delimiter(','),
             arg!(--ns <PID>                "match the processes that belong to the same namespace as <pid>"),
@@ -396,3 +469,66 @@ pub fn uu_app() -> Command {
                 .index(1),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ancestors() {
+        let proc_root =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../../tests/fixtures/pgrep/proc");
+
+        assert_eq!(ancestors(&proc_root, 300), HashSet::from([200, 100]));
+        assert_eq!(ancestors(&proc_root, 100), HashSet::new());
+        assert_eq!(ancestors(&proc_root, 999), HashSet::new());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_rt_signal() {
+        let (min, max) = (libc::SIGRTMIN() as usize, libc::SIGRTMAX() as usize);
+
+        assert_eq!(parse_rt_signal("RTMIN"), Some(min));
+        assert_eq!(parse_rt_signal("RTMIN+3"), Some(min + 3));
+        assert_eq!(parse_rt_signal("SIGRTMIN+3"), Some(min + 3));
+        assert_eq!(parse_rt_signal("rtmin+3"), Some(min + 3));
+        assert_eq!(parse_rt_signal("RTMAX"), Some(max));
+        assert_eq!(parse_rt_signal("RTMAX-1"), Some(max - 1));
+        assert_eq!(parse_rt_signal(&format!("RTMIN+{}", max - min)), Some(max));
+        assert_eq!(parse_rt_signal(&format!("RTMAX-{}", max - min)), Some(min));
+
+        assert_eq!(parse_rt_signal("RTMIN+100"), None);
+        assert_eq!(parse_rt_signal("RTMAX+1"), None);
+        assert_eq!(parse_rt_signal("RTMIN-1"), None);
+        assert_eq!(parse_rt_signal("RTMIN+"), None);
+        assert_eq!(parse_rt_signal("RTMIN++1"), None);
+        assert_eq!(parse_rt_signal("RTMIN+99999999999"), None);
+        assert_eq!(parse_rt_signal("RT"), None);
+        assert_eq!(parse_rt_signal("TERM"), None);
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "linux")))]
+    fn test_parse_rt_signal() {
+        assert_eq!(parse_rt_signal("RTMIN"), None);
+        assert_eq!(parse_rt_signal("RTMAX-1"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_parse_signal_value() {
+        assert_eq!(parse_signal_value("TERM").unwrap(), 15);
+        assert_eq!(parse_signal_value("9").unwrap(), 9);
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            parse_signal_value("RTMIN+1").unwrap(),
+            libc::SIGRTMIN() as usize + 1
+        );
+
+        assert_eq!(
+            parse_signal_value("RTMIN+100").unwrap_err().to_string(),
+            "Unknown signal 'RTMIN+100'"
+        );
+    }
+}
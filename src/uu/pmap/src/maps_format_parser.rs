@@ -18,6 +18,17 @@ pub struct MapLine {
     pub mapping: String,
 }
 
+// How the "Mapping" column names a region. The default, device and extended formats share it,
+// like procps does, so the same region reads the same in all of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MappingFormat {
+    // The file name, or "[ anon ]" and "[ stack ]" for regions not backed by a file.
+    #[default]
+    Name,
+    // Like `Name`, but with the full path of files (`-p`).
+    Path,
+}
+
 // Represents a set of permissions from the "perms" column of /proc/<PID>/maps.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Perms {
@@ -40,6 +51,20 @@ impl From<&str> for Perms {
     }
 }
 
+impl Perms {
+    // Whether all permissions named in `flags`, any of "rwxs", are set. Other characters are
+    // never satisfied, `--perm` rejects them beforehand.
+    pub fn satisfies(&self, flags: &str) -> bool {
+        flags.chars().all(|flag| match flag {
+            'r' => self.readable,
+            'w' => self.writable,
+            'x' => self.executable,
+            's' => self.shared,
+            _ => false,
+        })
+    }
+}
+
 // Please note: While `Perms` has four boolean fields, it's string representation has five
 // characters because pmap's default and device formats use five characters for the perms,
 // with the last character always being '-'.
@@ -63,7 +88,7 @@ impl fmt::Display for Perms {
 // # Errors
 //
 // Will return an `Error` if the format is incorrect.
-pub fn parse_map_line(line: &str) -> Result<MapLine, Error> {
+pub fn parse_map_line(line: &str, format: MappingFormat) -> Result<MapLine, Error> {
     let (memory_range, rest) = line
         .split_once(' ')
         .ok_or_else(|| Error::from(ErrorKind::InvalidData))?;
@@ -87,7 +112,7 @@ pub fn parse_map_line(line: &str) -> Result<MapLine, Error> {
     // skip the "inode" column
     let mapping: String = rest.splitn(2, ' ').skip(1).collect();
     let mapping = mapping.trim_ascii_start();
-    let mapping = parse_mapping(mapping);
+    let mapping = parse_mapping(mapping, format);
 
     Ok(MapLine {
         address,
@@ -121,18 +146,26 @@ fn parse_device(device: &str) -> Result<String, Error> {
     Ok(format!("{major:0>3}:{minor:0>5}"))
 }
 
-fn parse_mapping(mapping: &str) -> String {
-    if mapping == "[stack]" {
-        return "  [ stack ]".into();
-    }
-
-    if mapping.is_empty() || mapping.starts_with('[') || mapping.starts_with("anon") {
-        return "  [ anon ]".into();
-    }
-
-    match mapping.rsplit_once('/') {
-        Some((_, name)) => name.into(),
-        None => mapping.into(),
+// Only mappings with a path are backed by a file, everything else ("[heap]", "[vdso]",
+// "anon_inode:...", or nothing at all) is anonymous memory. Deleted files keep their
+// " (deleted)" suffix.
+fn parse_mapping(mapping: &str, format: MappingFormat) -> String {
+    let Some(path_start) = mapping.find('/') else {
+        return if mapping == "[stack]" {
+            "  [ stack ]".into()
+        } else {
+            "  [ anon ]".into()
+        };
+    };
+
+    match format {
+        MappingFormat::Path => mapping[path_start..].into(),
+        MappingFormat::Name => match mapping.rsplit_once('/') {
+            // procps keeps the slash of a path ending in one
+            Some((_, "")) => "/".into(),
+            Some((_, name)) => name.into(),
+            None => unreachable!(),
+        },
     }
 }
 
@@ -165,6 +198,31 @@ mod test {
         assert_eq!("rwx--", Perms::from("rwxp").to_string());
     }
 
+    #[test]
+    fn test_perms_satisfies() {
+        let all = [
+            "r", "w", "x", "s", "rw", "rx", "rs", "wx", "ws", "xs", "rwx", "rws", "rxs", "wxs",
+            "rwxs",
+        ];
+
+        for perms in [
+            "---p", "r--p", "rw-p", "r-xp", "rwxp", "rw-s", "--xs", "rwxs",
+        ] {
+            let parsed = Perms::from(perms);
+            for flags in all {
+                let expected = flags.chars().all(|flag| perms.contains(flag));
+                assert_eq!(parsed.satisfies(flags), expected, "{perms} {flags}");
+                // The order of the flags doesn't matter.
+                let reversed: String = flags.chars().rev().collect();
+                assert_eq!(parsed.satisfies(&reversed), expected, "{perms} {reversed}");
+            }
+        }
+
+        assert!(Perms::from("rwxs").satisfies(""));
+        assert!(!Perms::from("rwxs").satisfies("p"));
+        assert!(!Perms::from("rwxs").satisfies("rq"));
+    }
+
     #[test]
     fn test_parse_map_line() {
         let data = [
@@ -199,13 +257,16 @@ mod test {
         ];
 
         for (expected_map_line, line) in data {
-            assert_eq!(expected_map_line, parse_map_line(line).unwrap());
+            assert_eq!(
+                expected_map_line,
+                parse_map_line(line, MappingFormat::Name).unwrap()
+            );
         }
     }
 
     #[test]
     fn test_parse_map_line_with_invalid_format() {
-        assert!(parse_map_line("invalid_format").is_err());
+        assert!(parse_map_line("invalid_format", MappingFormat::Name).is_err());
     }
 
     #[test]
@@ -243,14 +304,36 @@ mod test {
 
     #[test]
     fn test_parse_mapping() {
-        assert_eq!("  [ anon ]", parse_mapping(""));
-        assert_eq!("  [ anon ]", parse_mapping("[vvar]"));
-        assert_eq!("  [ anon ]", parse_mapping("[vdso]"));
-        assert_eq!("  [ anon ]", parse_mapping("anon_inode:i915.gem"));
-        assert_eq!("  [ stack ]", parse_mapping("[stack]"));
-        assert_eq!(
-            "ld-linux-x86-64.so.2",
-            parse_mapping("/usr/lib/ld-linux-x86-64.so.2")
-        );
+        use MappingFormat::{Name, Path};
+
+        let data = [
+            ("", "  [ anon ]", "  [ anon ]"),
+            ("[heap]", "  [ anon ]", "  [ anon ]"),
+            ("[vvar]", "  [ anon ]", "  [ anon ]"),
+            ("[vdso]", "  [ anon ]", "  [ anon ]"),
+            ("anon_inode:i915.gem", "  [ anon ]", "  [ anon ]"),
+            ("[stack]", "  [ stack ]", "  [ stack ]"),
+            (
+                "/usr/lib/ld-linux-x86-64.so.2",
+                "ld-linux-x86-64.so.2",
+                "/usr/lib/ld-linux-x86-64.so.2",
+            ),
+            (
+                "/tmp/scratch (deleted)",
+                "scratch (deleted)",
+                "/tmp/scratch (deleted)",
+            ),
+            (
+                "/memfd:wayland-shm (deleted)",
+                "memfd:wayland-shm (deleted)",
+                "/memfd:wayland-shm (deleted)",
+            ),
+            ("/", "/", "/"),
+        ];
+
+        for (mapping, name, path) in data {
+            assert_eq!(name, parse_mapping(mapping, Name), "{mapping}");
+            assert_eq!(path, parse_mapping(mapping, Path), "{mapping}");
+        }
     }
 }
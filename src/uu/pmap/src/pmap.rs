@@ -3,15 +3,19 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use clap::{crate_version, Arg, ArgAction, Command};
-use maps_format_parser::{parse_map_line, MapLine};
+use clap::{crate_version, Arg, ArgAction, ArgGroup, Command};
+use maps_format_parser::{parse_map_line, MapLine, MappingFormat};
+use smaps_format_parser::{parse_smaps, SmapTable};
 use std::env;
 use std::fs;
 use std::io::Error;
-use uucore::error::{set_exit_code, UResult};
+use std::path::PathBuf;
+use uu_pgrep::pidfile::read_pidfile;
+use uucore::error::{set_exit_code, UResult, USimpleError};
 use uucore::{format_usage, help_about, help_usage};
 
 mod maps_format_parser;
+mod smaps_format_parser;
 
 const ABOUT: &str = help_about!("pmap.md");
 const USAGE: &str = help_usage!("pmap.md");
@@ -29,17 +33,88 @@ mod options {
     pub const QUIET: &str = "quiet";
     pub const SHOW_PATH: &str = "show-path";
     pub const RANGE: &str = "range";
+    pub const SUMMARY: &str = "summary";
+    pub const PROC_ROOT: &str = "proc-root";
+    pub const PIDFILE: &str = "pidfile";
+    pub const PERM: &str = "perm";
+}
+
+/// Base directory of the proc filesystem, `/proc` unless overridden with `--proc-root`,
+/// e.g. to inspect a bind-mounted proc tree or a test fixture.
+struct ProcRoot(PathBuf);
+
+impl Default for ProcRoot {
+    fn default() -> Self {
+        Self(PathBuf::from("/proc"))
+    }
+}
+
+impl ProcRoot {
+    fn pid_file(&self, pid: &str, name: &str) -> PathBuf {
+        self.0.join(pid).join(name)
+    }
+}
+
+/// Read the pid from the pidfile at `path`, it must belong to a running process.
+fn pid_from_pidfile(proc_root: &ProcRoot, path: &str) -> UResult<String> {
+    // Same messages as pgrep, but pmap has no separate exit code for fatal errors.
+    let pid = read_pidfile(path, false).map_err(|e| USimpleError::new(1, e.to_string()))?;
+
+    if pid <= 0 {
+        return Err(USimpleError::new(1, format!("Pidfile {path} not valid")));
+    }
+    if !proc_root.0.join(pid.to_string()).is_dir() {
+        return Err(USimpleError::new(
+            1,
+            format!("Pidfile {path} refers to process {pid}, which does not exist"),
+        ));
+    }
+
+    Ok(pid.to_string())
+}
+
+// Validates the flags given with `--perm`, the permissions a mapping must all have.
+fn parse_perm_flags(value: &str) -> Result<String, String> {
+    if value.is_empty() {
+        return Err("no permission flags given, expected any of rwxs".into());
+    }
+
+    match value.chars().find(|flag| !"rwxs".contains(*flag)) {
+        Some(flag) => Err(format!(
+            "invalid permission flag '{flag}', expected any of rwxs"
+        )),
+        None => Ok(value.into()),
+    }
 }
 
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
-    let pids = matches
-        .get_many::<String>(options::PID)
-        .expect("PID required");
+    let proc_root = matches
+        .get_one::<String>(options::PROC_ROOT)
+        .map(|root| ProcRoot(root.into()))
+        .unwrap_or_default();
+    let pids = match matches.get_one::<String>(options::PIDFILE) {
+        Some(path) => vec![pid_from_pidfile(&proc_root, path)?],
+        None => matches
+            .get_many::<String>(options::PID)
+            .expect("PID required")
+            .cloned()
+            .collect(),
+    };
+    let format = if matches.get_flag(options::SHOW_PATH) {
+        MappingFormat::Path
+    } else {
+        MappingFormat::Name
+    };
+    // Mappings without these permissions are left out, totals included.
+    let perm = matches
+        .get_one::<String>(options::PERM)
+        .map_or("", String::as_str);
+    let quiet = matches.get_flag(options::QUIET);
 
-    for pid in pids {
-        match parse_cmdline(pid) {
+    for pid in &pids {
+        match parse_cmdline(&proc_root, pid) {
             Ok(cmdline) => {
                 println!("{pid}:   {cmdline}");
             }
@@ -49,21 +124,38 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             }
         }
 
-        if matches.get_flag(options::DEVICE) {
-            output_device_format(pid).map_err(|_| set_exit_code(1)).ok();
+        let result = if matches.get_flag(options::EXTENDED) {
+            read_smaps(&proc_root, pid, format, perm).map(|table| {
+                output_extended_format(&table, quiet);
+                Some(table)
+            })
+        } else if matches.get_flag(options::DEVICE) {
+            output_device_format(&proc_root, pid, format, perm).map(|()| None)
         } else {
-            output_default_format(pid)
-                .map_err(|_| set_exit_code(1))
-                .ok();
+            output_default_format(&proc_root, pid, format, perm).map(|()| None)
+        };
+
+        let table = match result {
+            Ok(table) => table,
+            Err(_) => {
+                set_exit_code(1);
+                continue;
+            }
+        };
+        if matches.get_flag(options::SUMMARY) && !quiet {
+            // -X and --XX show the default format for now, their totals still come from smaps.
+            match table.map_or_else(|| read_smaps(&proc_root, pid, format, perm), Ok) {
+                Ok(table) => output_summary(&table),
+                Err(_) => set_exit_code(1),
+            }
         }
     }
 
     Ok(())
 }
 
-fn parse_cmdline(pid: &str) -> Result<String, Error> {
-    let path = format!("/proc/{pid}/cmdline");
-    let contents = fs::read(path)?;
+fn parse_cmdline(proc_root: &ProcRoot, pid: &str) -> Result<String, Error> {
+    let contents = fs::read(proc_root.pid_file(pid, "cmdline"))?;
     // Command line arguments are separated by null bytes.
     // Replace them with spaces for display.
     let cmdline = contents
@@ -75,45 +167,75 @@ fn parse_cmdline(pid: &str) -> Result<String, Error> {
     Ok(cmdline.into())
 }
 
-fn process_maps<F>(pid: &str, mut process_line: F) -> Result<(), Error>
+fn process_maps<F>(
+    proc_root: &ProcRoot,
+    pid: &str,
+    format: MappingFormat,
+    perm: &str,
+    mut process_line: F,
+) -> Result<(), Error>
 where
     F: FnMut(&MapLine),
 {
-    let path = format!("/proc/{pid}/maps");
-    let contents = fs::read_to_string(path)?;
+    let contents = fs::read_to_string(proc_root.pid_file(pid, "maps"))?;
 
     for line in contents.lines() {
-        let map_line = parse_map_line(line)?;
-        process_line(&map_line);
+        let map_line = parse_map_line(line, format)?;
+        if map_line.perms.satisfies(perm) {
+            process_line(&map_line);
+        }
     }
 
     Ok(())
 }
 
-fn output_default_format(pid: &str) -> Result<(), Error> {
+fn output_default_format(
+    proc_root: &ProcRoot,
+    pid: &str,
+    format: MappingFormat,
+    perm: &str,
+) -> Result<(), Error> {
     let mut total = 0;
+    let mut rows = vec![];
 
-    process_maps(pid, |map_line| {
-        println!(
-            "{} {:>6}K {} {}",
-            map_line.address, map_line.size_in_kb, map_line.perms, map_line.mapping
-        );
+    process_maps(proc_root, pid, format, perm, |map_line| {
+        rows.push((
+            map_line.address.clone(),
+            map_line.size_in_kb,
+            format!("{} {}", map_line.perms, map_line.mapping),
+        ));
         total += map_line.size_in_kb;
     })?;
 
-    println!(" total {total:>16}K");
+    // The size column grows with the largest mapping, e.g. huge GPU or DAX mappings, and the
+    // total stays right-aligned with it below the address column.
+    let width = rows
+        .iter()
+        .map(|(_, size, _)| size.to_string().len())
+        .max()
+        .unwrap_or_default()
+        .max(6);
+    for (address, size, rest) in rows {
+        println!("{address} {size:>width$}K {rest}");
+    }
+    println!(" total {total:>0$}K", width + 10);
 
     Ok(())
 }
 
-fn output_device_format(pid: &str) -> Result<(), Error> {
+fn output_device_format(
+    proc_root: &ProcRoot,
+    pid: &str,
+    format: MappingFormat,
+    perm: &str,
+) -> Result<(), Error> {
     let mut total_mapped = 0;
     let mut total_writeable_private = 0;
     let mut total_shared = 0;
 
     println!("Address           Kbytes Mode  Offset           Device    Mapping");
 
-    process_maps(pid, |map_line| {
+    process_maps(proc_root, pid, format, perm, |map_line| {
         println!(
             "{} {:>7} {} {} {} {}",
             map_line.address,
@@ -141,6 +263,55 @@ fn output_device_format(pid: &str) -> Result<(), Error> {
     Ok(())
 }
 
+fn read_smaps(
+    proc_root: &ProcRoot,
+    pid: &str,
+    format: MappingFormat,
+    perm: &str,
+) -> Result<SmapTable, Error> {
+    let contents = fs::read_to_string(proc_root.pid_file(pid, "smaps"))?;
+    let mut table = parse_smaps(&contents, format)?;
+    table
+        .entries
+        .retain(|entry| entry.map_line.perms.satisfies(perm));
+
+    Ok(table)
+}
+
+fn output_extended_format(table: &SmapTable, quiet: bool) {
+    if !quiet {
+        println!("Address           Kbytes     RSS   Dirty Mode  Mapping");
+    }
+
+    for entry in &table.entries {
+        println!(
+            "{} {:>7} {:>7} {:>7} {} {}",
+            entry.map_line.address,
+            entry.map_line.size_in_kb,
+            entry.rss_in_kb,
+            entry.dirty_in_kb(),
+            entry.map_line.perms,
+            entry.map_line.mapping
+        );
+    }
+
+    if !quiet {
+        // procps prints the separator with a trailing space.
+        println!("---------------- ------- ------- ------- ");
+        println!(
+            "total kB {:>15} {:>7} {:>7}",
+            table.total_size_in_kb(),
+            table.total_rss_in_kb(),
+            table.total_dirty_in_kb()
+        );
+    }
+}
+
+fn output_summary(table: &SmapTable) {
+    println!("anon total: {}K", table.total_anonymous_in_kb());
+    println!("file total: {}K", table.total_file_in_kb());
+}
+
 pub fn uu_app() -> Command {
     Command::new(env!("CARGO_PKG_NAME"))
         .version(crate_version!())
@@ -150,10 +321,18 @@ pub fn uu_app() -> Command {
         .arg(
             Arg::new(options::PID)
                 .help("Process ID")
-                .required_unless_present_any(["create-rc", "create-rc-to"]) // Adjusted for -n, -N note
+                .required_unless_present_any(["create-rc", "create-rc-to", "pidfile"]) // Adjusted for -n, -N note
                 .action(ArgAction::Append)
-                .conflicts_with_all(["create-rc", "create-rc-to"]),
+                .conflicts_with_all(["create-rc", "create-rc-to", "pidfile"]),
         ) // Ensure pid is not used with -n, -N
+        .arg(
+            Arg::new(options::PIDFILE)
+                .short('F')
+                .long("pidfile")
+                .value_name("FILE")
+                .help("read the process ID from FILE")
+                .conflicts_with_all(["create-rc", "create-rc-to"]),
+        )
         .arg(
             Arg::new(options::EXTENDED)
                 .short('x')
@@ -222,6 +401,36 @@ pub fn uu_app() -> Command {
                 .help("show path in the mapping")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::SUMMARY)
+                .long("summary")
+                .help("show anonymous and file-backed totals")
+                .requires("extended-formats")
+                .action(ArgAction::SetTrue),
+        )
+        .group(
+            ArgGroup::new("extended-formats")
+                .args([
+                    options::EXTENDED,
+                    options::MORE_EXTENDED,
+                    options::MOST_EXTENDED,
+                ])
+                .multiple(true),
+        )
+        .arg(
+            Arg::new(options::PERM)
+                .long("perm")
+                .value_name("FLAGS")
+                .value_parser(parse_perm_flags)
+                .help("show only mappings with all of the permissions in FLAGS (rwxs)"),
+        )
+        .arg(
+            Arg::new(options::PROC_ROOT)
+                .long("proc-root")
+                .value_name("DIR")
+                .hide(true)
+                .help("read process information from DIR instead of /proc"),
+        )
         .arg(
             Arg::new(options::RANGE)
                 .short('A')
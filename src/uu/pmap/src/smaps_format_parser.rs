@@ -0,0 +1,205 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::maps_format_parser::{parse_map_line, MapLine, MappingFormat};
+use std::io::{Error, ErrorKind};
+
+// Represents a mapping from /proc/<PID>/smaps with the memory counters used by the extended
+// format. The counters are in KB.
+#[derive(Debug, PartialEq)]
+pub struct SmapEntry {
+    pub map_line: MapLine,
+    pub rss_in_kb: u64,
+    pub shared_dirty_in_kb: u64,
+    pub private_dirty_in_kb: u64,
+    pub anonymous_in_kb: u64,
+}
+
+impl SmapEntry {
+    // Like procps, "Dirty" is the sum of the shared and private dirty pages. Swapped out pages
+    // are not part of it.
+    pub fn dirty_in_kb(&self) -> u64 {
+        self.shared_dirty_in_kb + self.private_dirty_in_kb
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SmapTable {
+    pub entries: Vec<SmapEntry>,
+}
+
+impl SmapTable {
+    pub fn total_size_in_kb(&self) -> u64 {
+        self.entries.iter().map(|it| it.map_line.size_in_kb).sum()
+    }
+
+    pub fn total_rss_in_kb(&self) -> u64 {
+        self.entries.iter().map(|it| it.rss_in_kb).sum()
+    }
+
+    pub fn total_dirty_in_kb(&self) -> u64 {
+        self.entries.iter().map(SmapEntry::dirty_in_kb).sum()
+    }
+
+    // Anonymous memory as the kernel counts it, which includes the copy-on-write pages of
+    // private file mappings.
+    pub fn total_anonymous_in_kb(&self) -> u64 {
+        self.entries.iter().map(|it| it.anonymous_in_kb).sum()
+    }
+
+    // Everything mapped that isn't anonymous memory.
+    pub fn total_file_in_kb(&self) -> u64 {
+        self.total_size_in_kb()
+            .saturating_sub(self.total_anonymous_in_kb())
+    }
+}
+
+// Parses the content of /proc/<PID>/smaps. Each mapping starts with a line in the format of
+// /proc/<PID>/maps, followed by "Key: value" lines. Keys not needed by pmap are ignored.
+//
+// # Errors
+//
+// Will return an `Error` if the format is incorrect.
+pub fn parse_smaps(contents: &str, format: MappingFormat) -> Result<SmapTable, Error> {
+    let mut table = SmapTable::default();
+
+    for line in contents.lines() {
+        let is_field = line
+            .split_ascii_whitespace()
+            .next()
+            .is_some_and(|it| it.ends_with(':'));
+
+        if !is_field {
+            table.entries.push(SmapEntry {
+                map_line: parse_map_line(line, format)?,
+                rss_in_kb: 0,
+                shared_dirty_in_kb: 0,
+                private_dirty_in_kb: 0,
+                anonymous_in_kb: 0,
+            });
+            continue;
+        }
+
+        let entry = table
+            .entries
+            .last_mut()
+            .ok_or_else(|| Error::from(ErrorKind::InvalidData))?;
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| Error::from(ErrorKind::InvalidData))?;
+
+        let counter = match key {
+            "Rss" => &mut entry.rss_in_kb,
+            "Shared_Dirty" => &mut entry.shared_dirty_in_kb,
+            "Private_Dirty" => &mut entry.private_dirty_in_kb,
+            "Anonymous" => &mut entry.anonymous_in_kb,
+            _ => continue,
+        };
+        *counter = parse_kb(value)?;
+    }
+
+    Ok(table)
+}
+
+// Parses values like "   132 kB".
+fn parse_kb(value: &str) -> Result<u64, Error> {
+    value
+        .trim()
+        .trim_end_matches("kB")
+        .trim_end()
+        .parse()
+        .map_err(|_| Error::from(ErrorKind::InvalidData))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SMAPS: &str = "\
+564d7f5e3000-564d7f5e5000 r--p 00000000 fe:00 318229                     /usr/bin/sleep
+Size:                  8 kB
+Rss:                   8 kB
+Shared_Dirty:          0 kB
+Private_Clean:         8 kB
+Private_Dirty:         0 kB
+Anonymous:             0 kB
+Swap:                  0 kB
+VmFlags: rd mr mw me
+564dbe2da000-564dbe2fb000 rw-p 00000000 00:00 0                          [heap]
+Size:                132 kB
+Rss:                  12 kB
+Shared_Dirty:          4 kB
+Private_Dirty:         4 kB
+Anonymous:             8 kB
+Swap:                 64 kB
+THPeligible:           0
+VmFlags: rd wr mr mw me ac
+7f3c2a22c000-7f3c2a22e000 rw-p 0002b000 fe:00 1055731                    /usr/lib/libc.so.6
+Size:                  8 kB
+Rss:                   8 kB
+Shared_Dirty:          0 kB
+Private_Dirty:         8 kB
+Anonymous:             8 kB
+Swap:                  0 kB
+VmFlags: rd wr mr mw me ac
+";
+
+    #[test]
+    fn test_parse_smaps() {
+        let table = parse_smaps(SMAPS, MappingFormat::Name).unwrap();
+
+        assert_eq!(table.entries.len(), 3);
+        assert_eq!(table.entries[0].map_line.mapping, "sleep");
+        assert_eq!(table.entries[0].rss_in_kb, 8);
+        assert_eq!(table.entries[0].dirty_in_kb(), 0);
+        assert_eq!(table.entries[0].anonymous_in_kb, 0);
+        assert_eq!(table.entries[1].map_line.mapping, "  [ anon ]");
+        assert_eq!(table.entries[1].rss_in_kb, 12);
+        // Swap is not dirty.
+        assert_eq!(table.entries[1].dirty_in_kb(), 8);
+        assert_eq!(table.entries[1].anonymous_in_kb, 8);
+
+        assert_eq!(table.total_size_in_kb(), 148);
+        assert_eq!(table.total_rss_in_kb(), 28);
+        assert_eq!(table.total_dirty_in_kb(), 16);
+    }
+
+    #[test]
+    fn test_summary_totals() {
+        let table = parse_smaps(SMAPS, MappingFormat::Name).unwrap();
+
+        // The copy-on-write pages of the libc data mapping count as anonymous, the rest of it
+        // as file-backed.
+        assert_eq!(table.entries[2].map_line.mapping, "libc.so.6");
+        assert_eq!(table.total_anonymous_in_kb(), 16);
+        assert_eq!(table.total_file_in_kb(), 132);
+    }
+
+    #[test]
+    fn test_parse_smaps_empty() {
+        assert_eq!(
+            parse_smaps("", MappingFormat::Name).unwrap(),
+            SmapTable::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_smaps_with_invalid_format() {
+        assert!(parse_smaps("Rss: 8 kB\n", MappingFormat::Name).is_err());
+        assert!(parse_smaps(
+            "564d7f5e3000-564d7f5e5000 r--p 00000000 fe:00 1 /a\nRss: x kB\n",
+            MappingFormat::Name
+        )
+        .is_err());
+        assert!(parse_smaps("not a mapping\n", MappingFormat::Name).is_err());
+    }
+
+    #[test]
+    fn test_parse_kb() {
+        assert_eq!(parse_kb("   132 kB").unwrap(), 132);
+        assert_eq!(parse_kb("0 kB").unwrap(), 0);
+        assert!(parse_kb(" kB").is_err());
+    }
+}
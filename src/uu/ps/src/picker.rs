@@ -19,6 +19,10 @@ pub(crate) fn collect_pickers(
             "time" | "cputime" => pickers.push(helper(time)),
             "ucmd" => pickers.push(helper(ucmd)),
             "cmd" => pickers.push(helper(cmd)),
+            "ipcns" | "mntns" | "netns" | "pidns" | "userns" | "utsns" => {
+                let kind = code.strip_suffix("ns").unwrap().to_owned();
+                pickers.push(helper(move |proc_info| namespace(&kind, proc_info)));
+            }
             _ => {}
         }
     }
@@ -76,6 +80,15 @@ fn cmd(proc_info: RefCell<ProcessInformation>) -> String {
     proc_info.borrow().cmdline.clone()
 }
 
+/// Inode of the namespace, blank if it can't be read
+fn namespace(kind: &str, proc_info: RefCell<ProcessInformation>) -> String {
+    proc_info
+        .borrow()
+        .namespace(kind)
+        .map(|inode| inode.to_string())
+        .unwrap_or_default()
+}
+
 fn ucmd(proc_info: RefCell<ProcessInformation>) -> String {
     proc_info.borrow_mut().status().get("Name").unwrap().into()
 }
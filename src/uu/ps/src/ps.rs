@@ -15,9 +15,9 @@ use mapping::{collect_code_mapping, default_codes, default_mapping};
 use parser::{parser, OptionalKeyValue};
 use prettytable::{format::consts::FORMAT_CLEAN, Row, Table};
 use std::{cell::RefCell, rc::Rc};
-use uu_pgrep::process::walk_process;
+use uu_pgrep::process::{walk_process, Namespace, NAMESPACES};
 use uucore::{
-    error::{UError, UResult, USimpleError},
+    error::{strip_errno, UError, UResult, USimpleError},
     format_usage, help_about, help_usage,
 };
 
@@ -39,6 +39,25 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
 
     proc_infos.dedup_by(|a, b| a.borrow().pid == b.borrow().pid);
 
+    // Namespaces are only read when asked for, it takes a few syscalls per process
+    if let Some(pid) = matches.get_one::<usize>("ns") {
+        let kinds = match matches.get_many::<String>("nslist") {
+            Some(kinds) => kinds.map(String::as_str).collect(),
+            None => NAMESPACES.to_vec(),
+        };
+        let reference = Namespace::from_pid(*pid).map_err(|e| {
+            USimpleError::new(
+                1,
+                format!(
+                    "reading namespaces of pid {pid} failed: {}",
+                    strip_errno(&e)
+                ),
+            )
+        })?;
+
+        proc_infos.retain(|it| reference.matches(&Namespace::from_process(&it.borrow()), &kinds));
+    }
+
     sorting::sort(&mut proc_infos, &matches);
 
     let arg_formats = collect_format(&matches);
@@ -173,6 +192,22 @@ pub fn uu_app() -> Command {
                 .value_parser(parser)
                 .help("user-defined format"),
         )
+        .arg(
+            Arg::new("ns")
+                .long("ns")
+                .value_name("PID")
+                .value_parser(clap::value_parser!(usize))
+                .help("match processes that belong to the same namespaces as <PID>"),
+        )
+        .arg(
+            Arg::new("nslist")
+                .long("nslist")
+                .value_name("NS")
+                .value_delimiter(',')
+                .value_parser(clap::builder::PossibleValuesParser::new(NAMESPACES))
+                .requires("ns")
+                .help("list which namespaces will be considered for the --ns option"),
+        )
     // .args([
     //     Arg::new("command").short('c').help("command name"),
     //     Arg::new("GID")
@@ -14,5 +14,5 @@ pub(crate) fn sort(input: &mut [Rc<RefCell<ProcessInformation>>], _matches: &Arg
 
 /// Sort by pid. (Default)
 fn sort_by_pid(input: &mut [Rc<RefCell<ProcessInformation>>]) {
-    input.sort_by(|a, b| a.borrow().pid.cmp(&b.borrow().pid));
+    input.sort_by_key(|it| it.borrow().pid);
 }
@@ -185,20 +185,11 @@ impl SlabInfo {
         }
     }
 
+    /// Average size of an object, weighted by the number of objects of each cache.
     pub fn object_avg(&self) -> u64 {
-        let Some(offset) = self.offset("objsize") else {
-            return 0;
-        };
-
-        let iter = self.data.iter().filter_map(|(_, data)| data.get(offset));
-
-        let count = iter.clone().count();
-        let sum = iter.sum::<u64>();
-
-        if count == 0 {
-            0
-        } else {
-            (sum) / (count as u64)
+        match self.total_objs() {
+            0 => 0,
+            objs => self.total_size() / objs,
         }
     }
 
@@ -238,24 +229,20 @@ impl SlabInfo {
             .sum::<u64>()
     }
 
+    /// Number of caches which have at least one active object.
     pub fn total_active_cache(&self) -> u64 {
-        self.names()
+        let Some(offset) = self.offset("active_objs") else {
+            return 0;
+        };
+
+        self.data
             .iter()
-            .map(|name| {
-                self.fetch(name, "objsize").unwrap_or_default()
-                    * self.fetch(name, "active_objs").unwrap_or_default()
-            })
-            .sum::<u64>()
+            .filter(|(_, data)| data.get(offset).is_some_and(|active| *active > 0))
+            .count() as u64
     }
 
     pub fn total_cache(&self) -> u64 {
-        self.names()
-            .iter()
-            .map(|name| {
-                self.fetch(name, "objsize").unwrap_or_default()
-                    * self.fetch(name, "num_objs").unwrap_or_default()
-            })
-            .sum::<u64>()
+        self.data.len() as u64
     }
 }
 
@@ -345,10 +332,34 @@ mod tests {
         assert_ne!(name, "nf_conntrack_expect");
     }
 
+    #[test]
+    fn test_totals() {
+        let test = "slabinfo - version: 2.1
+# name            <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables <limit> <batchcount> <sharedfactor> : slabdata <active_slabs> <num_slabs> <sharedavail>
+small                100    200     16  256    1 : tunables    0    0    0 : slabdata      1      2      0
+medium                30     40    256   16    1 : tunables    0    0    0 : slabdata      2      3      0
+unused                 0     10   1024    8    2 : tunables    0    0    0 : slabdata      0      2      0";
+        let result = SlabInfo::parse(test).unwrap();
+
+        assert_eq!(result.total_active_objs(), 130);
+        assert_eq!(result.total_objs(), 250);
+        assert_eq!(result.total_active_slabs(), 3);
+        assert_eq!(result.total_slabs(), 7);
+        assert_eq!(result.total_active_cache(), 2);
+        assert_eq!(result.total_cache(), 3);
+        // 100 * 16 + 30 * 256 + 0 * 1024
+        assert_eq!(result.total_active_size(), 9280);
+        // 200 * 16 + 40 * 256 + 10 * 1024
+        assert_eq!(result.total_size(), 23680);
+        assert_eq!(result.object_minimum(), 16);
+        assert_eq!(result.object_avg(), 94);
+        assert_eq!(result.object_maximum(), 1024);
+    }
+
     #[test]
     fn test_parse() {
         let test = include_str!("../../../../tests/fixtures/slabtop/data.txt");
-        let result = SlabInfo::parse(test.into()).unwrap();
+        let result = SlabInfo::parse(test).unwrap();
 
         assert_eq!(result.fetch("nf_conntrack_expect", "objsize").unwrap(), 208);
         assert_eq!(
@@ -56,29 +56,28 @@ fn percentage(numerator: u64, denominator: u64) -> f64 {
 
 fn output_header(slabinfo: &SlabInfo) {
     println!(
-        r" Active / Total Objects (% used)    : {} / {} ({:.1}%)",
+        r" Active / Total Objects (% used)    : {} / {} ({:.2}%)",
         slabinfo.total_active_objs(),
         slabinfo.total_objs(),
         percentage(slabinfo.total_active_objs(), slabinfo.total_objs())
     );
 
     println!(
-        r" Active / Total Slabs (% used)      : {} / {} ({:.1}%)",
+        r" Active / Total Slabs (% used)      : {} / {} ({:.2}%)",
         slabinfo.total_active_slabs(),
         slabinfo.total_slabs(),
         percentage(slabinfo.total_active_slabs(), slabinfo.total_slabs(),)
     );
 
-    // TODO: I don't know the 'cache' meaning.
     println!(
-        r" Active / Total Caches (% used)     : {} / {} ({:.1}%)",
+        r" Active / Total Caches (% used)     : {} / {} ({:.2}%)",
         slabinfo.total_active_cache(),
         slabinfo.total_cache(),
         percentage(slabinfo.total_active_cache(), slabinfo.total_cache())
     );
 
     println!(
-        r" Active / Total Size (% used)       : {:.2}K / {:.2}K ({:.1}%)",
+        r" Active / Total Size (% used)       : {:.2}K / {:.2}K ({:.2}%)",
         to_kb(slabinfo.total_active_size()),
         to_kb(slabinfo.total_size()),
         percentage(slabinfo.total_active_size(), slabinfo.total_size())
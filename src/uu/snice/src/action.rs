@@ -33,6 +33,27 @@ pub(crate) enum SelectedTarget {
 
 #[allow(unused)]
 impl SelectedTarget {
+    /// Guess what a loose argument stands for, like procps does: a number is a pid, then a
+    /// terminal, a user and finally a command name.
+    pub(crate) fn guess(arg: &str) -> Self {
+        if let Ok(pid) = arg.parse() {
+            return Self::Pid(pid);
+        }
+
+        let tty = arg.strip_prefix("/dev/").unwrap_or(arg);
+        if std::path::Path::new("/dev").join(tty).exists() {
+            if let Ok(tty) = Teletype::try_from(tty) {
+                return Self::Tty(tty);
+            }
+        }
+
+        if users().iter().any(|it| it.name() == arg) {
+            return Self::User(arg.into());
+        }
+
+        Self::Command(arg.into())
+    }
+
     pub(crate) fn to_pids(&self) -> Vec<u32> {
         match self {
             Self::Command(cmd) => Self::from_cmd(cmd),
@@ -109,52 +130,28 @@ impl Display for ActionResult {
 
 /// Set priority of process.
 ///
-/// But we don't know if the process of pid are exist, if [None], the process doesn't exist
+/// Returns [None] if the process doesn't exist (anymore).
 #[cfg(target_os = "linux")]
 fn set_priority(pid: u32, prio: &Priority) -> Option<ActionResult> {
     use libc::{getpriority, setpriority, PRIO_PROCESS};
     use nix::errno::Errno;
 
-    // Very dirty.
-    let current_priority = {
-        // Clear errno
-        Errno::clear();
-
-        let prio = unsafe { getpriority(PRIO_PROCESS, pid) };
-        // prio == -1 might be error.
-        if prio == -1 && Errno::last() != Errno::UnknownErrno {
-            // Must clear errno.
-            Errno::clear();
-
-            // I don't know but, just considering it just caused by permission.
-            // https://manpages.debian.org/bookworm/manpages-dev/getpriority.2.en.html#ERRORS
-            return match Errno::last() {
-                Errno::ESRCH => Some(ActionResult::PermissionDenied),
-                _ => None,
-            };
-        }
-        prio
-    };
-
-    let prio = match prio {
-        Priority::Increase(prio) => current_priority + *prio as i32,
-        Priority::Decrease(prio) => current_priority - *prio as i32,
-        Priority::To(prio) => *prio as i32,
-    };
-
-    // result only 0, -1
+    // -1 is a valid priority, only errno tells an error apart.
     Errno::clear();
-    let result = unsafe { setpriority(PRIO_PROCESS, pid, prio) };
+    let current_priority = unsafe { getpriority(PRIO_PROCESS, pid) };
+    if current_priority == -1 && Errno::last() != Errno::UnknownErrno {
+        return None;
+    }
 
     // https://manpages.debian.org/bookworm/manpages-dev/setpriority.2.en.html#ERRORS
-    if result == -1 {
-        match Errno::last() {
-            Errno::ESRCH => Some(ActionResult::PermissionDenied),
+    if unsafe { setpriority(PRIO_PROCESS, pid, prio.apply(current_priority)) } == -1 {
+        return match Errno::last() {
+            Errno::EPERM | Errno::EACCES => Some(ActionResult::PermissionDenied),
             _ => None,
-        }
-    } else {
-        Some(ActionResult::Success)
+        };
     }
+
+    Some(ActionResult::Success)
 }
 
 // TODO: Implemented this on other platform
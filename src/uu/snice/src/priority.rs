@@ -51,6 +51,19 @@ impl TryFrom<&str> for Priority {
     }
 }
 
+impl Priority {
+    /// The nice value resulting from applying this to `current`, within -20..=19.
+    pub(crate) fn apply(&self, current: i32) -> i32 {
+        let nice = match self {
+            Self::Increase(prio) => current.saturating_add_unsigned(*prio),
+            Self::Decrease(prio) => current.saturating_sub_unsigned(*prio),
+            Self::To(prio) => i32::try_from(*prio).unwrap_or(i32::MAX),
+        };
+
+        nice.clamp(-20, 19)
+    }
+}
+
 impl Default for Priority {
     fn default() -> Self {
         Priority::Increase(4)
@@ -90,6 +103,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_apply() {
+        assert_eq!(Priority::default().apply(0), 4);
+        assert_eq!(Priority::Increase(5).apply(2), 7);
+        assert_eq!(Priority::Decrease(5).apply(2), -3);
+        assert_eq!(Priority::To(10).apply(2), 10);
+
+        assert_eq!(Priority::Increase(5).apply(17), 19);
+        assert_eq!(Priority::Increase(u32::MAX).apply(0), 19);
+        assert_eq!(Priority::Decrease(17).apply(-10), -20);
+        assert_eq!(Priority::To(u32::MAX).apply(0), 19);
+    }
+
     #[test]
     fn test_to_string() {
         assert_eq!(Priority::Decrease(4).to_string(), "-4");
@@ -3,7 +3,7 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use std::{collections::HashSet, path::PathBuf, str::FromStr};
+use std::{collections::HashSet, ffi::OsString, path::PathBuf, str::FromStr};
 
 use action::{perform_action, process_snapshot, users, ActionResult, SelectedTarget};
 use clap::{arg, crate_version, value_parser, Arg, ArgMatches, Command};
@@ -15,7 +15,7 @@ use uu_pgrep::process::{ProcessInformation, Teletype};
 use uucore::signals::ALL_SIGNALS;
 use uucore::{
     error::{UResult, USimpleError},
-    format_usage, help_about, help_usage,
+    format_usage, help_about, help_usage, show,
 };
 
 const ABOUT: &str = help_about!("snice.md");
@@ -75,12 +75,7 @@ struct Settings {
 }
 
 impl Settings {
-    fn try_new(matches: &ArgMatches) -> UResult<Self> {
-        let priority = matches
-            .try_get_one::<String>("priority")
-            .unwrap_or(Some(&String::new()))
-            .cloned();
-
+    fn try_new(matches: &ArgMatches, priority: Option<String>) -> UResult<Self> {
         let expression = match priority {
             Some(expr) => {
                 Priority::try_from(expr).map_err(|err| USimpleError::new(1, err.to_string()))?
@@ -133,11 +128,18 @@ impl Settings {
             .map(SelectedTarget::User)
             .collect::<Vec<_>>();
 
+        // Loose arguments are guessed to be a pid, terminal, user or command.
+        let guessed = matches
+            .get_many::<String>("expression")
+            .unwrap_or_default()
+            .map(|it| SelectedTarget::guess(it));
+
         let collected = cmd
             .into_iter()
             .chain(pid)
             .chain(tty)
             .chain(user)
+            .chain(guessed)
             .collect::<Vec<_>>();
 
         if collected.is_empty() {
@@ -148,11 +150,30 @@ impl Settings {
     }
 }
 
+/// Take the `+N`/`-N` priority from the first argument.
+///
+/// It has to be removed before parsing, clap would read `-17` as a bunch of flags.
+fn take_priority(args: &mut Vec<OsString>) -> Option<String> {
+    let first = args.get(1)?.to_str()?;
+    let digits = first.strip_prefix(['+', '-'])?;
+
+    if digits.is_empty() || !digits.bytes().all(|it| it.is_ascii_digit()) {
+        return None;
+    }
+
+    let priority = first.to_owned();
+    args.remove(1);
+    Some(priority)
+}
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
+    let mut args = args.collect::<Vec<_>>();
+    let priority = take_priority(&mut args);
+
     let matches = uu_app().try_get_matches_from(args)?;
 
-    let settings = Settings::try_new(&matches)?;
+    let settings = Settings::try_new(&matches, priority)?;
 
     // Case0: Print SIGNALS
     #[cfg(target_family = "unix")]
@@ -184,6 +205,16 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         if settings.verbose {
             let output = construct_verbose_result(&pids, &results).trim().to_owned();
             println!("{}", output);
+        } else {
+            // Like renice, a process we may not touch doesn't stop the others.
+            for (pid, result) in pids.iter().zip(&results) {
+                if let Some(ActionResult::PermissionDenied) = result {
+                    show!(USimpleError::new(
+                        1,
+                        format!("failed to set priority of pid {pid}: Permission denied"),
+                    ));
+                }
+            }
         }
     }
 
@@ -213,7 +244,7 @@ fn construct_verbose_result(pids: &[u32], action_results: &[Option<ActionResult>
 
             let mut cmd = process
                 .exe()
-                .and_then(|it| it.iter().last())
+                .and_then(|it| it.iter().next_back())
                 .unwrap_or("?".as_ref());
             let cmd = cmd.to_str().unwrap();
 
@@ -248,7 +279,11 @@ pub fn uu_app() -> Command {
         .override_usage(format_usage(USAGE))
         .infer_long_args(true)
         .arg_required_else_help(true)
-        .arg(Arg::new("priority"))
+        .arg(
+            Arg::new("expression")
+                .num_args(0..)
+                .help("pid, terminal, user or command to select"),
+        )
         .args([
             // Options
             // arg!(-f --fast          "fast mode (not implemented)"),
@@ -270,6 +305,35 @@ pub fn uu_app() -> Command {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_take_priority() {
+        let mut with_priority = args(&["snice", "-17", "-u", "daemon"]);
+        assert_eq!(take_priority(&mut with_priority), Some("-17".into()));
+        assert_eq!(with_priority, args(&["snice", "-u", "daemon"]));
+
+        let mut with_priority = args(&["snice", "+5", "seti", "crack"]);
+        assert_eq!(take_priority(&mut with_priority), Some("+5".into()));
+        assert_eq!(with_priority, args(&["snice", "seti", "crack"]));
+
+        for unchanged in [
+            &["snice", "-u", "daemon"][..],
+            &["snice", "5"],
+            &["snice", "-"],
+            &["snice", "+5a"],
+            &["snice"],
+        ] {
+            let mut unchanged = args(unchanged);
+            let before = unchanged.clone();
+            assert_eq!(take_priority(&mut unchanged), None);
+            assert_eq!(unchanged, before);
+        }
+    }
+
     static ALL_SIGNALS: [&str; 32] = [
         "EXIT", "HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "BUS", "FPE", "KILL", "USR1", "SEGV",
         "USR2", "PIPE", "ALRM", "TERM", "STKFLT", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU",
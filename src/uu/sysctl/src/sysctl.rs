@@ -51,27 +51,40 @@ mod linux {
     }
 
     pub fn get_sysctl(var: &str) -> std::io::Result<String> {
-        Ok(std::fs::read_to_string(variable_path(var))?
-            .trim_end()
-            .to_string())
+        Ok(super::normalize_value(&std::fs::read_to_string(
+            variable_path(var),
+        )?))
     }
 
     pub fn set_sysctl(var: &str, value: &str) -> std::io::Result<()> {
         std::fs::write(variable_path(var), value)
     }
 
-    pub fn handle_one_arg(
-        var_or_assignment: &str,
-        quiet: bool,
-    ) -> Result<Option<(String, String)>, Box<UIoError>> {
+    /// Split `var[=value]` into the normalized variable name and the value to set, if any.
+    pub fn split_assignment(var_or_assignment: &str) -> (String, Option<&str>) {
         let mut splitted = var_or_assignment.splitn(2, '=');
         let var = normalize_var(
             splitted
                 .next()
                 .expect("Split always returns at least 1 value"),
         );
+        (var, splitted.next())
+    }
+
+    /// Describe what setting `var` to `value` would change, without writing anything.
+    pub fn preview_sysctl(var: &str, value: &str) -> Result<String, Box<UIoError>> {
+        let current = get_sysctl(var)
+            .map_err(|e| e.map_err_context(|| format!("error reading key '{}'", var)))?;
+        Ok(super::describe_change(var, &current, value))
+    }
+
+    pub fn handle_one_arg(
+        var_or_assignment: &str,
+        quiet: bool,
+    ) -> Result<Option<(String, String)>, Box<UIoError>> {
+        let (var, value_to_set) = split_assignment(var_or_assignment);
 
-        if let Some(value_to_set) = splitted.next() {
+        if let Some(value_to_set) = value_to_set {
             set_sysctl(&var, value_to_set)
                 .map_err(|e| e.map_err_context(|| format!("error writing key '{}'", var)))?;
             if quiet {
@@ -89,6 +102,27 @@ mod linux {
 #[cfg(target_os = "linux")]
 use linux::*;
 
+/// Normalize a raw value read from `/proc/sys` the same way procps does: tabs
+/// separating fields are kept as-is, trailing whitespace is dropped from every
+/// line and trailing empty lines are removed.
+pub fn normalize_value(raw: &str) -> String {
+    raw.trim_end()
+        .split('\n')
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The `--dry-run` line for setting `var` from `current` to `new`.
+pub fn describe_change(var: &str, current: &str, new: &str) -> String {
+    let new = normalize_value(new);
+    if current == new {
+        format!("{} unchanged", var)
+    } else {
+        format!("{} = {} -> {}", var, current, new)
+    }
+}
+
 #[cfg(target_os = "linux")]
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
@@ -104,6 +138,20 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     };
 
     for var_or_assignment in vars {
+        if matches.get_flag("dry-run") {
+            if let (var, Some(value)) = split_assignment(&var_or_assignment) {
+                match preview_sysctl(&var, value) {
+                    Ok(line) => println!("{}", line),
+                    Err(e) => {
+                        if !matches.get_flag("ignore") {
+                            uucore::show!(e);
+                        }
+                    }
+                }
+                continue;
+            }
+        }
+
         match handle_one_arg(&var_or_assignment, matches.get_flag("quiet")) {
             Ok(None) => (),
             Ok(Some((var, value_to_print))) => {
@@ -163,6 +211,7 @@ pub fn uu_app() -> Command {
                 .short('N')
                 .long("names")
                 .action(ArgAction::SetTrue)
+                .conflicts_with("values")
                 .help("Only print names"),
         )
         .arg(
@@ -186,6 +235,12 @@ pub fn uu_app() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Do not print when setting variables"),
         )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Show what setting variables would change, without writing"),
+        )
         .arg(
             Arg::new("noop_o")
                 .short('o')
@@ -197,3 +252,36 @@ pub fn uu_app() -> Command {
                 .help("Does nothing, for BSD compatibility"),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_value() {
+        assert_eq!(normalize_value("4\t4\t1\t7\n"), "4\t4\t1\t7");
+        assert_eq!(normalize_value("32768\t60999 \n"), "32768\t60999");
+        assert_eq!(normalize_value("a \t\nb\t\n\n"), "a\nb");
+        assert_eq!(normalize_value(""), "");
+    }
+
+    #[test]
+    fn test_describe_change() {
+        assert_eq!(
+            describe_change("kernel.ostype", "Linux", "X"),
+            "kernel.ostype = Linux -> X"
+        );
+        assert_eq!(
+            describe_change("net.ipv4.ip_forward", "0", "0\n"),
+            "net.ipv4.ip_forward unchanged"
+        );
+        assert_eq!(
+            describe_change(
+                "net.ipv4.ip_local_port_range",
+                "32768\t60999",
+                "32768\t60999 "
+            ),
+            "net.ipv4.ip_local_port_range unchanged"
+        );
+    }
+}
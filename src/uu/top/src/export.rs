@@ -0,0 +1,148 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Snapshot of the task area written as CSV with `--export`.
+///
+/// Every iteration is appended below a `# iteration N <timestamp>` comment line. The first
+/// iteration replaces the file atomically through a temp file; later ones are appended to the
+/// open file with a single write each.
+#[derive(Debug)]
+pub(crate) struct Export {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl Export {
+    pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: None,
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn append(
+        &mut self,
+        iteration: u64,
+        fields: &[String],
+        rows: &[Vec<String>],
+    ) -> io::Result<()> {
+        let mut content = format!(
+            "# iteration {} {}\n",
+            iteration,
+            chrono::Local::now().to_rfc3339()
+        );
+        content.push_str(&csv_line(fields));
+        for row in rows {
+            content.push_str(&csv_line(row));
+        }
+
+        match &mut self.file {
+            Some(file) => file.write_all(content.as_bytes()),
+            None => {
+                self.create(&content)?;
+                self.file = Some(OpenOptions::new().append(true).open(&self.path)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn create(&self, content: &str) -> io::Result<()> {
+        let name = self
+            .path
+            .file_name()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp = self.path.with_file_name(temp_name);
+
+        fs::write(&temp, content)?;
+        if let Err(e) = fs::rename(&temp, &self.path) {
+            let _ = fs::remove_file(&temp);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+/// Format one CSV record as described in RFC 4180, terminated by a newline.
+fn csv_line<T: AsRef<str>>(cells: &[T]) -> String {
+    let mut line = cells
+        .iter()
+        .map(|cell| csv_cell(cell.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+fn csv_cell(cell: &str) -> String {
+    // A leading '#' is quoted as well, so that rows can't be mistaken for iteration markers.
+    if cell.contains([',', '"', '\n', '\r']) || cell.starts_with('#') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_line() {
+        assert_eq!(csv_line(&["PID", "%CPU"]), "PID,%CPU\n");
+        assert_eq!(
+            csv_line(&["1", "sh -c \"a, b\"", "#1", "two\nlines", ""]),
+            "1,\"sh -c \"\"a, b\"\"\",\"#1\",\"two\nlines\",\n"
+        );
+    }
+
+    #[test]
+    fn test_append() {
+        let path = std::env::temp_dir().join(format!("top-{}-export.csv", std::process::id()));
+        let mut export = Export::new(&path);
+        let fields = ["PID", "COMMAND"].map(String::from);
+        let rows = vec![vec!["1".to_string(), "init".to_string()]];
+        // A previous export is replaced rather than appended to.
+        fs::write(&path, "stale\n").unwrap();
+
+        export.append(1, &fields, &rows).unwrap();
+        export.append(2, &fields, &[]).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("# iteration 1 "));
+        assert_eq!(&lines[1..3], ["PID,COMMAND", "1,init"]);
+        assert!(lines[3].starts_with("# iteration 2 "));
+        assert_eq!(lines[4], "PID,COMMAND");
+
+        // Only the export itself is left behind.
+        let leftovers = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .flatten()
+            .filter(|it| {
+                it.file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!(".top-{}-export.csv", std::process::id()))
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        fs::remove_file(path).unwrap();
+    }
+}
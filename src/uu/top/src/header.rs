@@ -0,0 +1,925 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::picker::sysinfo;
+use std::{collections::BTreeMap, sync::Mutex, time::Instant};
+use sysinfo::System;
+
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Information shown in the first line of the summary area.
+#[derive(Debug)]
+pub(crate) struct Uptime {
+    /// Seconds since boot
+    pub(crate) uptime: u64,
+    pub(crate) users: usize,
+    /// Load averages over the last 1, 5 and 15 minutes
+    pub(crate) load_average: (f64, f64, f64),
+}
+
+/// How busy the system is relative to the number of CPUs.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum LoadClass {
+    Low,
+    Medium,
+    High,
+}
+
+impl LoadClass {
+    fn color(&self) -> &'static str {
+        match self {
+            Self::Low => GREEN,
+            Self::Medium => YELLOW,
+            Self::High => RED,
+        }
+    }
+}
+
+/// Direction of the 1-minute load compared to the 5-minute load.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+impl Trend {
+    fn arrow(&self) -> &'static str {
+        match self {
+            Self::Rising => "↑",
+            Self::Falling => "↓",
+            Self::Steady => "",
+        }
+    }
+}
+
+pub(crate) fn load_class(load: f64, cpus: usize) -> LoadClass {
+    let ratio = load / cpus.max(1) as f64;
+
+    if ratio < 0.7 {
+        LoadClass::Low
+    } else if ratio < 1.0 {
+        LoadClass::Medium
+    } else {
+        LoadClass::High
+    }
+}
+
+pub(crate) fn trend(one: f64, five: f64) -> Trend {
+    // Load averages are displayed with two decimals, so smaller changes are invisible.
+    if (one - five).abs() < 0.005 {
+        Trend::Steady
+    } else if one > five {
+        Trend::Rising
+    } else {
+        Trend::Falling
+    }
+}
+
+impl Uptime {
+    pub(crate) fn new() -> Self {
+        let load = System::load_average();
+
+        Self {
+            uptime: System::uptime(),
+            users: user_count(),
+            load_average: (load.one, load.five, load.fifteen),
+        }
+    }
+
+    /// Render the uptime line, coloring the load averages if `colorful` is set.
+    pub(crate) fn render(&self, colorful: bool, cpus: usize) -> String {
+        let (one, five, fifteen) = self.load_average;
+
+        let load = if colorful {
+            let paint =
+                |load: f64| format!("{}{:.2}{}", load_class(load, cpus).color(), load, RESET);
+
+            format!(
+                "{}{}, {}, {}",
+                paint(one),
+                trend(one, five).arrow(),
+                paint(five),
+                paint(fifteen)
+            )
+        } else {
+            format!("{:.2}, {:.2}, {:.2}", one, five, fifteen)
+        };
+
+        format!(
+            "top - {} up {}, {:>2} user{},  load average: {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            format_uptime(self.uptime),
+            self.users,
+            if self.users == 1 { "" } else { "s" },
+            load
+        )
+    }
+}
+
+fn format_uptime(uptime: u64) -> String {
+    let days = uptime / 86400;
+    let hours = (uptime % 86400) / 3600;
+    let minutes = (uptime % 3600) / 60;
+
+    let time = if hours == 0 {
+        format!("{} min", minutes)
+    } else {
+        format!("{:>2}:{:0>2}", hours, minutes)
+    };
+
+    match days {
+        0 => time,
+        1 => format!("1 day, {}", time),
+        _ => format!("{} days, {}", days, time),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn user_count() -> usize {
+    use uucore::utmpx::Utmpx;
+
+    Utmpx::iter_all_records()
+        .filter(|it| it.is_user_process())
+        .count()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn user_count() -> usize {
+    0
+}
+
+/// Unit of memory values, picked with `-E` for the summary lines and `-e` for the task area.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum MemoryUnit {
+    KiB,
+    #[default]
+    MiB,
+    GiB,
+    TiB,
+    PiB,
+    EiB,
+}
+
+impl MemoryUnit {
+    /// The `-E` or `-e` argument: k, m, g, t, p or e.
+    pub(crate) fn from_scale(scale: &str) -> Option<Self> {
+        match scale {
+            "k" => Some(Self::KiB),
+            "m" => Some(Self::MiB),
+            "g" => Some(Self::GiB),
+            "t" => Some(Self::TiB),
+            "p" => Some(Self::PiB),
+            "e" => Some(Self::EiB),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::KiB => "KiB",
+            Self::MiB => "MiB",
+            Self::GiB => "GiB",
+            Self::TiB => "TiB",
+            Self::PiB => "PiB",
+            Self::EiB => "EiB",
+        }
+    }
+
+    /// Marks task memory values in this unit, procps leaves KiB unmarked.
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::KiB => "",
+            Self::MiB => "m",
+            Self::GiB => "g",
+            Self::TiB => "t",
+            Self::PiB => "p",
+            Self::EiB => "e",
+        }
+    }
+
+    fn coarser(self) -> Option<Self> {
+        match self {
+            Self::KiB => Some(Self::MiB),
+            Self::MiB => Some(Self::GiB),
+            Self::GiB => Some(Self::TiB),
+            Self::TiB => Some(Self::PiB),
+            Self::PiB => Some(Self::EiB),
+            Self::EiB => None,
+        }
+    }
+
+    /// Bytes per unit.
+    fn size(self) -> u64 {
+        match self {
+            Self::KiB => bytesize::KIB,
+            Self::MiB => bytesize::MIB,
+            Self::GiB => bytesize::GIB,
+            Self::TiB => bytesize::TIB,
+            Self::PiB => bytesize::PIB,
+            Self::EiB => bytesize::PIB * 1024,
+        }
+    }
+}
+
+/// Format `bytes` in `unit` the way the memory summary lines show them, KiB are whole numbers.
+pub(crate) fn format_memory(bytes: u64, unit: MemoryUnit) -> String {
+    let precision = if unit == MemoryUnit::KiB { 0 } else { 1 };
+    format!("{:.*}", precision, bytes as f64 / unit.size() as f64)
+}
+
+/// Format `bytes` for the VIRT, RES and SHR columns in `unit`. Like procps, a value too wide
+/// for its column of `width` is shown in the next coarser unit that fits.
+pub(crate) fn format_task_memory(bytes: u64, unit: MemoryUnit, width: usize) -> String {
+    let mut unit = unit;
+
+    loop {
+        let formatted = format!("{}{}", format_memory(bytes, unit), unit.suffix());
+        match unit.coarser() {
+            Some(next) if formatted.len() > width => unit = next,
+            _ => return formatted,
+        }
+    }
+}
+
+/// Memory and swap usage in bytes, shown in the `Mem` and `Swap` summary lines.
+#[derive(Debug, Default)]
+pub(crate) struct Memory {
+    pub(crate) total: u64,
+    pub(crate) free: u64,
+    pub(crate) used: u64,
+    pub(crate) available: u64,
+    pub(crate) swap_total: u64,
+    pub(crate) swap_free: u64,
+    /// Swap activity since the previous frame, `None` if the counters are unavailable.
+    pub(crate) swap_rates: Option<SwapRates>,
+}
+
+impl Memory {
+    pub(crate) fn new() -> Self {
+        let system = sysinfo().read().unwrap();
+
+        Self {
+            total: system.total_memory(),
+            free: system.free_memory(),
+            used: system.used_memory(),
+            available: system.available_memory(),
+            swap_total: system.total_swap(),
+            swap_free: system.free_swap(),
+            swap_rates: swap_rates(),
+        }
+    }
+
+    /// Everything neither free nor used, i.e. buffers and caches.
+    fn buff_cache(&self) -> u64 {
+        self.total
+            .saturating_sub(self.free)
+            .saturating_sub(self.used)
+    }
+
+    /// Render the `Mem` and `Swap` lines. Swap activity is appended only while swap is in
+    /// use.
+    pub(crate) fn render(&self, unit: MemoryUnit) -> [String; 2] {
+        let value = |bytes| format!("{:>9}", format_memory(bytes, unit));
+        let swap_used = self.swap_total.saturating_sub(self.swap_free);
+        let activity = match self.swap_rates {
+            Some(rates) if swap_used > 0 => {
+                format!(", {:.1} si, {:.1} so", rates.swap_in, rates.swap_out)
+            }
+            _ => String::new(),
+        };
+
+        [
+            format!(
+                "{} Mem :{} total,{} free,{} used,{} buff/cache",
+                unit.label(),
+                value(self.total),
+                value(self.free),
+                value(self.used),
+                value(self.buff_cache()),
+            ),
+            format!(
+                "{} Swap:{} total,{} free,{} used.{} avail Mem{}",
+                unit.label(),
+                value(self.swap_total),
+                value(self.swap_free),
+                value(swap_used),
+                value(self.available),
+                activity,
+            ),
+        ]
+    }
+}
+
+pub(crate) fn cpu_count() -> usize {
+    sysinfo().read().unwrap().cpus().len()
+}
+
+/// Jiffies spent in each state, from a `cpu` line of `/proc/stat`.
+///
+/// Kernels add columns over time: `iowait`, `irq` and `softirq` came with 2.6, `steal`,
+/// `guest` and `guest_nice` later. Missing columns are 0, and columns unknown to us are
+/// accounted as idle so that the percentages still add up to 100.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CpuLoad {
+    pub(crate) user: u64,
+    pub(crate) nice: u64,
+    pub(crate) system: u64,
+    pub(crate) idle: u64,
+    pub(crate) iowait: u64,
+    pub(crate) irq: u64,
+    pub(crate) softirq: u64,
+    pub(crate) steal: u64,
+    pub(crate) guest: u64,
+    pub(crate) guest_nice: u64,
+}
+
+impl CpuLoad {
+    /// Parse the values of a `cpu` line, without the label. At least the four fields of
+    /// ancient kernels are required.
+    pub(crate) fn parse(values: &str) -> Option<Self> {
+        let values = values
+            .split_whitespace()
+            .map(str::parse::<u64>)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+
+        if values.len() < 4 {
+            return None;
+        }
+
+        let field = |i: usize| values.get(i).copied().unwrap_or_default();
+        let unknown: u64 = values.iter().skip(10).sum();
+
+        Some(Self {
+            user: field(0),
+            nice: field(1),
+            system: field(2),
+            idle: field(3) + unknown,
+            iowait: field(4),
+            irq: field(5),
+            softirq: field(6),
+            steal: field(7),
+            guest: field(8),
+            guest_nice: field(9),
+        })
+    }
+
+    /// Total time. Guest time is already part of user and nice time.
+    pub(crate) fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    fn delta(&self, previous: &Self) -> Self {
+        Self {
+            user: self.user.saturating_sub(previous.user),
+            nice: self.nice.saturating_sub(previous.nice),
+            system: self.system.saturating_sub(previous.system),
+            idle: self.idle.saturating_sub(previous.idle),
+            iowait: self.iowait.saturating_sub(previous.iowait),
+            irq: self.irq.saturating_sub(previous.irq),
+            softirq: self.softirq.saturating_sub(previous.softirq),
+            steal: self.steal.saturating_sub(previous.steal),
+            guest: self.guest.saturating_sub(previous.guest),
+            guest_nice: self.guest_nice.saturating_sub(previous.guest_nice),
+        }
+    }
+
+    /// Render the `%Cpu(s)` line from the time spent since `previous`. Like in [Self::total],
+    /// guest time shows as part of user and nice time.
+    pub(crate) fn render(&self, previous: &Self) -> String {
+        let delta = self.delta(previous);
+        let total = delta.total().max(1) as f64;
+        let percent = |value: u64| value as f64 * 100.0 / total;
+
+        format!(
+            "%Cpu(s): {:>4.1} us, {:>4.1} sy, {:>4.1} ni, {:>4.1} id, {:>4.1} wa, {:>4.1} hi, {:>4.1} si, {:>4.1} st",
+            percent(delta.user),
+            percent(delta.system),
+            percent(delta.nice),
+            percent(delta.idle),
+            percent(delta.iowait),
+            percent(delta.irq),
+            percent(delta.softirq),
+            percent(delta.steal)
+        )
+    }
+}
+
+/// The CPU lines of `/proc/stat`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CpuStat {
+    /// The aggregate `cpu` line
+    pub(crate) total: CpuLoad,
+    /// The `cpuN` lines by CPU number. Offline CPUs are missing, so there may be gaps.
+    pub(crate) cpus: BTreeMap<usize, CpuLoad>,
+}
+
+impl CpuStat {
+    pub(crate) fn parse(content: &str) -> Self {
+        let mut stat = Self::default();
+
+        for line in content.lines() {
+            let Some((label, values)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some(number) = label.strip_prefix("cpu") else {
+                continue;
+            };
+            let Some(load) = CpuLoad::parse(values) else {
+                continue;
+            };
+
+            if number.is_empty() {
+                stat.total = load;
+            } else if let Ok(number) = number.parse() {
+                stat.cpus.insert(number, load);
+            }
+        }
+
+        stat
+    }
+
+    #[cfg(target_os = "linux")]
+    fn read() -> Option<Self> {
+        std::fs::read_to_string("/proc/stat")
+            .ok()
+            .map(|content| Self::parse(&content))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn read() -> Option<Self> {
+        None
+    }
+}
+
+/// The aggregate CPU times of the previous frame, percentages are relative to them.
+static PREVIOUS_CPU: Mutex<Option<CpuLoad>> = Mutex::new(None);
+
+/// Take the CPU times the first frame is compared to.
+pub(crate) fn sample_cpu() {
+    if let Some(stat) = CpuStat::read() {
+        *PREVIOUS_CPU.lock().unwrap() = Some(stat.total);
+    }
+}
+
+/// The `%Cpu(s)` summary line, empty if `/proc/stat` is unavailable.
+pub(crate) fn cpu_line() -> String {
+    let Some(current) = CpuStat::read().map(|stat| stat.total) else {
+        return String::new();
+    };
+
+    let mut previous = PREVIOUS_CPU.lock().unwrap();
+    let line = current.render(&previous.unwrap_or_default());
+    *previous = Some(current);
+
+    line
+}
+
+/// The `pswpin` and `pswpout` counters of `/proc/vmstat`: pages swapped in and out since
+/// boot.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SwapCounters {
+    pub(crate) swap_in: u64,
+    pub(crate) swap_out: u64,
+}
+
+/// Pages swapped in and out per second.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct SwapRates {
+    pub(crate) swap_in: f64,
+    pub(crate) swap_out: f64,
+}
+
+impl SwapCounters {
+    /// Parse the content of `/proc/vmstat`, both counters are required.
+    pub(crate) fn parse(content: &str) -> Option<Self> {
+        let counter = |name: &str| {
+            content.lines().find_map(|line| {
+                let (key, value) = line.split_once(' ')?;
+                (key == name).then(|| value.trim().parse::<u64>().ok())?
+            })
+        };
+
+        Some(Self {
+            swap_in: counter("pswpin")?,
+            swap_out: counter("pswpout")?,
+        })
+    }
+
+    fn read() -> Option<Self> {
+        std::fs::read_to_string("/proc/vmstat")
+            .ok()
+            .and_then(|content| Self::parse(&content))
+    }
+
+    /// The rates over the `seconds` since `previous`. A counter that went down, e.g. after
+    /// a wrap, counts as no activity.
+    pub(crate) fn rates(&self, previous: &Self, seconds: f64) -> SwapRates {
+        let rate = |current: u64, previous: u64| {
+            if seconds > 0.0 {
+                current.saturating_sub(previous) as f64 / seconds
+            } else {
+                0.0
+            }
+        };
+
+        SwapRates {
+            swap_in: rate(self.swap_in, previous.swap_in),
+            swap_out: rate(self.swap_out, previous.swap_out),
+        }
+    }
+}
+
+/// The swap counters of the previous frame and when they were read.
+static PREVIOUS_SWAP: Mutex<Option<(SwapCounters, Instant)>> = Mutex::new(None);
+
+/// Take the swap counters the first frame is compared to.
+pub(crate) fn sample_swap() {
+    if let Some(counters) = SwapCounters::read() {
+        *PREVIOUS_SWAP.lock().unwrap() = Some((counters, Instant::now()));
+    }
+}
+
+/// The swap activity since the previous frame, `None` if `/proc/vmstat` is unavailable.
+fn swap_rates() -> Option<SwapRates> {
+    let current = SwapCounters::read()?;
+    let now = Instant::now();
+
+    let mut previous = PREVIOUS_SWAP.lock().unwrap();
+    let rates = previous
+        .map(|(counters, taken)| current.rates(&counters, now.duration_since(taken).as_secs_f64()));
+    *previous = Some((current, now));
+
+    rates
+}
+
+/// The `some avg10` value of a `/proc/pressure/<resource>` file: the share of the last 10
+/// seconds in which at least one task was stalled on the resource, in percent.
+///
+/// The `full` line which may follow is not shown, kernels before 5.13 don't have it for cpu.
+pub(crate) fn parse_pressure(content: &str) -> Option<f64> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+fn read_pressure(resource: &str) -> Option<f64> {
+    std::fs::read_to_string(format!("/proc/pressure/{resource}"))
+        .ok()
+        .and_then(|content| parse_pressure(&content))
+}
+
+/// Render the pressure line from the cpu, memory and io stall percentages.
+pub(crate) fn render_pressure(cpu: f64, memory: f64, io: f64) -> String {
+    format!(
+        "Pressure: cpu {:.1}% | mem {:.1}% | io {:.1}% (avg10)",
+        cpu, memory, io
+    )
+}
+
+/// The pressure line, `None` if the kernel doesn't provide pressure stall information.
+pub(crate) fn pressure_line() -> Option<String> {
+    Some(render_pressure(
+        read_pressure("cpu")?,
+        read_pressure("memory")?,
+        read_pressure("io")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_memory() {
+        assert_eq!(format_memory(0, MemoryUnit::MiB), "0.0");
+        assert_eq!(format_memory(2048, MemoryUnit::KiB), "2");
+        assert_eq!(
+            format_memory(3 * bytesize::GIB / 2, MemoryUnit::MiB),
+            "1536.0"
+        );
+        assert_eq!(format_memory(3 * bytesize::GIB / 2, MemoryUnit::GiB), "1.5");
+        assert_eq!(format_memory(u64::MAX, MemoryUnit::EiB), "16.0");
+    }
+
+    #[test]
+    fn test_format_task_memory() {
+        const KIB: u64 = bytesize::KIB;
+
+        assert_eq!(format_task_memory(0, MemoryUnit::KiB, 6), "0");
+        assert_eq!(format_task_memory(12345 * KIB, MemoryUnit::KiB, 6), "12345");
+        assert_eq!(format_task_memory(12345 * KIB, MemoryUnit::MiB, 6), "12.1m");
+        assert_eq!(format_task_memory(12345 * KIB, MemoryUnit::GiB, 6), "0.0g");
+        // Too wide for the column, so a coarser unit is used.
+        assert_eq!(
+            format_task_memory(1234567 * KIB, MemoryUnit::KiB, 7),
+            "1234567"
+        );
+        assert_eq!(
+            format_task_memory(1234567 * KIB, MemoryUnit::KiB, 6),
+            "1.2g"
+        );
+        assert_eq!(
+            format_task_memory(1234567 * KIB, MemoryUnit::MiB, 7),
+            "1205.6m"
+        );
+        assert_eq!(format_task_memory(u64::MAX, MemoryUnit::KiB, 1), "16.0e");
+    }
+
+    #[test]
+    fn test_summary_unit_from_scale() {
+        assert_eq!(MemoryUnit::from_scale("k"), Some(MemoryUnit::KiB));
+        assert_eq!(MemoryUnit::from_scale("e"), Some(MemoryUnit::EiB));
+        assert_eq!(MemoryUnit::from_scale("x"), None);
+    }
+
+    #[test]
+    fn test_memory_render() {
+        let memory = Memory {
+            total: 16 * bytesize::GIB,
+            free: 4 * bytesize::GIB,
+            used: 6 * bytesize::GIB,
+            available: 9 * bytesize::GIB,
+            swap_total: 2 * bytesize::GIB,
+            swap_free: 3 * bytesize::GIB / 2,
+            swap_rates: None,
+        };
+
+        assert_eq!(
+            memory.render(MemoryUnit::MiB),
+            [
+                "MiB Mem :  16384.0 total,   4096.0 free,   6144.0 used,   6144.0 buff/cache",
+                "MiB Swap:   2048.0 total,   1536.0 free,    512.0 used.   9216.0 avail Mem",
+            ]
+        );
+        assert_eq!(
+            memory.render(MemoryUnit::GiB),
+            [
+                "GiB Mem :     16.0 total,      4.0 free,      6.0 used,      6.0 buff/cache",
+                "GiB Swap:      2.0 total,      1.5 free,      0.5 used.      9.0 avail Mem",
+            ]
+        );
+
+        let rates = SwapRates {
+            swap_in: 12.0,
+            swap_out: 3.5,
+        };
+        let memory = Memory {
+            swap_rates: Some(rates),
+            ..memory
+        };
+        assert_eq!(
+            memory.render(MemoryUnit::GiB)[1],
+            "GiB Swap:      2.0 total,      1.5 free,      0.5 used.      9.0 avail Mem, 12.0 si, 3.5 so"
+        );
+
+        // Nothing to show while swap is unused.
+        let memory = Memory {
+            swap_free: memory.swap_total,
+            ..memory
+        };
+        assert_eq!(
+            memory.render(MemoryUnit::GiB)[1],
+            "GiB Swap:      2.0 total,      2.0 free,      0.0 used.      9.0 avail Mem"
+        );
+    }
+
+    #[test]
+    fn test_swap_counters_parse() {
+        let content = "nr_free_pages 1234\npswpin 17\npswpout 42\npswpin_extra 9\n";
+        assert_eq!(
+            SwapCounters::parse(content),
+            Some(SwapCounters {
+                swap_in: 17,
+                swap_out: 42,
+            })
+        );
+        assert_eq!(SwapCounters::parse("nr_free_pages 1234\npswpin 17\n"), None);
+        assert_eq!(SwapCounters::parse("pswpin x\npswpout 42\n"), None);
+    }
+
+    #[test]
+    fn test_swap_rates() {
+        let refreshes = [
+            (SwapCounters::default(), 0.0),
+            (
+                SwapCounters {
+                    swap_in: 300,
+                    swap_out: 30,
+                },
+                3.0,
+            ),
+            (
+                SwapCounters {
+                    swap_in: 400,
+                    swap_out: 30,
+                },
+                0.5,
+            ),
+            // The counters were reset.
+            (
+                SwapCounters {
+                    swap_in: 10,
+                    swap_out: 5,
+                },
+                1.0,
+            ),
+        ];
+        let expected = [(100.0, 10.0), (200.0, 0.0), (0.0, 0.0)];
+
+        for (pair, (swap_in, swap_out)) in refreshes.windows(2).zip(expected) {
+            let (previous, _) = pair[0];
+            let (current, seconds) = pair[1];
+            assert_eq!(
+                current.rates(&previous, seconds),
+                SwapRates { swap_in, swap_out }
+            );
+        }
+
+        let counters = SwapCounters {
+            swap_in: 5,
+            swap_out: 5,
+        };
+        assert_eq!(
+            counters.rates(&SwapCounters::default(), 0.0),
+            SwapRates::default()
+        );
+    }
+
+    #[test]
+    fn test_cpu_load_parse() {
+        // 2.6.0 - 2.6.10
+        assert_eq!(
+            CpuLoad::parse("1 2 3 4 5 6 7"),
+            Some(CpuLoad {
+                user: 1,
+                nice: 2,
+                system: 3,
+                idle: 4,
+                iowait: 5,
+                irq: 6,
+                softirq: 7,
+                ..Default::default()
+            })
+        );
+
+        let ten = CpuLoad::parse("65304 0 19622 159533 1390 0 2 876 0 0").unwrap();
+        assert_eq!(ten.idle, 159533);
+        assert_eq!(ten.steal, 876);
+        assert_eq!(ten.total(), 65304 + 19622 + 159533 + 1390 + 2 + 876);
+
+        // Columns of future kernels count as idle.
+        let twelve = CpuLoad::parse("1 2 3 4 5 6 7 8 9 10 11 12").unwrap();
+        assert_eq!(twelve.guest_nice, 10);
+        assert_eq!(twelve.idle, 4 + 11 + 12);
+
+        assert_eq!(CpuLoad::parse("1 2 3"), None);
+        assert_eq!(CpuLoad::parse("1 2 3 x"), None);
+        assert_eq!(CpuLoad::parse(""), None);
+    }
+
+    #[test]
+    fn test_cpu_stat_parse() {
+        let stat = CpuStat::parse(
+            "cpu  40 0 20 140 0 0 0 0 0 0
+cpu0 10 0 5 35 0 0 0 0 0 0
+cpu1 10 0 5 35 0 0 0 0 0 0
+cpu3 20 0 10 70 0 0 0 0 0 0
+intr 407022 0 0
+cpufreq 1
+ctxt 12345
+",
+        );
+
+        assert_eq!(stat.total.user, 40);
+        assert_eq!(stat.cpus.keys().copied().collect::<Vec<_>>(), [0, 1, 3]);
+        assert_eq!(stat.cpus[&3].idle, 70);
+    }
+
+    #[test]
+    fn test_cpu_render() {
+        let previous = CpuLoad::parse("100 0 100 100 0 0 0 0").unwrap();
+        let current = CpuLoad::parse("150 0 125 200 0 0 0 0 0 0 0 0").unwrap();
+        assert_eq!(
+            current.render(&previous),
+            "%Cpu(s): 28.6 us, 14.3 sy,  0.0 ni, 57.1 id,  0.0 wa,  0.0 hi,  0.0 si,  0.0 st"
+        );
+
+        // Guest time is part of user and nice time, and not counted twice.
+        let previous = CpuLoad::parse("100 0 100 100 0 0 0 0 0 0").unwrap();
+        let current = CpuLoad::parse("200 40 125 200 10 0 5 20 60 30").unwrap();
+        let rendered = current.render(&previous);
+        assert_eq!(
+            rendered,
+            "%Cpu(s): 33.3 us,  8.3 sy, 13.3 ni, 33.3 id,  3.3 wa,  0.0 hi,  1.7 si,  6.7 st"
+        );
+        let sum: f64 = rendered
+            .split_whitespace()
+            .filter_map(|word| word.parse::<f64>().ok())
+            .sum();
+        assert!((sum - 100.0).abs() < 0.5, "{sum}");
+
+        // No time passed, or the counters went backwards.
+        assert_eq!(
+            previous.render(&current),
+            "%Cpu(s):  0.0 us,  0.0 sy,  0.0 ni,  0.0 id,  0.0 wa,  0.0 hi,  0.0 si,  0.0 st"
+        );
+    }
+
+    #[test]
+    fn test_load_class() {
+        assert_eq!(load_class(0.0, 4), LoadClass::Low);
+        assert_eq!(load_class(2.7, 4), LoadClass::Low);
+        assert_eq!(load_class(2.8, 4), LoadClass::Medium);
+        assert_eq!(load_class(3.9, 4), LoadClass::Medium);
+        assert_eq!(load_class(4.0, 4), LoadClass::High);
+        assert_eq!(load_class(1.0, 0), LoadClass::High);
+    }
+
+    #[test]
+    fn test_trend() {
+        assert_eq!(trend(1.0, 1.0), Trend::Steady);
+        assert_eq!(trend(1.5, 1.0), Trend::Rising);
+        assert_eq!(trend(0.5, 1.0), Trend::Falling);
+    }
+
+    #[test]
+    fn test_render_mono() {
+        let uptime = Uptime {
+            uptime: 3600,
+            users: 1,
+            load_average: (1.5, 1.0, 0.5),
+        };
+
+        let rendered = uptime.render(false, 2);
+        assert!(!rendered.contains('\x1b'));
+        assert!(!rendered.contains('↑'));
+        assert!(rendered.ends_with("up  1:00,  1 user,  load average: 1.50, 1.00, 0.50"));
+    }
+
+    #[test]
+    fn test_render_colorful() {
+        let uptime = Uptime {
+            uptime: 60,
+            users: 2,
+            load_average: (1.5, 1.0, 0.5),
+        };
+
+        let rendered = uptime.render(true, 2);
+        assert!(rendered.ends_with(&format!(
+            "load average: {YELLOW}1.50{RESET}↑, {GREEN}1.00{RESET}, {GREEN}0.50{RESET}"
+        )));
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(59), "0 min");
+        assert_eq!(format_uptime(600), "10 min");
+        assert_eq!(format_uptime(3660), " 1:01");
+        assert_eq!(format_uptime(86400 + 7200), "1 day,  2:00");
+        assert_eq!(format_uptime(3 * 86400 + 60), "3 days, 1 min");
+    }
+
+    #[test]
+    fn test_parse_pressure() {
+        let with_full = "some avg10=1.23 avg60=0.50 avg300=0.10 total=123456
+full avg10=0.40 avg60=0.20 avg300=0.05 total=23456
+";
+        assert_eq!(parse_pressure(with_full), Some(1.23));
+
+        let without_full = "some avg10=12.50 avg60=3.00 avg300=1.00 total=99\n";
+        assert_eq!(parse_pressure(without_full), Some(12.5));
+
+        assert_eq!(
+            parse_pressure("full avg10=0.40 avg60=0.20 avg300=0.05 total=1\n"),
+            None
+        );
+        assert_eq!(parse_pressure("some avg10=x avg60=0.20\n"), None);
+        assert_eq!(parse_pressure(""), None);
+    }
+
+    #[test]
+    fn test_render_pressure() {
+        assert_eq!(
+            render_pressure(1.23, 0.0, 3.46),
+            "Pressure: cpu 1.2% | mem 0.0% | io 3.5% (avg10)"
+        );
+    }
+}
@@ -3,12 +3,11 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+use crate::header::{format_task_memory, MemoryUnit};
 use std::{
+    cmp::Ordering,
     ffi::OsString,
-    fs::File,
-    io::read_to_string,
-    path::PathBuf,
-    str::FromStr,
+    fs::read_to_string,
     sync::{OnceLock, RwLock},
 };
 use sysinfo::{Pid, System, Users};
@@ -19,25 +18,97 @@ pub fn sysinfo() -> &'static RwLock<System> {
     SYSINFO.get_or_init(|| RwLock::new(System::new_all()))
 }
 
-pub(crate) fn pickers(fields: &[String]) -> Vec<Box<dyn Fn(u32) -> String>> {
+/// What the PR column shows for real-time processes, their priorities don't fit the column.
+pub(crate) const REALTIME: &str = "rt";
+
+/// Longest COMMAND kept for a row in bytes, no display is wide enough to need more.
+pub(crate) const COMMAND_LIMIT: usize = 4096;
+
+/// Widths of the task memory columns, values that don't fit move to a coarser unit.
+const VIRT_WIDTH: usize = 7;
+const RES_WIDTH: usize = 6;
+const SHR_WIDTH: usize = 6;
+
+/// Default width of the CGROUP column.
+pub(crate) const CGROUP_WIDTH: usize = 16;
+
+/// Pickers are only constructed for the displayed fields, so costly ones like CGROUP
+/// don't touch /proc unless their column is enabled.
+pub(crate) fn pickers(
+    fields: &[String],
+    cgroup_width: usize,
+    task_unit: MemoryUnit,
+) -> Vec<Box<dyn Fn(u32) -> String>> {
     fields
         .iter()
         .map(|field| match field.as_str() {
             "PID" => helper(pid),
             "USER" => helper(user),
             "PR" => helper(pr),
-            "RES" => helper(res),
-            "SHR" => helper(shr),
+            "NI" => helper(ni),
+            "VIRT" => helper(move |pid| virt(pid, task_unit)),
+            "RES" => helper(move |pid| res(pid, task_unit)),
+            "SHR" => helper(move |pid| shr(pid, task_unit)),
             "S" => helper(s),
             "%CPU" => helper(cpu),
             "TIME+" => helper(time_plus),
             "%MEM" => helper(mem),
             "COMMAND" => helper(command),
+            "CGROUP" => helper(move |pid| cgroup(pid, cgroup_width)),
             _ => helper(todo),
         })
         .collect()
 }
 
+/// What a row is sorted by with `-o`.
+///
+/// Numeric fields compare the underlying values rather than the cells, which may be scaled
+/// like `1.2g` or formatted like `1:02.03`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SortValue {
+    Number(f64),
+    Text(String),
+}
+
+impl SortValue {
+    pub(crate) fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            (Self::Number(_), Self::Text(_)) => Ordering::Less,
+            (Self::Text(_), Self::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// The value of `field` for `pid` to sort by.
+pub(crate) fn sort_value(field: &str, pid: u32, cgroup_width: usize) -> SortValue {
+    use SortValue::{Number, Text};
+
+    let number = |cell: String| Number(cell.parse().unwrap_or_default());
+
+    match field {
+        "PID" => Number(pid.into()),
+        // Real-time priorities are below all others.
+        "PR" => match pr(pid) {
+            cell if cell == REALTIME => Number(-100.0),
+            cell => number(cell),
+        },
+        "NI" => number(ni(pid)),
+        "VIRT" => Number(virtual_memory(pid) as f64),
+        "RES" => Number(resident_memory(pid) as f64),
+        "SHR" => Number(shared_memory(pid).unwrap_or_default() as f64),
+        "%CPU" => Number(cpu_usage(pid).into()),
+        "%MEM" => Number(memory_share(pid).into()),
+        "TIME+" => Number(cpu_time(pid).unwrap_or_default() as f64),
+        "USER" => Text(user(pid)),
+        "S" => Text(s(pid)),
+        "COMMAND" => Text(command(pid)),
+        "CGROUP" => Text(cgroup(pid, cgroup_width)),
+        _ => Text(todo(pid)),
+    }
+}
+
 #[inline]
 fn helper(f: impl Fn(u32) -> String + 'static) -> Box<dyn Fn(u32) -> String> {
     Box::new(f)
@@ -47,15 +118,15 @@ fn todo(_pid: u32) -> String {
     "TODO".into()
 }
 
-fn cpu(pid: u32) -> String {
+fn cpu_usage(pid: u32) -> f32 {
     let binding = sysinfo().read().unwrap();
-    let Some(proc) = binding.process(Pid::from_u32(pid)) else {
-        return "0.0".into();
-    };
-
-    let usage = proc.cpu_usage();
+    binding
+        .process(Pid::from_u32(pid))
+        .map_or(0.0, |proc| proc.cpu_usage())
+}
 
-    format!("{:.2}", usage)
+fn cpu(pid: u32) -> String {
+    format!("{:.2}", cpu_usage(pid))
 }
 
 fn pid(pid: u32) -> String {
@@ -76,7 +147,47 @@ fn user(pid: u32) -> String {
     .to_string()
 }
 
-#[cfg(not(target_os = "windows"))]
+/// The fields of `/proc/<pid>/stat` following the command name, i.e. starting with the state.
+#[cfg(target_os = "linux")]
+fn stat_fields(pid: u32) -> Option<Vec<String>> {
+    let stat = read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let (_, fields) = stat.rsplit_once(") ")?;
+    Some(fields.split_whitespace().map(String::from).collect())
+}
+
+/// Format the kernel's priority like procps does.
+///
+/// Normal processes have a priority of 0 to 39, real-time ones are negative
+/// (-1 - rt_priority, or -101 for deadline tasks) and idle injection threads report
+/// `i32::MAX`, none of which fit the column.
+pub(crate) fn format_priority(priority: i64) -> String {
+    if (0..=999).contains(&priority) {
+        priority.to_string()
+    } else {
+        REALTIME.into()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pr(pid: u32) -> String {
+    stat_fields(pid)
+        .and_then(|fields| fields.get(15)?.parse().ok())
+        .map_or_else(|| "?".into(), format_priority)
+}
+
+#[cfg(target_os = "linux")]
+fn ni(pid: u32) -> String {
+    stat_fields(pid)
+        .and_then(|fields| fields.get(16).cloned())
+        .unwrap_or_else(|| "?".into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn ni(pid: u32) -> String {
+    todo(pid)
+}
+
+#[cfg(all(not(target_os = "windows"), not(target_os = "linux")))]
 fn pr(pid: u32) -> String {
     use libc::{getpriority, PRIO_PROCESS};
     use nix::errno::Errno;
@@ -99,12 +210,55 @@ fn pr(_pid: u32) -> String {
     "0".into()
 }
 
-fn res(_pid: u32) -> String {
-    "TODO".into()
+fn virtual_memory(pid: u32) -> u64 {
+    let binding = sysinfo().read().unwrap();
+    binding
+        .process(Pid::from_u32(pid))
+        .map_or(0, |proc| proc.virtual_memory())
 }
 
-fn shr(_pid: u32) -> String {
-    "TODO".into()
+fn resident_memory(pid: u32) -> u64 {
+    let binding = sysinfo().read().unwrap();
+    binding
+        .process(Pid::from_u32(pid))
+        .map_or(0, |proc| proc.memory())
+}
+
+fn virt(pid: u32, unit: MemoryUnit) -> String {
+    format_task_memory(virtual_memory(pid), unit, VIRT_WIDTH)
+}
+
+fn res(pid: u32, unit: MemoryUnit) -> String {
+    format_task_memory(resident_memory(pid), unit, RES_WIDTH)
+}
+
+/// Resident shared pages from `/proc/<pid>/statm`, in bytes.
+#[cfg(target_os = "linux")]
+fn shared_memory(pid: u32) -> Option<u64> {
+    let statm = read_to_string(format!("/proc/{}/statm", pid)).ok()?;
+    let pages = statm.split_whitespace().nth(2)?.parse::<u64>().ok()?;
+    // SAFETY: `sysconf` only reads a configuration value, failing with -1 at worst.
+    let page_size = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    };
+
+    Some(pages * page_size)
+}
+
+#[cfg(target_os = "linux")]
+fn shr(pid: u32, unit: MemoryUnit) -> String {
+    format_task_memory(shared_memory(pid).unwrap_or_default(), unit, SHR_WIDTH)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn shared_memory(_pid: u32) -> Option<u64> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn shr(pid: u32, _unit: MemoryUnit) -> String {
+    todo(pid)
 }
 
 fn s(pid: u32) -> String {
@@ -122,66 +276,134 @@ fn s(pid: u32) -> String {
         .to_string()
 }
 
+/// Width of the TIME+ column.
+const TIME_PLUS_WIDTH: usize = 10;
+
+/// Representation of accumulated CPU time, from the most to the least precise.
+///
+/// Like procps, a value which doesn't fit the column is shown with the next
+/// coarser representation. Values are truncated, never rounded up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum TimeScale {
+    /// `MM:SS.hh`
+    #[default]
+    Hundredths,
+    /// `MM:SS`
+    Seconds,
+    /// `HH,MM`
+    Minutes,
+    /// `DDd+HH:MM`
+    Days,
+    /// `WWw+Dd`
+    Weeks,
+}
+
+impl TimeScale {
+    fn coarser(self) -> Option<Self> {
+        match self {
+            Self::Hundredths => Some(Self::Seconds),
+            Self::Seconds => Some(Self::Minutes),
+            Self::Minutes => Some(Self::Days),
+            Self::Days => Some(Self::Weeks),
+            Self::Weeks => None,
+        }
+    }
+
+    fn format(self, hundredths: u64) -> String {
+        let seconds = hundredths / 100;
+        let minutes = seconds / 60;
+        let hours = minutes / 60;
+        let days = hours / 24;
+
+        match self {
+            Self::Hundredths => {
+                format!("{}:{:0>2}.{:0>2}", minutes, seconds % 60, hundredths % 100)
+            }
+            Self::Seconds => format!("{}:{:0>2}", minutes, seconds % 60),
+            Self::Minutes => format!("{},{:0>2}", hours, minutes % 60),
+            Self::Days => format!("{}d+{:0>2}:{:0>2}", days, hours % 24, minutes % 60),
+            Self::Weeks => format!("{}w+{}d", days / 7, days % 7),
+        }
+    }
+}
+
+/// Format a CPU time given in hundredths of a second for the TIME+ column.
+pub(crate) fn format_time_plus(hundredths: u64, scale: TimeScale) -> String {
+    let mut scale = scale;
+
+    loop {
+        let formatted = scale.format(hundredths);
+
+        match scale.coarser() {
+            Some(next) if formatted.len() > TIME_PLUS_WIDTH => scale = next,
+            _ => return formatted,
+        }
+    }
+}
+
 fn time_plus(pid: u32) -> String {
-    let binding = sysinfo().read().unwrap();
-    let Some(proc) = binding.process(Pid::from_u32(pid)) else {
-        return "0:00.00".into();
+    format_time_plus(cpu_time(pid).unwrap_or_default(), TimeScale::default())
+}
+
+/// Accumulated user and system CPU time of the process, in hundredths of a second.
+#[cfg(target_os = "linux")]
+fn cpu_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces, the remaining fields start after its ')'.
+    let fields = stat
+        .rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    let utime = fields.get(11)?.parse::<u64>().ok()?;
+    let stime = fields.get(12)?.parse::<u64>().ok()?;
+
+    // SAFETY: `sysconf` only reads a configuration value, failing with -1 at worst.
+    let ticks = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
     };
 
-    let (hour, min, sec) = {
-        let total = proc.run_time();
-        let hour = total / 3600;
-        let minute = (total % 3600) / 60;
-        let second = total % 60;
+    Some((utime + stime) * 100 / ticks)
+}
 
-        (hour, minute, second)
-    };
+#[cfg(not(target_os = "linux"))]
+fn cpu_time(pid: u32) -> Option<u64> {
+    let binding = sysinfo().read().unwrap();
+    let proc = binding.process(Pid::from_u32(pid))?;
 
-    format!("{}:{:0>2}.{:0>2}", hour, min, sec)
+    Some(proc.run_time() * 100)
 }
 
-fn mem(pid: u32) -> String {
+fn memory_share(pid: u32) -> f32 {
     let binding = sysinfo().read().unwrap();
     let Some(proc) = binding.process(Pid::from_u32(pid)) else {
-        return "0.0".into();
+        return 0.0;
     };
 
-    format!(
-        "{:.1}",
-        proc.memory() as f32 / sysinfo().read().unwrap().total_memory() as f32
-    )
+    proc.memory() as f32 / binding.total_memory() as f32
+}
+
+fn mem(pid: u32) -> String {
+    format!("{:.1}", memory_share(pid))
 }
 
 fn command(pid: u32) -> String {
     let f = |cmd: &[OsString]| -> String {
-        let binding = cmd
-            .iter()
-            .map(|os_str| os_str.to_string_lossy().into_owned())
-            .collect::<Vec<_>>()
-            .join(" ");
+        let binding = join_command_line(cmd, COMMAND_LIMIT);
         let trimmed = binding.trim();
 
         let result: String = trimmed.into();
 
         if cfg!(target_os = "linux") && result.is_empty() {
-            {
-                match PathBuf::from_str(&format!("/proc/{}/status", pid)) {
-                    Ok(path) => {
-                        let file = File::open(path).unwrap();
-                        let content = read_to_string(file).unwrap();
-                        let line = content
-                            .lines()
-                            .collect::<Vec<_>>()
-                            .first()
-                            .unwrap()
-                            .split(':')
-                            .collect::<Vec<_>>();
-
-                        line[1].trim().to_owned()
-                    }
-                    Err(_) => String::new(),
-                }
-            }
+            // The process may have exited since the last refresh.
+            read_to_string(format!("/proc/{}/status", pid))
+                .ok()
+                .and_then(|content| {
+                    let line = content.lines().next()?;
+                    line.split_once(':').map(|(_, name)| name.trim().to_owned())
+                })
+                .unwrap_or_default()
         } else {
             result
         }
@@ -192,9 +414,281 @@ fn command(pid: u32) -> String {
         return "?".into();
     };
 
-    proc.exe()
-        .and_then(|it| it.iter().last())
-        .map(|it| it.to_str().unwrap())
-        .unwrap_or(&f(proc.cmd()))
-        .into()
+    // Only join the command line when there is no executable name, it can be huge.
+    let command = proc
+        .exe()
+        .and_then(|it| it.iter().next_back())
+        .map_or_else(|| f(proc.cmd()), |it| it.to_string_lossy().into_owned());
+    truncate_right(command, COMMAND_LIMIT)
+}
+
+/// Join the arguments of a command line with spaces, stopping once the result is longer than
+/// `limit` bytes. Command lines can be hundreds of KB, there is no need to copy more than
+/// [truncate_right] keeps.
+fn join_command_line(cmd: &[OsString], limit: usize) -> String {
+    let mut joined = String::new();
+
+    for arg in cmd {
+        if joined.len() > limit {
+            break;
+        }
+        if !joined.is_empty() {
+            joined.push(' ');
+        }
+
+        let arg = arg.to_string_lossy();
+        // A byte past the limit is enough to tell that the command line was cut.
+        let mut end = arg.len().min((limit + 1).saturating_sub(joined.len()));
+        while !arg.is_char_boundary(end) {
+            end += 1;
+        }
+        joined.push_str(&arg[..end]);
+    }
+
+    joined
+}
+
+/// Cut `input` to at most `limit` bytes, marking the cut with a trailing `…`. The cut never
+/// splits a character.
+fn truncate_right(mut input: String, limit: usize) -> String {
+    const ELLIPSIS: char = '…';
+
+    if input.len() <= limit {
+        return input;
+    }
+
+    // Limits too small for the marker just cut.
+    let marker = Some(ELLIPSIS).filter(|it| it.len_utf8() <= limit);
+    let mut end = limit - marker.map_or(0, char::len_utf8);
+    while !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    input.truncate(end);
+    input.extend(marker);
+    input
+}
+
+fn cgroup(pid: u32, width: usize) -> String {
+    match cgroup_v2_path(pid) {
+        Some(path) => truncate_left(cgroup_name(&path), width),
+        None => "-".into(),
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    static CGROUP_READS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// The cgroup v2 path of the process, e.g. `/system.slice/docker-abc123.scope`.
+fn cgroup_v2_path(pid: u32) -> Option<String> {
+    #[cfg(test)]
+    CGROUP_READS.with(|reads| reads.set(reads.get() + 1));
+
+    let content = read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    // The unified hierarchy is the one with id 0 and no controllers.
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(String::from)
+}
+
+/// The last component of a cgroup path, which names the container or unit.
+fn cgroup_name(path: &str) -> &str {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some((_, name)) => name,
+        None => "/",
+    }
+}
+
+/// Cut `input` down to `width` characters, keeping its end as that is the distinctive part
+/// of names like `docker-<id>.scope`. A leading `+` marks the truncation.
+fn truncate_left(input: &str, width: usize) -> String {
+    let len = input.chars().count();
+
+    if len <= width {
+        input.into()
+    } else if width == 0 {
+        String::new()
+    } else {
+        let mut result = String::from("+");
+        result.extend(input.chars().skip(len - width + 1));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_right() {
+        assert_eq!(truncate_right("java".into(), 4), "java");
+        assert_eq!(truncate_right("java -jar".into(), 4), "j…");
+        assert_eq!(truncate_right("java".into(), 2), "ja");
+        assert_eq!(truncate_right("java".into(), 0), "");
+
+        // A synthetic 1 MB command line is capped for storage.
+        let capped = truncate_right("x".repeat(1 << 20), COMMAND_LIMIT);
+        assert_eq!(capped.len(), COMMAND_LIMIT);
+        assert!(capped.ends_with("x…"));
+
+        // "é" is two bytes, the cut lands in the middle of one and backs off.
+        let capped = truncate_right("é".repeat(COMMAND_LIMIT), COMMAND_LIMIT);
+        assert_eq!(capped.len(), COMMAND_LIMIT - 1);
+        assert!(capped.ends_with("é…"));
+    }
+
+    #[test]
+    fn test_join_command_line() {
+        let cmd = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            join_command_line(&cmd(&["java", "-jar", "app"]), 100),
+            "java -jar app"
+        );
+        assert_eq!(join_command_line(&cmd(&[]), 100), "");
+        // One byte past the limit tells that the rest was left out.
+        assert_eq!(
+            join_command_line(&cmd(&["java", "-jar", "app"]), 6),
+            "java -j"
+        );
+        assert_eq!(
+            join_command_line(&cmd(&["java", "-jar", "app"]), 4),
+            "java "
+        );
+        assert_eq!(join_command_line(&cmd(&["é"]), 0), "é");
+
+        // A synthetic 1 MB command line is never joined in full.
+        let huge = vec![OsString::from("x".repeat(1024)); 1024];
+        let joined = join_command_line(&huge, COMMAND_LIMIT);
+        assert_eq!(joined.len(), COMMAND_LIMIT + 1);
+        let capped = truncate_right(joined, COMMAND_LIMIT);
+        assert_eq!(capped.len(), COMMAND_LIMIT);
+        assert!(capped.ends_with("x…"));
+        let joined = join_command_line(&[OsString::from("x".repeat(1 << 20))], COMMAND_LIMIT);
+        assert_eq!(joined.len(), COMMAND_LIMIT + 1);
+    }
+
+    #[test]
+    fn test_format_priority() {
+        assert_eq!(format_priority(20), "20");
+        assert_eq!(format_priority(0), "0");
+        assert_eq!(format_priority(39), "39");
+        // SCHED_FIFO/SCHED_RR with rt_priority 50 and 99, and SCHED_DEADLINE
+        assert_eq!(format_priority(-51), REALTIME);
+        assert_eq!(format_priority(-100), REALTIME);
+        assert_eq!(format_priority(-101), REALTIME);
+        // idle injection
+        assert_eq!(format_priority(i32::MAX.into()), REALTIME);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_pr_ni_own_process() {
+        let pid = std::process::id();
+        let nice: i64 = ni(pid).parse().unwrap();
+        assert_eq!(pr(pid), (20 + nice).to_string());
+    }
+
+    #[test]
+    fn test_cgroup_name() {
+        assert_eq!(
+            cgroup_name("/system.slice/docker-abc123.scope"),
+            "docker-abc123.scope"
+        );
+        assert_eq!(cgroup_name("/user.slice/"), "user.slice");
+        assert_eq!(cgroup_name("/"), "/");
+        assert_eq!(cgroup_name(""), "/");
+    }
+
+    #[test]
+    fn test_truncate_left() {
+        assert_eq!(truncate_left("init.scope", 16), "init.scope");
+        assert_eq!(
+            truncate_left("docker-0123456789abcdef.scope", 16),
+            "+789abcdef.scope"
+        );
+        assert_eq!(truncate_left("abcdef", 6), "abcdef");
+        assert_eq!(truncate_left("abcdef", 5), "+cdef");
+        assert_eq!(truncate_left("abcdef", 1), "+");
+        assert_eq!(truncate_left("abcdef", 0), "");
+    }
+
+    #[test]
+    fn test_cgroup_lazy() {
+        let reads = || CGROUP_READS.with(|reads| reads.get());
+        let pid = std::process::id();
+
+        let fields = ["PID", "COMMAND"].map(String::from);
+        let before = reads();
+        for picker in pickers(&fields, CGROUP_WIDTH, MemoryUnit::KiB) {
+            picker(pid);
+        }
+        assert_eq!(reads(), before);
+
+        let fields = ["PID", "CGROUP"].map(String::from);
+        let row: Vec<_> = pickers(&fields, CGROUP_WIDTH, MemoryUnit::KiB)
+            .iter()
+            .map(|picker| picker(pid))
+            .collect();
+        assert_eq!(reads(), before + 1);
+        assert!(row[1].chars().count() <= CGROUP_WIDTH);
+    }
+
+    #[test]
+    fn test_format_time_plus() {
+        use TimeScale::*;
+
+        const SECOND: u64 = 100;
+        const HOUR: u64 = 3600 * SECOND;
+        const DAY: u64 = 24 * HOUR;
+
+        let table: [(u64, [&str; 5]); 5] = [
+            (0, ["0:00.00", "0:00", "0,00", "0d+00:00", "0w+0d"]),
+            (5999, ["0:59.99", "0:59", "0,00", "0d+00:00", "0w+0d"]),
+            (HOUR, ["60:00.00", "60:00", "1,00", "0d+01:00", "0w+0d"]),
+            (
+                26 * HOUR,
+                ["1560:00.00", "1560:00", "26,00", "1d+02:00", "0w+1d"],
+            ),
+            (
+                10 * DAY,
+                ["14400:00", "14400:00", "240,00", "10d+00:00", "1w+3d"],
+            ),
+        ];
+
+        for (hundredths, expected) in table {
+            for (scale, expected) in [Hundredths, Seconds, Minutes, Days, Weeks]
+                .into_iter()
+                .zip(expected)
+            {
+                assert_eq!(
+                    format_time_plus(hundredths, scale),
+                    expected,
+                    "{hundredths} with {scale:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_time_plus_overflow() {
+        const DAY: u64 = 24 * 3600 * 100;
+
+        assert_eq!(
+            format_time_plus(700 * DAY, TimeScale::Hundredths),
+            "1008000:00"
+        );
+        assert_eq!(
+            format_time_plus(7000 * DAY, TimeScale::Hundredths),
+            "168000,00"
+        );
+        assert_eq!(format_time_plus(10_000 * DAY, TimeScale::Days), "1428w+4d");
+        // Weeks are the last resort, even when they don't fit.
+        assert_eq!(
+            format_time_plus(10_000_000 * DAY, TimeScale::Hundredths),
+            "1428571w+3d"
+        );
+    }
 }
@@ -4,57 +4,237 @@
 // file that was distributed with this source code.
 
 use clap::{arg, crate_version, value_parser, ArgAction, ArgGroup, ArgMatches, Command};
+use export::Export;
+use header::{
+    cpu_count, cpu_line, pressure_line, sample_cpu, sample_swap, Memory, MemoryUnit, Uptime,
+};
 use picker::pickers;
 use picker::sysinfo;
+use picker::{sort_value, SortValue, CGROUP_WIDTH};
 use prettytable::{format::consts::FORMAT_CLEAN, Row, Table};
-use std::{thread::sleep, time::Duration};
+use std::{
+    env,
+    io::IsTerminal,
+    thread::sleep,
+    time::{Duration, Instant},
+};
 use sysinfo::{Pid, Users};
 use uucore::{
     error::{UResult, USimpleError},
-    format_usage, help_about, help_usage,
+    format_usage, help_about, help_usage, show_warning,
 };
 
 const ABOUT: &str = help_about!("top.md");
 const USAGE: &str = help_usage!("top.md");
 
+mod export;
 mod field;
+mod header;
 mod picker;
 
-#[allow(unused)]
 #[derive(Debug)]
 enum Filter {
     Pid(Vec<u32>),
-    User(String),
-    EUser(String),
+    User(UserFilter),
+}
+
+/// The user given to `-u` or `-U`, like procps a leading `!` selects everyone else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UserFilter {
+    uid: u32,
+    /// Match any of the real, effective, saved and filesystem uids (`-U`), not only the
+    /// effective one (`-u`).
+    any: bool,
+    negate: bool,
+}
+
+impl UserFilter {
+    fn parse(value: &str, any: bool) -> UResult<Self> {
+        let (negate, user) = match value.strip_prefix('!') {
+            Some(user) => (true, user),
+            None => (false, value),
+        };
+
+        Ok(Self {
+            uid: try_into_uid(user)?,
+            any,
+            negate,
+        })
+    }
+
+    /// Whether a process with the real, effective, saved and filesystem `uids` is shown.
+    fn matches(&self, uids: [u32; 4]) -> bool {
+        let matched = if self.any {
+            uids.contains(&self.uid)
+        } else {
+            uids[1] == self.uid
+        };
+        matched != self.negate
+    }
 }
 
+/// Environment variables providing defaults for `-d` and `-n`, e.g. for containers.
+const DELAY_ENV: &str = "TOP_DELAY";
+const ITERATIONS_ENV: &str = "TOP_ITERATIONS";
+
+/// Default delay between updates, in seconds.
+const DEFAULT_DELAY: Duration = Duration::from_secs(3);
+// TODO: Keep updating until interrupted once there is an interactive mode.
+const DEFAULT_ITERATIONS: u64 = 1;
+
 #[derive(Debug)]
 struct Settings {
     // batch:bool
     filter: Option<Filter>,
+    delay: Duration,
+    iterations: u64,
     width: Option<usize>,
+    /// Width of the CGROUP column, if it is shown.
+    cgroup: Option<usize>,
+    sort: Option<SortKey>,
+    /// Unit of the memory summary lines
+    summary_unit: MemoryUnit,
+    /// Unit of the VIRT, RES and SHR columns
+    task_unit: MemoryUnit,
+    /// Show the pressure stall line below the uptime line.
+    pressure: bool,
+    /// Leave out kernel threads, for a userland only view.
+    hide_kernel_threads: bool,
 }
 
 impl Settings {
     fn new(matches: &ArgMatches) -> Self {
         let width = matches.get_one::<usize>("width").cloned();
 
+        let delay = setting_from(
+            matches.get_one::<Duration>("delay").copied(),
+            DELAY_ENV,
+            env::var(DELAY_ENV).ok(),
+            parse_delay,
+            DEFAULT_DELAY,
+        );
+        let iterations = setting_from(
+            matches.get_one::<u64>("iterations").copied(),
+            ITERATIONS_ENV,
+            env::var(ITERATIONS_ENV).ok(),
+            parse_iterations,
+            DEFAULT_ITERATIONS,
+        );
+
         Self {
             width,
+            cgroup: matches.contains_id("cgroup").then(|| {
+                matches
+                    .get_one::<usize>("cgroup")
+                    .copied()
+                    .unwrap_or(CGROUP_WIDTH)
+            }),
+            sort: matches
+                .get_one::<String>("sort-override")
+                .map(|field| SortKey::new(field)),
+            summary_unit: matches
+                .get_one::<String>("scale-summary-mem")
+                .and_then(|scale| MemoryUnit::from_scale(scale))
+                .unwrap_or_default(),
+            task_unit: matches
+                .get_one::<String>("scale-task-mem")
+                .and_then(|scale| MemoryUnit::from_scale(scale))
+                .unwrap_or(MemoryUnit::KiB),
+            pressure: matches.get_flag("pressure"),
+            hide_kernel_threads: matches.get_flag("hide-kernel-threads"),
             filter: None,
+            delay,
+            iterations,
         }
     }
 }
 
+/// The field given with `-o`. Like procps, rows are sorted high to low unless the field
+/// is prefixed with `-`, a `+` prefix is accepted as well.
+#[derive(Debug, PartialEq, Eq)]
+struct SortKey {
+    field: String,
+    descending: bool,
+}
+
+impl SortKey {
+    fn new(value: &str) -> Self {
+        match value.split_at(value.len().min(1)) {
+            ("-", field) => Self {
+                field: field.into(),
+                descending: false,
+            },
+            ("+", field) => Self {
+                field: field.into(),
+                descending: true,
+            },
+            _ => Self {
+                field: value.into(),
+                descending: true,
+            },
+        }
+    }
+
+    /// Sort `items` by the values of the field they are paired with.
+    fn sort<T>(&self, items: &mut [(SortValue, T)]) {
+        items.sort_by(|(a, _), (b, _)| {
+            let ordering = a.compare(b);
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
+
+/// Pick a setting from the command line, then the environment, then the default.
+///
+/// An invalid environment value only produces a warning, as it is easy to get wrong in
+/// templated deployments and shouldn't prevent top from starting.
+fn setting_from<T>(
+    flag: Option<T>,
+    env_name: &str,
+    env_value: Option<String>,
+    parse: fn(&str) -> Result<T, String>,
+    default: T,
+) -> T {
+    if let Some(value) = flag {
+        return value;
+    }
+
+    match env_value.as_deref().map(parse) {
+        Some(Ok(value)) => value,
+        Some(Err(e)) => {
+            show_warning!("ignoring {}: {}", env_name, e);
+            default
+        }
+        None => default,
+    }
+}
+
+/// Parse a delay in seconds. Negative values, and those too large for a [Duration], are
+/// rejected.
+fn parse_delay(value: &str) -> Result<Duration, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|delay| Duration::try_from_secs_f64(delay).ok())
+        .ok_or_else(|| format!("invalid delay time '{}'", value))
+}
+
+fn parse_iterations(value: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(iterations) if iterations > 0 => Ok(iterations),
+        _ => Err(format!("invalid iterations '{}'", value)),
+    }
+}
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
 
-    // Must refresh twice.
-    // https://docs.rs/sysinfo/0.31.2/sysinfo/struct.System.html#method.refresh_cpu_usage
-    picker::sysinfo().write().unwrap().refresh_all();
-    sleep(Duration::from_millis(200));
-    picker::sysinfo().write().unwrap().refresh_all();
+    sample_baseline();
 
     let settings = Settings::new(&matches);
 
@@ -62,30 +242,139 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         let filter = matches
             .get_many::<u32>("pid")
             .map(|pidlist| Filter::Pid(pidlist.cloned().collect()))
+            .map(Ok)
             .or_else(|| {
                 matches
                     .get_one::<String>("filter-any-user")
-                    .map(|user| Filter::User(user.clone()))
+                    .map(|user| UserFilter::parse(user, true).map(Filter::User))
             })
             .or_else(|| {
                 matches
                     .get_one::<String>("filter-only-euser")
-                    .map(|euser| Filter::EUser(euser.clone()))
-            });
-
-        let filter = match filter {
-            Some(Filter::User(data)) => Some(Filter::User(try_into_uid(data)?)),
-            // TODO: Make sure this working
-            Some(Filter::EUser(data)) => Some(Filter::EUser(try_into_uid(data)?)),
-            _ => filter,
-        };
+                    .map(|euser| UserFilter::parse(euser, false).map(Filter::User))
+            })
+            .transpose()?;
 
         Settings { filter, ..settings }
     };
 
-    let fields = selected_fields();
-    let collected = collect(&settings, &fields);
+    let fields = selected_fields(&settings);
+    let mut export = matches.get_one::<String>("export").map(Export::new);
+
+    if let Some(sort) = &settings.sort {
+        if !fields.contains(&sort.field) {
+            return Err(USimpleError::new(
+                1,
+                format!("unrecognized field name '{}'", sort.field),
+            ));
+        }
+    }
 
+    let mut schedule = Schedule::new(settings.delay, Instant::now());
+    for iteration in 0..settings.iterations {
+        if iteration > 0 {
+            sleep(schedule.remaining(Instant::now()));
+            if schedule.is_stale(Instant::now()) {
+                // Usage averaged over the time top was stopped says little about now.
+                sample_baseline();
+            } else {
+                picker::sysinfo().write().unwrap().refresh_all();
+            }
+            println!();
+        }
+
+        let rows = collect_rows(&settings, &fields);
+        print_frame(&settings, &fields, &rows);
+
+        if let Some(export) = &mut export {
+            export.append(iteration + 1, &fields, &rows).map_err(|e| {
+                USimpleError::new(
+                    1,
+                    format!("failed to write '{}': {}", export.path().display(), e),
+                )
+            })?;
+        }
+
+        schedule.reschedule(Instant::now());
+    }
+
+    Ok(())
+}
+
+/// Take the samples CPU usage and swap activity are computed against, both for the summary
+/// and the tasks.
+fn sample_baseline() {
+    // Must refresh twice.
+    // https://docs.rs/sysinfo/0.31.2/sysinfo/struct.System.html#method.refresh_cpu_usage
+    picker::sysinfo().write().unwrap().refresh_all();
+    sample_cpu();
+    sample_swap();
+    sleep(Duration::from_millis(200));
+    picker::sysinfo().write().unwrap().refresh_all();
+}
+
+/// Below this, a late frame is not considered stale even with a short delay.
+const MIN_STALE_GAP: Duration = Duration::from_secs(1);
+
+/// When the next frame is due.
+///
+/// The deadline counts from the end of the previous frame, so time top spent stopped, e.g.
+/// by Ctrl-Z or a frozen cgroup, is skipped rather than made up for with a burst of frames.
+#[derive(Debug)]
+struct Schedule {
+    delay: Duration,
+    /// When the previous frame was done
+    last: Instant,
+}
+
+impl Schedule {
+    fn new(delay: Duration, now: Instant) -> Self {
+        Self { delay, last: now }
+    }
+
+    /// `None` if the deadline is too far ahead to be represented, as good as never.
+    fn deadline(&self) -> Option<Instant> {
+        self.last.checked_add(self.delay)
+    }
+
+    /// How long to wait at `now` for the next frame.
+    fn remaining(&self, now: Instant) -> Duration {
+        self.deadline().map_or(self.delay, |deadline| {
+            deadline.saturating_duration_since(now)
+        })
+    }
+
+    /// Whether a frame at `now` comes more than twice the delay after the previous one,
+    /// so that usage sampled back then should not be compared against.
+    fn is_stale(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last) > self.delay.saturating_mul(2).max(MIN_STALE_GAP)
+    }
+
+    fn reschedule(&mut self, now: Instant) {
+        self.last = now;
+    }
+}
+
+/// The rows of the task area, sorted as requested.
+fn collect_rows(settings: &Settings, fields: &[String]) -> Vec<Vec<String>> {
+    let cgroup_width = settings.cgroup.unwrap_or(CGROUP_WIDTH);
+    let pickers = pickers(fields, cgroup_width, settings.task_unit);
+    let render = |pid: u32| pickers.iter().map(|picker| picker(pid)).collect::<Vec<_>>();
+    let pids = collect_pids(settings).into_iter();
+
+    let Some(sort) = &settings.sort else {
+        return pids.map(render).collect();
+    };
+    // Some values like TIME+ are read live, so each is read right along with its row to keep
+    // the order consistent with what is shown.
+    let mut rows = pids
+        .map(|pid| (sort_value(&sort.field, pid, cgroup_width), render(pid)))
+        .collect::<Vec<_>>();
+    sort.sort(&mut rows);
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+fn print_frame(settings: &Settings, fields: &[String], collected: &[Vec<String>]) {
     let table = {
         let mut table = Table::new();
 
@@ -97,105 +386,146 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         table
     };
 
-    println!("{}", header());
-    println!("\n");
-
-    let cutter = {
-        #[inline]
-        fn f(f: impl Fn(&str) -> String + 'static) -> Box<dyn Fn(&str) -> String> {
-            Box::new(f)
-        }
-
-        if let Some(width) = settings.width {
-            f(move |line: &str| apply_width(line, width))
-        } else {
-            f(|line: &str| line.to_string())
-        }
-    };
-
-    table
-        .to_string()
-        .lines()
-        .map(cutter)
-        .for_each(|it| println!("{}", it));
+    let mut lines = vec![header()];
+    // Left out silently on kernels without pressure stall information.
+    if settings.pressure {
+        lines.extend(pressure_line());
+    }
+    lines.push(cpu_line());
+    lines.extend(Memory::new().render(settings.summary_unit));
+    lines.push(String::new());
+    lines.extend(table.to_string().lines().map(String::from));
 
-    Ok(())
+    for line in layout(lines, settings.width) {
+        println!("{}", line);
+    }
 }
 
-fn try_into_uid<T>(input: T) -> UResult<String>
-where
-    T: Into<String>,
-{
-    let into: String = input.into();
-
-    if into.parse::<u32>().is_ok() {
-        return Ok(into);
+fn try_into_uid(user_name: &str) -> UResult<u32> {
+    if let Ok(uid) = user_name.parse::<u32>() {
+        return Ok(uid);
     }
 
-    let user_name = into;
     let users = Users::new_with_refreshed_list();
 
     users
         .iter()
         .find(|it| it.name() == user_name)
-        .map(|it| it.id().to_string())
+        .and_then(|it| it.id().to_string().parse().ok())
         .ok_or(USimpleError::new(1, "Invalid user"))
 }
 
+/// The real, effective, saved and filesystem uids from the `Uid:` line of a status file.
+fn parse_status_uids(status: &str) -> Option<[u32; 4]> {
+    let mut uids = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))?
+        .split_whitespace()
+        .map(|uid| uid.parse().ok());
+
+    Some([uids.next()??, uids.next()??, uids.next()??, uids.next()??])
+}
+
+#[cfg(target_os = "linux")]
+fn process_uids(pid: u32) -> Option<[u32; 4]> {
+    parse_status_uids(&std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?)
+}
+
+// Other systems don't tell the saved and filesystem uids apart from the effective one.
+// https://docs.rs/sysinfo/0.31.3/sysinfo/struct.Process.html#method.effective_user_id
+#[cfg(not(target_os = "linux"))]
+fn process_uids(pid: u32) -> Option<[u32; 4]> {
+    let binding = sysinfo().read().unwrap();
+    let proc = binding.process(Pid::from_u32(pid))?;
+    let uid = proc.user_id()?.to_string().parse().ok()?;
+    let euid = proc.effective_user_id()?.to_string().parse().ok()?;
+
+    Some([uid, euid, euid, euid])
+}
+
+/// Below this width the summary and the field columns no longer fit.
+const MIN_WIDTH: usize = 40;
+
+const TOO_SMALL: &str = "terminal too small";
+
+/// Fit the output lines into `width` columns. If the width is smaller than
+/// `MIN_WIDTH` only a short notice is printed instead of a garbled layout.
+fn layout(lines: Vec<String>, width: Option<usize>) -> Vec<String> {
+    match width {
+        None => lines,
+        Some(width) if width < MIN_WIDTH => vec![apply_width(TOO_SMALL, width)],
+        Some(width) => lines
+            .into_iter()
+            .map(|line| apply_width(line, width))
+            .collect(),
+    }
+}
+
 fn apply_width<T>(input: T, width: usize) -> String
 where
     T: Into<String>,
 {
     let input: String = input.into();
+    let len = input.chars().count();
 
-    if input.len() > width {
+    if len > width {
         input.chars().take(width).collect()
     } else {
         let mut result = String::from(&input);
-        result.extend(std::iter::repeat(' ').take(width - input.len()));
+        result.extend(std::iter::repeat_n(' ', width.saturating_sub(len)));
         result
     }
 }
 
-// TODO: Implement information collecting.
+// TODO: Add the "Tasks:" summary line.
 fn header() -> String {
-    "TODO".into()
+    let colorful = std::io::stdout().is_terminal();
+
+    Uptime::new().render(colorful, cpu_count())
 }
 
 // TODO: Implement fields selecting
-fn selected_fields() -> Vec<String> {
-    vec![
-        "PID", "USER", "PR", "NI", "VIRT", "RES", "SHR", "S", "%CPU", "%MEM", "TIME+", "COMMAND",
-    ]
-    .into_iter()
-    .map(Into::into)
-    .collect()
-}
+fn selected_fields(settings: &Settings) -> Vec<String> {
+    let mut fields = vec![
+        "PID", "USER", "PR", "NI", "VIRT", "RES", "SHR", "S", "%CPU", "%MEM", "TIME+",
+    ];
+    if settings.cgroup.is_some() {
+        fields.push("CGROUP");
+    }
+    fields.push("COMMAND");
 
-fn collect(settings: &Settings, fields: &[String]) -> Vec<Vec<String>> {
-    let pickers = pickers(fields);
+    fields.into_iter().map(Into::into).collect()
+}
 
+/// The pids of the processes shown.
+fn collect_pids(settings: &Settings) -> Vec<u32> {
     let pids = sysinfo()
         .read()
         .unwrap()
         .processes()
-        .iter()
-        .map(|(it, _)| it.as_u32())
+        .keys()
+        .map(|it| it.as_u32())
         .collect::<Vec<_>>();
 
     let filter = construct_filter(settings);
 
     pids.into_iter()
         .filter(|pid| filter(*pid))
-        .map(|it| {
-            pickers
-                .iter()
-                .map(move |picker| picker(it))
-                .collect::<Vec<_>>()
-        })
+        .filter(|pid| !(settings.hide_kernel_threads && is_kernel_thread(*pid)))
         .collect()
 }
 
+/// Kernel threads are kthreadd and its children, none of them has a command line.
+fn is_kernel_thread(pid: u32) -> bool {
+    let kthreadd = Pid::from_u32(2);
+    let binding = sysinfo().read().unwrap();
+    let Some(proc) = binding.process(Pid::from_u32(pid)) else {
+        return false;
+    };
+
+    proc.cmd().is_empty() && (proc.pid() == kthreadd || proc.parent() == Some(kthreadd))
+}
+
 /// Constructing filter from `Settings`
 fn construct_filter(settings: &Settings) -> Box<dyn Fn(u32) -> bool> {
     let Some(ref filter) = settings.filter else {
@@ -213,39 +543,8 @@ fn construct_filter(settings: &Settings) -> Box<dyn Fn(u32) -> bool> {
         }
 
         Filter::User(user) => {
-            let user = user.to_owned();
-
-            helper(move |pid| {
-                let binding = sysinfo().read().unwrap();
-                let Some(proc) = binding.process(Pid::from_u32(pid)) else {
-                    return false;
-                };
-
-                let Some(uid) = proc.user_id() else {
-                    return false;
-                };
-
-                uid.to_string() == user
-            })
-        }
-
-        // Doesn't work on Windows.
-        // https://docs.rs/sysinfo/0.31.3/sysinfo/struct.Process.html#method.effective_user_id
-        Filter::EUser(euser) => {
-            let euser = euser.to_owned();
-
-            helper(move |pid| {
-                let binding = sysinfo().read().unwrap();
-                let Some(proc) = binding.process(Pid::from_u32(pid)) else {
-                    return false;
-                };
-
-                let Some(euid) = proc.effective_user_id() else {
-                    return false;
-                };
-
-                euid.to_string() == euser
-            })
+            let user = *user;
+            helper(move |pid| process_uids(pid).is_some_and(|uids| user.matches(uids)))
         }
     }
 }
@@ -258,26 +557,317 @@ pub fn uu_app() -> Command {
         .override_usage(format_usage(USAGE))
         .infer_long_args(true)
         .args([
-            // arg!(-b  --"batch-mode"                         "run in non-interactive batch mode"),
+            arg!(-b  --"batch-mode"                         "run in non-interactive batch mode"),
             // arg!(-c  --"cmdline-toggle"                     "reverse last remembered 'c' state"),
-            // arg!(-d  --delay                <SECS>          "iterative delay as SECS [.TENTHS]"),
-            // arg!(-E  --"scale-summary-mem"  <SCALE>         "set mem as: k,m,g,t,p,e for SCALE"),
-            // arg!(-e  --"scale-task-mem"     <SCALE>         "set mem with: k,m,g,t,p for SCALE"),
+            arg!(-d  --delay                <SECS>          "iterative delay as SECS [.TENTHS]")
+                .value_parser(parse_delay),
+            arg!(-E  --"scale-summary-mem"  <SCALE>         "set mem as: k,m,g,t,p,e for SCALE")
+                .value_parser(["k", "m", "g", "t", "p", "e"]),
+            arg!(-e  --"scale-task-mem"     <SCALE>         "set mem with: k,m,g,t,p for SCALE")
+                .value_parser(["k", "m", "g", "t", "p"]),
             // arg!(-H  --"threads-show"                       "show tasks plus all their threads"),
             // arg!(-i  --"idle-toggle"                        "reverse last remembered 'i' state"),
-            // arg!(-n  --iterations           <NUMBER>        "exit on maximum iterations NUMBER"),
+            arg!(-n  --iterations           <NUMBER>        "exit on maximum iterations NUMBER")
+                .value_parser(parse_iterations),
             arg!(-O  --"list-fields"                        "output all field names, then exit"),
-            // arg!(-o  --"sort-override"      <FIELD>         "force sorting on this named FIELD"),
+            arg!(-o  --"sort-override"      <FIELD>         "force sorting on this named FIELD"),
             arg!(-p  --pid                  <PIDLIST>       "monitor only the tasks in PIDLIST")
                 .action(ArgAction::Append)
                 .value_parser(value_parser!(u32))
                 .value_delimiter(','),
             // arg!(-S  --"accum-time-toggle"                  "reverse last remembered 'S' state"),
             // arg!(-s  --"secure-mode"                        "run with secure mode restrictions"),
-            arg!(-U  --"filter-any-user"    <USER>          "show only processes owned by USER"),
-            arg!(-u  --"filter-only-euser"  <EUSER>         "show only processes owned by USER"),
-            arg!(-w  --width                <COLUMNS>       "change print width [,use COLUMNS]"),
+            arg!(-U  --"filter-any-user"    <USER>          "show only processes with USER as any uid, prefix ! to exclude"),
+            arg!(-u  --"filter-only-euser"  <EUSER>         "show only processes with EUSER as effective uid, prefix ! to exclude"),
+            arg!(-w  --width                <COLUMNS>       "change print width [,use COLUMNS]")
+                .value_parser(value_parser!(usize)),
+            arg!(    --cgroup               [WIDTH]         "show the CGROUP column, WIDTH wide")
+                .value_parser(value_parser!(usize)),
+            arg!(    --export               <FILE>          "also write the task list as CSV to FILE"),
+            arg!(    --pressure                             "show the pressure stall information line"),
+            arg!(    --"hide-kernel-threads"                "leave out kthreadd and its children"),
             // arg!(-1  --single-cpu-toggle         "reverse last remembered '1' state"),
         ])
         .group(ArgGroup::new("filter").args(["pid", "filter-any-user", "filter-only-euser"]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<String> {
+        vec![
+            "top - 12:00:00 up 1 min,  1 user,  load average: 0.00, 0.00, 0.00".into(),
+            "  PID USER      PR  NI    VIRT    RES    SHR S  %CPU  %MEM     TIME+ COMMAND".into(),
+        ]
+    }
+
+    #[test]
+    fn test_layout_narrow() {
+        for width in [1, 10, 39] {
+            let lines = layout(sample(), Some(width));
+            assert_eq!(lines.len(), 1);
+            assert_eq!(lines[0].chars().count(), width);
+            assert!(TOO_SMALL.starts_with(lines[0].trim_end()));
+        }
+    }
+
+    #[test]
+    fn test_layout_minimum() {
+        let lines = layout(sample(), Some(MIN_WIDTH));
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|it| it.chars().count() == MIN_WIDTH));
+    }
+
+    #[test]
+    fn test_layout_unlimited() {
+        assert_eq!(layout(sample(), None), sample());
+    }
+
+    #[test]
+    fn test_memory_units() {
+        let settings = |args: &[&str]| {
+            uu_app()
+                .try_get_matches_from(["top"].iter().chain(args))
+                .map(|matches| Settings::new(&matches))
+        };
+
+        let defaults = settings(&[]).unwrap();
+        assert_eq!(defaults.summary_unit, MemoryUnit::MiB);
+        assert_eq!(defaults.task_unit, MemoryUnit::KiB);
+
+        for (scale, unit) in [
+            ("k", MemoryUnit::KiB),
+            ("m", MemoryUnit::MiB),
+            ("g", MemoryUnit::GiB),
+            ("t", MemoryUnit::TiB),
+            ("p", MemoryUnit::PiB),
+        ] {
+            assert_eq!(settings(&["-E", scale]).unwrap().summary_unit, unit);
+            assert_eq!(settings(&["-e", scale]).unwrap().task_unit, unit);
+        }
+        assert_eq!(
+            settings(&["-E", "e"]).unwrap().summary_unit,
+            MemoryUnit::EiB
+        );
+
+        // Like procps, exabytes are only offered for the summary.
+        assert!(settings(&["-e", "e"]).is_err());
+        assert!(settings(&["-e", "x"]).is_err());
+        assert!(settings(&["-E", "x"]).is_err());
+        assert!(settings(&["-e", "G"]).is_err());
+    }
+
+    #[test]
+    fn test_setting_precedence() {
+        let delay = |flag: Option<Duration>, env: Option<&str>| {
+            setting_from(
+                flag,
+                DELAY_ENV,
+                env.map(String::from),
+                parse_delay,
+                DEFAULT_DELAY,
+            )
+        };
+
+        assert_eq!(delay(None, None), DEFAULT_DELAY);
+        assert_eq!(delay(None, Some("0.5")), Duration::from_millis(500));
+        assert_eq!(
+            delay(Some(Duration::from_secs(2)), None),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            delay(Some(Duration::from_secs(2)), Some("0.5")),
+            Duration::from_secs(2)
+        );
+        assert_eq!(delay(None, Some("-1")), DEFAULT_DELAY);
+        assert_eq!(delay(None, Some("soon")), DEFAULT_DELAY);
+        assert_eq!(delay(None, Some("1e25")), DEFAULT_DELAY);
+        assert_eq!(delay(None, Some("inf")), DEFAULT_DELAY);
+
+        let iterations = |flag: Option<u64>, env: Option<&str>| {
+            setting_from(
+                flag,
+                ITERATIONS_ENV,
+                env.map(String::from),
+                parse_iterations,
+                DEFAULT_ITERATIONS,
+            )
+        };
+
+        assert_eq!(iterations(None, None), DEFAULT_ITERATIONS);
+        assert_eq!(iterations(None, Some("3")), 3);
+        assert_eq!(iterations(Some(2), None), 2);
+        assert_eq!(iterations(Some(2), Some("3")), 2);
+        assert_eq!(iterations(None, Some("0")), DEFAULT_ITERATIONS);
+        assert_eq!(iterations(None, Some("many")), DEFAULT_ITERATIONS);
+    }
+
+    #[test]
+    fn test_sort_key() {
+        let key = |field: &str, descending| SortKey {
+            field: field.into(),
+            descending,
+        };
+
+        assert_eq!(SortKey::new("%CPU"), key("%CPU", true));
+        assert_eq!(SortKey::new("+CGROUP"), key("CGROUP", true));
+        assert_eq!(SortKey::new("-CGROUP"), key("CGROUP", false));
+        assert_eq!(SortKey::new(""), key("", true));
+    }
+
+    #[test]
+    fn test_sort_values() {
+        use SortValue::{Number, Text};
+
+        let sorted = |key: &str, values: &[SortValue]| {
+            let mut items = values
+                .iter()
+                .cloned()
+                .map(|value| (value, ()))
+                .collect::<Vec<_>>();
+            SortKey::new(key).sort(&mut items);
+            items
+                .into_iter()
+                .map(|(value, _)| value)
+                .collect::<Vec<_>>()
+        };
+
+        // TIME+ in hundredths: 10:00.00 is more than 9:00.00.
+        assert_eq!(
+            sorted(
+                "TIME+",
+                &[Number(54_000.0), Number(60_000.0), Number(100.0)]
+            ),
+            [Number(60_000.0), Number(54_000.0), Number(100.0)]
+        );
+        // RES in bytes: 308.1m is more than 8.2m, whatever the cells look like.
+        assert_eq!(
+            sorted("-RES", &[Number(323_066_265.0), Number(8_598_323.0)]),
+            [Number(8_598_323.0), Number(323_066_265.0)]
+        );
+        // Real-time priorities come first.
+        assert_eq!(
+            sorted("-PR", &[Number(20.0), Number(-100.0), Number(-51.0)]),
+            [Number(-100.0), Number(-51.0), Number(20.0)]
+        );
+        assert_eq!(
+            sorted("-CGROUP", &[Text("user.slice".into()), Text("-".into())]),
+            [Text("-".into()), Text("user.slice".into())]
+        );
+    }
+
+    #[test]
+    fn test_apply_width_multibyte() {
+        assert_eq!(apply_width("1.00↑", 6), "1.00↑ ");
+        assert_eq!(apply_width("1.00↑", 5), "1.00↑");
+        assert_eq!(apply_width("", 0), "");
+    }
+
+    #[test]
+    fn test_schedule_deadline() {
+        let start = Instant::now();
+        let delay = Duration::from_secs(3);
+        let mut schedule = Schedule::new(delay, start);
+
+        assert_eq!(schedule.remaining(start), delay);
+        assert_eq!(
+            schedule.remaining(start + Duration::from_secs(1)),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            schedule.remaining(start + Duration::from_secs(5)),
+            Duration::ZERO
+        );
+
+        // Stopped for a minute: the next frame is one delay after the late one, not right away.
+        let resumed = start + Duration::from_secs(60);
+        assert_eq!(schedule.remaining(resumed), Duration::ZERO);
+        schedule.reschedule(resumed);
+        assert_eq!(schedule.remaining(resumed), delay);
+        assert_eq!(schedule.deadline(), Some(resumed + delay));
+    }
+
+    #[test]
+    fn test_schedule_stale() {
+        let start = Instant::now();
+        let schedule = Schedule::new(Duration::from_secs(3), start);
+
+        assert!(!schedule.is_stale(start + Duration::from_secs(3)));
+        assert!(!schedule.is_stale(start + Duration::from_secs(6)));
+        assert!(schedule.is_stale(start + Duration::from_millis(6001)));
+        assert!(schedule.is_stale(start + Duration::from_secs(60)));
+
+        // Short delays still tolerate some scheduling jitter.
+        let schedule = Schedule::new(Duration::ZERO, start);
+        assert!(!schedule.is_stale(start + Duration::from_millis(500)));
+        assert!(schedule.is_stale(start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_schedule_huge_delay() {
+        let start = Instant::now();
+        let delay = parse_delay("1e19").unwrap();
+        let schedule = Schedule::new(delay, start);
+
+        assert_eq!(schedule.deadline(), None);
+        assert_eq!(schedule.remaining(start), delay);
+        assert!(!schedule.is_stale(start + Duration::from_secs(60)));
+
+        assert!(parse_delay("1e20").is_err());
+        assert!(parse_delay("NaN").is_err());
+    }
+
+    #[test]
+    fn test_user_filter_parse() {
+        let filter = |uid, any, negate| UserFilter { uid, any, negate };
+
+        assert_eq!(
+            UserFilter::parse("1000", true).unwrap(),
+            filter(1000, true, false)
+        );
+        assert_eq!(
+            UserFilter::parse("!1000", false).unwrap(),
+            filter(1000, false, true)
+        );
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(
+                UserFilter::parse("root", false).unwrap(),
+                filter(0, false, false)
+            );
+            assert_eq!(
+                UserFilter::parse("!root", true).unwrap(),
+                filter(0, true, true)
+            );
+        }
+
+        for invalid in ["NOT_EXIST", "!NOT_EXIST", "!", "!!0"] {
+            assert!(UserFilter::parse(invalid, true).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_user_filter_matches() {
+        // A setuid program: started by 1000, running as 0.
+        let status = "Name:\tpasswd\nUid:\t1000\t0\t0\t0\nGid:\t1000\t1000\t1000\t1000\n";
+        let uids = parse_status_uids(status).unwrap();
+        assert_eq!(uids, [1000, 0, 0, 0]);
+
+        let matches = |uid, any, negate| UserFilter { uid, any, negate }.matches(uids);
+        // -u checks the effective uid only.
+        assert!(matches(0, false, false));
+        assert!(!matches(1000, false, false));
+        assert!(!matches(0, false, true));
+        assert!(matches(1000, false, true));
+        // -U checks each of them.
+        assert!(matches(0, true, false));
+        assert!(matches(1000, true, false));
+        assert!(!matches(1000, true, true));
+        assert!(!matches(42, true, false));
+        assert!(matches(42, true, true));
+
+        assert_eq!(parse_status_uids("Uid:\t1000\t0\n"), None);
+        assert_eq!(parse_status_uids("Name:\tinit\n"), None);
+    }
+}
@@ -7,21 +7,31 @@
 use chrono::Datelike;
 use clap::crate_version;
 use clap::{Arg, ArgAction, Command};
-#[cfg(target_os = "linux")]
-use libc::{sysconf, _SC_CLK_TCK};
-use std::process;
+use std::ops::RangeInclusive;
 #[cfg(target_os = "linux")]
 use std::{collections::HashMap, fs, path::Path};
+use std::{env, process};
+#[cfg(target_os = "linux")]
+use uu_pgrep::process::{clock_ticks, walk_process, ProcessInformation, Teletype};
 #[cfg(target_os = "linux")]
 use uucore::utmpx::Utmpx;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
+use uucore::{error::UResult, format_usage, help_about, help_usage, show_warning};
 
 const ABOUT: &str = help_about!("w.md");
 const USAGE: &str = help_usage!("w.md");
 
+const USERLEN_ENV: &str = "PROCPS_USERLEN";
+const USERLEN_DEFAULT: usize = 8;
+const USERLEN_RANGE: RangeInclusive<usize> = 8..=32;
+const FROMLEN_ENV: &str = "PROCPS_FROMLEN";
+const FROMLEN_DEFAULT: usize = 16;
+const FROMLEN_RANGE: RangeInclusive<usize> = 10..=64;
+
 struct UserInfo {
     user: String,
     terminal: String,
+    pid: String,
+    from: String,
     login_time: String,
     idle_time: String,
     jcpu: String,
@@ -29,58 +39,84 @@ struct UserInfo {
     command: String,
 }
 
+/// The parts of a process needed for the JCPU, PCPU and WHAT columns.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq)]
+struct ProcessSample {
+    pid: usize,
+    /// Device number of the controlling terminal, 0 if there is none
+    tty_nr: u64,
+    /// Whether the process belongs to the foreground process group of its terminal
+    foreground: bool,
+    /// Start time and CPU time, in clock ticks
+    start_time: u64,
+    cpu_time: u64,
+}
+
 #[cfg(target_os = "linux")]
-fn fetch_terminal_jcpu() -> Result<HashMap<u64, f64>, std::io::Error> {
-    // Iterate over all pid folders in /proc and build a HashMap with their terminals and cpu usage.
-    let pid_dirs = fs::read_dir("/proc")?
-        .filter_map(Result::ok)
-        .filter(|entry| entry.path().is_dir())
-        .filter_map(|entry| {
-            entry
-                .path()
-                .file_name()
-                .and_then(|s| s.to_os_string().into_string().ok())
+impl ProcessSample {
+    fn new(process: &mut ProcessInformation) -> Option<Self> {
+        Some(Self {
+            pid: process.pid,
+            tty_nr: process.tty_nr().ok()?,
+            foreground: process.tpgid().ok()? == process.pgrp().ok()? as i64,
+            start_time: process.start_time().ok()?,
+            cpu_time: process.cpu_time().ok()?,
         })
-        // Check to see if directory is an integer (pid)
-        .filter_map(|pid_dir_str| pid_dir_str.parse::<i32>().ok());
-    let mut pid_hashmap = HashMap::new();
-    for pid in pid_dirs {
-        // Fetch terminal number for current pid
-        let terminal_number = fetch_terminal_number(pid)?;
-        // Get current total CPU time for current pid
-        let pcpu_time = fetch_pcpu_time(pid)?;
-        // Update HashMap with found terminal number and add pcpu time for current pid
-        *pid_hashmap.entry(terminal_number).or_insert(0.0) += pcpu_time;
     }
-    Ok(pid_hashmap)
 }
 
+/// CPU usage of the processes attached to one terminal.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, PartialEq)]
+struct TerminalUsage {
+    /// CPU time of all processes on the terminal, in clock ticks
+    jcpu: u64,
+    /// The process shown as WHAT: like procps, the most recently started process of
+    /// the foreground process group
+    foreground: Option<ProcessSample>,
+}
+
+// Group the processes by their controlling terminal, so that each session row is a lookup
+// instead of another walk over all processes.
 #[cfg(target_os = "linux")]
-fn fetch_terminal_number(pid: i32) -> Result<u64, std::io::Error> {
-    let stat_path = Path::new("/proc").join(pid.to_string()).join("stat");
-    // Separate stat and get terminal number, which is at position 6
-    let f = fs::read_to_string(stat_path)?;
-    let stat: Vec<&str> = f.split_whitespace().collect();
-    Ok(stat[6].parse().unwrap_or_default())
+fn group_by_terminal(
+    processes: impl IntoIterator<Item = ProcessSample>,
+) -> HashMap<u64, TerminalUsage> {
+    let mut terminals: HashMap<u64, TerminalUsage> = HashMap::new();
+    for process in processes.into_iter().filter(|it| it.tty_nr != 0) {
+        let usage = terminals.entry(process.tty_nr).or_default();
+        usage.jcpu += process.cpu_time;
+
+        // `None` sorts before any process.
+        let latest = usage.foreground.as_ref().map(|it| (it.start_time, it.pid));
+        if process.foreground && latest < Some((process.start_time, process.pid)) {
+            usage.foreground = Some(process);
+        }
+    }
+    terminals
 }
 
+// Format a duration like procps does for the IDLE, JCPU and PCPU columns: `0.05s` below a
+// minute, `1:05` for minutes and seconds, `2:15m` for hours and minutes and `3days` from two
+// days on.
 #[cfg(target_os = "linux")]
-fn get_clock_tick() -> i64 {
-    unsafe { sysconf(_SC_CLK_TCK) }
+fn format_duration(centiseconds: u64) -> String {
+    let seconds = centiseconds / 100;
+    if seconds >= 48 * 60 * 60 {
+        format!("{}days", seconds / (24 * 60 * 60))
+    } else if seconds >= 60 * 60 {
+        format!("{}:{:02}m", seconds / (60 * 60), seconds / 60 % 60)
+    } else if seconds > 60 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("{}.{:02}s", seconds, centiseconds % 100)
+    }
 }
 
 #[cfg(target_os = "linux")]
-fn fetch_pcpu_time(pid: i32) -> Result<f64, std::io::Error> {
-    let stat_path = Path::new("/proc").join(pid.to_string()).join("stat");
-    // Seperate stat file by whitespace and get utime and stime, which are at
-    // positions 13 and 14 (0-based), respectively.
-    let f = fs::read_to_string(stat_path)?;
-    let stat: Vec<&str> = f.split_whitespace().collect();
-    // Parse utime and stime to f64
-    let utime: f64 = stat[13].parse().unwrap_or_default();
-    let stime: f64 = stat[14].parse().unwrap_or_default();
-    // Divide by clock tick to get actual time
-    Ok((utime + stime) / get_clock_tick() as f64)
+fn format_cpu_time(ticks: u64) -> String {
+    format_duration(ticks * 100 / clock_ticks())
 }
 
 #[cfg(target_os = "linux")]
@@ -107,30 +143,52 @@ fn fetch_cmdline(pid: i32) -> Result<String, std::io::Error> {
     fs::read_to_string(cmdline_path)
 }
 
+// The pid recorded in utmp may belong to a process that is long gone, e.g. after a crashed
+// session, so it is only shown if it still exists.
+#[cfg(target_os = "linux")]
+fn format_session_pid(pid: i32) -> String {
+    if pid > 0 && Path::new("/proc").join(pid.to_string()).exists() {
+        pid.to_string()
+    } else {
+        "?".into()
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn fetch_user_info() -> Result<Vec<UserInfo>, std::io::Error> {
-    let terminal_jcpu_hm = fetch_terminal_jcpu()?;
+    let terminals = group_by_terminal(
+        walk_process().filter_map(|mut process| ProcessSample::new(&mut process)),
+    );
 
     let mut user_info_list = Vec::new();
     for entry in Utmpx::iter_all_records() {
         if entry.is_user_process() {
-            let mut jcpu: f64 = 0.0;
+            let usage = Teletype::try_from(format!("/dev/{}", entry.tty_device()))
+                .ok()
+                .and_then(|tty| terminals.get(&tty.device_number()));
+            let jcpu = usage.map_or(0, |usage| usage.jcpu);
 
-            if let Ok(terminal_number) = fetch_terminal_number(entry.pid()) {
-                jcpu = terminal_jcpu_hm
-                    .get(&terminal_number)
-                    .cloned()
-                    .unwrap_or_default();
-            }
+            // Without a foreground process on the terminal, fall back to the session leader.
+            let (what_pid, pcpu) = match usage.and_then(|usage| usage.foreground.as_ref()) {
+                Some(foreground) => (foreground.pid as i32, foreground.cpu_time),
+                None => (
+                    entry.pid(),
+                    ProcessInformation::try_new(Path::new("/proc").join(entry.pid().to_string()))
+                        .and_then(|mut process| process.cpu_time())
+                        .unwrap_or_default(),
+                ),
+            };
 
             let user_info = UserInfo {
                 user: entry.user(),
                 terminal: entry.tty_device(),
+                pid: format_session_pid(entry.pid()),
+                from: entry.host(),
                 login_time: format_time(entry.login_time().to_string()).unwrap_or_default(),
                 idle_time: "TODO".into(), // Placeholder, needs actual implementation
-                jcpu: format!("{:.2}", jcpu),
-                pcpu: fetch_pcpu_time(entry.pid()).unwrap_or_default().to_string(),
-                command: fetch_cmdline(entry.pid()).unwrap_or_default(),
+                jcpu: format_cpu_time(jcpu),
+                pcpu: format_cpu_time(pcpu),
+                command: fetch_cmdline(what_pid).unwrap_or_default(),
             };
             user_info_list.push(user_info);
         }
@@ -144,36 +202,120 @@ fn fetch_user_info() -> Result<Vec<UserInfo>, std::io::Error> {
     Ok(Vec::new())
 }
 
+// Returns the column width configured by the environment variable `name`, or `default` if it is
+// unset. Values outside of `range` are clamped, unparsable values are ignored.
+fn column_width(
+    name: &str,
+    value: Option<&str>,
+    default: usize,
+    range: RangeInclusive<usize>,
+) -> usize {
+    let Some(value) = value else {
+        return default;
+    };
+
+    match value.parse::<usize>() {
+        Ok(width) if range.contains(&width) => width,
+        Ok(width) => {
+            let clamped = width.clamp(*range.start(), *range.end());
+            show_warning!(
+                "{} must be between {} and {}, using {}",
+                name,
+                range.start(),
+                range.end(),
+                clamped
+            );
+            clamped
+        }
+        Err(_) => {
+            show_warning!("{} is not a valid number, ignoring", name);
+            default
+        }
+    }
+}
+
+fn env_column_width(name: &str, default: usize, range: RangeInclusive<usize>) -> usize {
+    let value = env::var(name).ok();
+    column_width(name, value.as_deref(), default, range)
+}
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
 
     let no_header = matches.get_flag("no-header");
     let short = matches.get_flag("short");
+    let from = matches.get_flag("from");
+    let pids = matches.get_flag("pids");
+
+    let user_width = env_column_width(USERLEN_ENV, USERLEN_DEFAULT, USERLEN_RANGE);
+    let from_width = env_column_width(FROMLEN_ENV, FROMLEN_DEFAULT, FROMLEN_RANGE);
+
+    // The FROM column is optional, so it carries its own trailing separator.
+    let format_from = |value: &str| {
+        if from {
+            format!("{:<from_width$} ", value)
+        } else {
+            String::new()
+        }
+    };
+
+    // Same for the PID column.
+    let format_pid = |value: &str| {
+        if pids {
+            format!("{:<8}", value)
+        } else {
+            String::new()
+        }
+    };
 
     match fetch_user_info() {
         Ok(user_info) => {
             if !no_header {
                 if short {
-                    println!("{:<9}{:<9}{:<7}{:<}", "USER", "TTY", "IDLE", "WHAT");
+                    println!(
+                        "{:<user_width$} {:<9}{}{}{:<7}{:<}",
+                        "USER",
+                        "TTY",
+                        format_pid("PID"),
+                        format_from("FROM"),
+                        "IDLE",
+                        "WHAT"
+                    );
                 } else {
                     println!(
-                        "{:<9}{:<9}{:<9}{:<6} {:<7}{:<5}{:<}",
-                        "USER", "TTY", "LOGIN@", "IDLE", "JCPU", "PCPU", "WHAT"
+                        "{:<user_width$} {:<9}{}{}{:<9}{:<6} {:<7}{:<5} {:<}",
+                        "USER",
+                        "TTY",
+                        format_pid("PID"),
+                        format_from("FROM"),
+                        "LOGIN@",
+                        "IDLE",
+                        "JCPU",
+                        "PCPU",
+                        "WHAT"
                     );
                 }
             }
             for user in user_info {
                 if short {
                     println!(
-                        "{:<9}{:<9}{:<7}{:<}",
-                        user.user, user.terminal, user.idle_time, user.command
+                        "{:<user_width$} {:<9}{}{}{:<7}{:<}",
+                        user.user,
+                        user.terminal,
+                        format_pid(&user.pid),
+                        format_from(&user.from),
+                        user.idle_time,
+                        user.command
                     );
                 } else {
+                    // Durations may fill the PCPU column, so WHAT is set off by a space.
                     println!(
-                        "{:<9}{:<9}{:<9}{:<6} {:<7}{:<5}{:<}",
+                        "{:<user_width$} {:<9}{}{}{:<9}{:<6} {:<7}{:<5} {:<}",
                         user.user,
                         user.terminal,
+                        format_pid(&user.pid),
+                        format_from(&user.from),
                         user.login_time,
                         user.idle_time,
                         user.jcpu,
@@ -251,7 +393,7 @@ pub fn uu_app() -> Command {
             Arg::new("pids")
                 .short('p')
                 .long("pids")
-                .help("show the PID(s) of processes in WHAT")
+                .help("show the PID of the session leader")
                 .action(ArgAction::SetTrue),
         )
 }
@@ -260,10 +402,31 @@ pub fn uu_app() -> Command {
 #[cfg(target_os = "linux")]
 mod tests {
     use crate::{
-        fetch_cmdline, fetch_pcpu_time, fetch_terminal_number, format_time, get_clock_tick,
+        column_width, fetch_cmdline, format_duration, format_session_pid, format_time,
+        group_by_terminal, ProcessSample, TerminalUsage,
     };
     use std::{fs, path::Path, process};
 
+    #[test]
+    fn test_column_width() {
+        assert_eq!(column_width("TEST", None, 8, 8..=32), 8);
+        assert_eq!(column_width("TEST", Some("20"), 8, 8..=32), 20);
+        assert_eq!(column_width("TEST", Some("8"), 8, 8..=32), 8);
+        assert_eq!(column_width("TEST", Some("32"), 8, 8..=32), 32);
+        assert_eq!(column_width("TEST", Some("2"), 8, 8..=32), 8);
+        assert_eq!(column_width("TEST", Some("100"), 8, 8..=32), 32);
+        assert_eq!(column_width("TEST", Some("abc"), 16, 10..=64), 16);
+        assert_eq!(column_width("TEST", Some("-5"), 16, 10..=64), 16);
+    }
+
+    #[test]
+    fn test_format_session_pid() {
+        let pid = process::id() as i32;
+        assert_eq!(format_session_pid(pid), pid.to_string());
+        assert_eq!(format_session_pid(0), "?");
+        assert_eq!(format_session_pid(i32::MAX), "?");
+    }
+
     #[test]
     fn test_format_time() {
         let unix_epoc = chrono::Local::now()
@@ -295,27 +458,56 @@ mod tests {
         )
     }
 
-    #[test]
-    fn test_fetch_terminal_number() {
-        let pid = process::id() as i32;
-        let path = Path::new("/proc").join(pid.to_string()).join("stat");
-        let f = fs::read_to_string(path).unwrap();
-        let stat: Vec<&str> = f.split_whitespace().collect();
-        let term_num = stat[6];
-        assert_eq!(fetch_terminal_number(pid).unwrap().to_string(), term_num)
+    fn sample(pid: usize, tty_nr: u64, foreground: bool, start_time: u64) -> ProcessSample {
+        ProcessSample {
+            pid,
+            tty_nr,
+            foreground,
+            start_time,
+            cpu_time: pid as u64,
+        }
     }
 
     #[test]
-    fn test_fetch_pcpu_time() {
-        let pid = process::id() as i32;
-        let path = Path::new("/proc").join(pid.to_string()).join("stat");
-        let f = fs::read_to_string(path).unwrap();
-        let stat: Vec<&str> = f.split_whitespace().collect();
-        let utime: f64 = stat[13].parse().unwrap();
-        let stime: f64 = stat[14].parse().unwrap();
+    fn test_group_by_terminal() {
+        let terminals = group_by_terminal([
+            // A login shell on pts/0 running a pipeline in the foreground.
+            sample(10, 34816, false, 100),
+            sample(20, 34816, true, 200),
+            sample(21, 34816, true, 200),
+            sample(15, 34816, true, 150),
+            // A background job on tty1, no foreground process left.
+            sample(30, 1025, false, 300),
+            // Daemons without a terminal are not grouped.
+            sample(40, 0, true, 400),
+        ]);
+
+        assert_eq!(terminals.len(), 2);
+        assert_eq!(terminals[&34816].jcpu, 10 + 20 + 21 + 15);
         assert_eq!(
-            fetch_pcpu_time(pid).unwrap(),
-            (utime + stime) / get_clock_tick() as f64
-        )
+            terminals[&34816].foreground,
+            Some(sample(21, 34816, true, 200))
+        );
+        assert_eq!(
+            terminals[&1025],
+            TerminalUsage {
+                jcpu: 30,
+                foreground: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0.00s");
+        assert_eq!(format_duration(5), "0.05s");
+        assert_eq!(format_duration(1234), "12.34s");
+        assert_eq!(format_duration(6000), "60.00s");
+        assert_eq!(format_duration(6100), "1:01");
+        assert_eq!(format_duration(59 * 6000 + 5900), "59:59");
+        assert_eq!(format_duration(60 * 6000), "1:00m");
+        assert_eq!(format_duration(47 * 360000 + 59 * 6000), "47:59m");
+        assert_eq!(format_duration(48 * 360000), "2days");
+        assert_eq!(format_duration(100 * 24 * 360000), "100days");
     }
 }
@@ -4,13 +4,17 @@
 // file that was distributed with this source code.
 
 use clap::crate_version;
-use clap::{Arg, Command};
-use std::io::{Error, ErrorKind};
+use clap::{Arg, ArgAction, Command};
+use std::io::{self, Error, ErrorKind, Read, Write};
 use std::num::ParseIntError;
-use std::process::{Command as SystemCommand, Stdio};
-use std::thread::sleep;
+use std::process::{Command as SystemCommand, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::thread::{self, sleep};
 use std::time::Duration;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
+use uucore::{
+    error::{UResult, USimpleError},
+    format_usage, help_about, help_usage,
+};
 
 const ABOUT: &str = help_about!("watch.md");
 const USAGE: &str = help_usage!("watch.md");
@@ -52,6 +56,51 @@ fn parse_interval(input: &str) -> Result<Duration, ParseIntError> {
     Ok(std::cmp::max(duration, Duration::from_millis(100)))
 }
 
+/// Run `command` and collect its stdout and, unless `hide_stderr`, its stderr into one
+/// buffer, in the order the chunks arrive like a `2>&1` redirection would.
+fn run_merged(command: &mut SystemCommand, hide_stderr: bool) -> io::Result<(ExitStatus, Vec<u8>)> {
+    let stderr = if hide_stderr {
+        Stdio::null()
+    } else {
+        Stdio::piped()
+    };
+    let mut child = command.stdout(Stdio::piped()).stderr(stderr).spawn()?;
+
+    let output = Mutex::new(Vec::new());
+    let copy = |mut stream: Box<dyn Read + Send>| -> io::Result<()> {
+        let mut buffer = [0; 4096];
+        loop {
+            match stream.read(&mut buffer)? {
+                0 => return Ok(()),
+                n => output.lock().unwrap().extend_from_slice(&buffer[..n]),
+            }
+        }
+    };
+
+    let streams = [
+        child
+            .stdout
+            .take()
+            .map(|it| Box::new(it) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|it| Box::new(it) as Box<dyn Read + Send>),
+    ];
+    thread::scope(|scope| {
+        let readers: Vec<_> = streams
+            .into_iter()
+            .flatten()
+            .map(|stream| scope.spawn(|| copy(stream)))
+            .collect();
+        readers
+            .into_iter()
+            .try_for_each(|reader| reader.join().unwrap())
+    })?;
+
+    Ok((child.wait()?, output.into_inner().unwrap()))
+}
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
@@ -72,6 +121,14 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         },
     };
 
+    let chgexit = matches.get_flag("chgexit");
+    let errexit = matches.get_flag("errexit");
+    let max_iterations = matches.get_one::<u64>("max-iterations").copied();
+    let hide_stderr = matches.get_flag("no-stderr");
+
+    let mut previous_output: Option<Vec<u8>> = None;
+    let mut iterations = 0;
+
     loop {
         #[cfg(windows)]
         let mut command =
@@ -84,21 +141,35 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         #[cfg(not(windows))]
         command.arg("-c");
 
-        let output = command
-            .arg(command_to_watch)
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .output()?;
+        let (status, output) = run_merged(command.arg(command_to_watch), hide_stderr)?;
+        iterations += 1;
 
-        if !output.status.success() {
-            eprintln!("watch: command failed: {:?}", output.status);
-            break;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&output)?;
+        stdout.flush()?;
+
+        if !status.success() {
+            eprintln!("watch: command failed: {:?}", status);
+            return Ok(());
+        }
+
+        if chgexit && previous_output.as_ref().is_some_and(|it| *it != output) {
+            return Ok(());
+        }
+        previous_output = Some(output);
+
+        if max_iterations.is_some_and(|max| iterations >= max) {
+            if chgexit || errexit {
+                return Err(USimpleError::new(
+                    1,
+                    format!("condition not met after {iterations} iterations"),
+                ));
+            }
+            return Ok(());
         }
 
         sleep(interval);
     }
-
-    Ok(())
 }
 
 pub fn uu_app() -> Command {
@@ -149,13 +220,28 @@ pub fn uu_app() -> Command {
             Arg::new("errexit")
                 .short('e')
                 .long("errexit")
-                .help("Exit if command has a non-zero exit"),
+                .help("Exit if command has a non-zero exit")
+                .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("chgexit")
                 .short('g')
                 .long("chgexit")
-                .help("Exit when output from command changes"),
+                .help("Exit when output from command changes")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("max-iterations")
+                .long("max-iterations")
+                .value_name("N")
+                .help("Exit after running the command N times, with status 1 if -e or -g never triggered")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("no-stderr")
+                .long("no-stderr")
+                .help("Discard the command's stderr instead of showing it with stdout")
+                .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("equexit")
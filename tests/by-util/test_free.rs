@@ -83,6 +83,20 @@ fn test_total() {
     }
 }
 
+#[test]
+fn test_legacy_used() {
+    let result = new_ucmd!().arg("--legacy-used").arg("-t").succeeds();
+    let used: Vec<u64> = result
+        .stdout_str()
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().nth(2).unwrap().parse().unwrap())
+        .collect();
+
+    assert_eq!(used.len(), 3);
+    assert_eq!(used[0] + used[1], used[2]);
+}
+
 #[test]
 fn test_count() {
     for arg in ["-c", "--count"] {
@@ -152,6 +166,51 @@ fn test_committed() {
     }
 }
 
+#[test]
+fn test_zswap() {
+    let reported = std::fs::read_to_string("/proc/meminfo")
+        .map(|meminfo| meminfo.contains("\nZswapped:"))
+        .unwrap_or(false);
+
+    let result = new_ucmd!()
+        .args(&["--zswap", "-c", "2", "-s", "0.01"])
+        .succeeds();
+    let rows: Vec<_> = result
+        .stdout_str()
+        .lines()
+        .filter(|line| line.starts_with("Zswap:"))
+        .collect();
+
+    if reported {
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].split_whitespace().count(), 3);
+        result.no_stderr();
+    } else {
+        assert!(rows.is_empty());
+        // Once, not for every sample.
+        result.stderr_is("free: zswap is not reported by the kernel, omitting the Zswap row\n");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_verbose() {
+    let reported = std::fs::read_to_string("/proc/meminfo")
+        .map(|meminfo| meminfo.contains("\nMemAvailable:"))
+        .unwrap_or(false);
+
+    let result = new_ucmd!()
+        .args(&["--verbose", "-c", "2", "-s", "0.01"])
+        .succeeds();
+    if reported {
+        result.no_stderr();
+    } else {
+        result.stderr_is(
+            "free: MemAvailable is not reported by the kernel, estimating available memory\n",
+        );
+    }
+}
+
 #[test]
 fn test_seconds_zero() {
     for arg in ["-s", "--seconds"] {
@@ -110,6 +110,78 @@ fn test_older() {
     }
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_younger() {
+    let mut sleep = Command::new("sleep").arg("5").spawn().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let pid = sleep.id().to_string();
+
+    new_ucmd!()
+        .arg("--younger=1")
+        .arg("sleep")
+        .succeeds()
+        .stdout_contains(&pid);
+
+    let result = new_ucmd!().arg("--older=1").arg("sleep").run();
+    assert!(!result.stdout_str().lines().any(|it| it == pid));
+
+    new_ucmd!()
+        .arg("--older=0.1")
+        .arg("sleep")
+        .succeeds()
+        .stdout_contains(&pid);
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_newest_oldest_after_age_filters() {
+    let pattern = "^sleep 3.0731$";
+    let mut older = Command::new("sleep").arg("3.0731").spawn().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    let mut newer = Command::new("sleep").arg("3.0731").spawn().unwrap();
+
+    for (args, pid) in [
+        (["-n", "--older=0"], newer.id()),
+        (["-o", "--younger=60"], older.id()),
+        // The newest process is too young, so the newest of those left is picked.
+        (["-n", "--older=0.8"], older.id()),
+        (["-o", "--younger=0.8"], newer.id()),
+    ] {
+        new_ucmd!()
+            .args(&args)
+            .args(&["-f", pattern])
+            .succeeds()
+            .stdout_is(format!("{pid}\n"));
+    }
+
+    for args in [["-n", "--older=60"], ["-o", "--older=60"]] {
+        new_ucmd!()
+            .args(&args)
+            .args(&["-f", pattern])
+            .fails()
+            .code_is(1)
+            .no_output();
+    }
+
+    older.kill().unwrap();
+    older.wait().unwrap();
+    newer.kill().unwrap();
+    newer.wait().unwrap();
+}
+
+#[test]
+fn test_older_invalid() {
+    new_ucmd!()
+        .arg("--older=-1")
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid number of seconds");
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_older_matching_pattern() {
@@ -347,3 +419,908 @@ fn test_parent_non_matching_parent() {
         .code_is(1)
         .no_output();
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_patterns_from_file() {
+    let mut sleep = Command::new("sleep").arg("2").spawn().unwrap();
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write(
+        "patterns",
+        "# names to look for\nNON_EXISTING_PROCESS\n\nsleep\n",
+    );
+
+    ts.ucmd()
+        .arg("--patterns-from")
+        .arg("patterns")
+        .succeeds()
+        .stdout_contains(sleep.id().to_string());
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_patterns_from_stdin() {
+    let mut sleep = Command::new("sleep").arg("2").spawn().unwrap();
+
+    new_ucmd!()
+        .arg("--patterns-from=-")
+        .pipe_in("NON_EXISTING_PROCESS\nsleep\n")
+        .succeeds()
+        .stdout_contains(sleep.id().to_string());
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+fn test_patterns_from_conflicts_with_pattern() {
+    new_ucmd!()
+        .arg("--patterns-from=-")
+        .arg("sleep")
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot be used with");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_pidfile() {
+    let mut sleep = Command::new("sleep").arg("2").spawn().unwrap();
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write("pidfile", &format!("{}\n", sleep.id()));
+
+    ts.ucmd()
+        .arg("--pidfile=pidfile")
+        .succeeds()
+        .stdout_is(format!("{}\n", sleep.id()));
+
+    ts.ucmd()
+        .arg("-F")
+        .arg("pidfile")
+        .arg("NON_EXISTING_PROCESS")
+        .fails()
+        .code_is(1)
+        .no_output();
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+fn test_pidfile_selects_nothing() {
+    let ts = TestScenario::new(util_name!());
+
+    // Zero and negative pids name no process, that is no error.
+    for content in ["-5\n", "0\n"] {
+        ts.fixtures.write("pidfile", content);
+        ts.ucmd()
+            .arg("--pidfile=pidfile")
+            .fails()
+            .code_is(1)
+            .no_output();
+        ts.ucmd()
+            .arg("--pidfile=pidfile")
+            .arg("-c")
+            .fails()
+            .code_is(1)
+            .stdout_is("0\n")
+            .no_stderr();
+    }
+}
+
+#[test]
+fn test_pidfile_invalid() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write("pidfile", "not a pid\n");
+
+    ts.ucmd()
+        .arg("--pidfile=pidfile")
+        .fails()
+        .code_is(3)
+        .no_stdout()
+        .stderr_contains("Pidfile pidfile not valid");
+}
+
+#[test]
+fn test_pidfile_nonexistent_file() {
+    new_ucmd!()
+        .arg("--pidfile=NON_EXISTING_FILE")
+        .fails()
+        .code_is(3)
+        .no_stdout()
+        .stderr_contains("Failed to open pidfile 'NON_EXISTING_FILE': No such file or directory");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_pidfile_directory() {
+    let scene = TestScenario::new(util_name!());
+    scene.fixtures.mkdir("pids");
+
+    scene
+        .ucmd()
+        .arg("--pidfile=pids")
+        .fails()
+        .code_is(3)
+        .no_stdout()
+        .stderr_contains("Failed to read pidfile 'pids': Is a directory");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_logpidfile_not_locked() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write("pidfile", "1\n");
+
+    ts.ucmd()
+        .arg("--pidfile=pidfile")
+        .arg("--logpidfile")
+        .fails()
+        .code_is(3)
+        .no_stdout()
+        .stderr_contains("Pidfile pidfile is not locked");
+}
+
+#[test]
+fn test_logpidfile_requires_pidfile() {
+    new_ucmd!()
+        .arg("-L")
+        .arg("sleep")
+        .fails()
+        .code_is(1)
+        .stderr_contains("--pidfile <file>");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_uid_self() {
+    let mut sleep = Command::new("sleep").arg("2.0723").spawn().unwrap();
+
+    for id in ["-Uself", "-uself", "-Gself", "--uid=root,self"] {
+        new_ucmd!()
+            .arg(id)
+            .arg("-f")
+            .arg("^sleep 2.0723$")
+            .succeeds()
+            .stdout_is(format!("{}\n", sleep.id()));
+    }
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+fn test_uid_unknown_name() {
+    new_ucmd!()
+        .arg("-u")
+        .arg("no-such-user")
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid user name: no-such-user");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_ascending_pid_order() {
+    let mut sleeps: [Child; 3] =
+        array::from_fn(|_| Command::new("sleep").arg("2.0314").spawn().unwrap());
+
+    // Unique arguments, so sleeps from tests running in parallel don't interfere.
+    let pgrep = || {
+        new_ucmd!()
+            .arg("-f")
+            .arg("^sleep 2.0314$")
+            .succeeds()
+            .stdout_move_str()
+    };
+    let first = pgrep();
+    let second = pgrep();
+    assert_eq!(first, second);
+
+    let pids: Vec<usize> = first.lines().map(|it| it.parse().unwrap()).collect();
+    assert!(pids.windows(2).all(|it| it[0] < it[1]));
+    assert_eq!(pids.len(), 3);
+    for sleep in &sleeps {
+        assert!(pids.contains(&(sleep.id() as usize)));
+    }
+
+    for sleep in &mut sleeps {
+        sleep.kill().unwrap();
+        sleep.wait().unwrap();
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_full_exact() {
+    let mut sleep = Command::new("sleep").arg("31.5").spawn().unwrap();
+
+    new_ucmd!()
+        .arg("-fx")
+        .arg("sleep 31.5")
+        .succeeds()
+        .stdout_is(format!("{}\n", sleep.id()));
+
+    new_ucmd!().arg("-fx").arg("sleep 31").fails().code_is(1);
+    // The name alone is not the full command line.
+    new_ucmd!().arg("-fx").arg("sleep").fails().code_is(1);
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_lightweight_count() {
+    use std::sync::{Arc, Barrier};
+
+    // Park three named threads in this process until pgrep is done.
+    let barrier = Arc::new(Barrier::new(4));
+    let threads: Vec<_> = (0..3)
+        .map(|i| {
+            let barrier = Arc::clone(&barrier);
+            std::thread::Builder::new()
+                .name(format!("pgw684-{i}"))
+                .spawn(move || barrier.wait())
+                .unwrap()
+        })
+        .collect();
+
+    new_ucmd!()
+        .arg("-w")
+        .arg("-c")
+        .arg("^pgw684-")
+        .succeeds()
+        .stdout_is("3\n");
+
+    let tids = new_ucmd!()
+        .arg("--lightweight")
+        .arg("^pgw684-")
+        .succeeds()
+        .stdout_move_str();
+    for tid in tids.lines() {
+        let comm = std::fs::read_to_string(format!("/proc/self/task/{tid}/comm")).unwrap();
+        assert!(comm.starts_with("pgw684-"));
+    }
+
+    // Without -w only the process name is matched.
+    new_ucmd!()
+        .arg("-c")
+        .arg("^pgw684-")
+        .fails()
+        .code_is(1)
+        .stdout_is("0\n");
+
+    barrier.wait();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_debug_truncated_name() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::process::Stdio;
+
+    // Scripts are named after their file, which the kernel truncates to 15 characters.
+    let name = "pgrepdebug697abcdefg";
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write(name, "#!/bin/sh\nread line\n");
+    std::fs::set_permissions(ts.fixtures.plus(name), PermissionsExt::from_mode(0o755)).unwrap();
+
+    let mut script = loop {
+        match Command::new(ts.fixtures.plus(name))
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            // Another test may have forked while the script was still open for writing.
+            Err(e) if e.raw_os_error() == Some(libc::ETXTBSY) => continue,
+            result => break result.unwrap(),
+        }
+    };
+    let comm = format!("/proc/{}/comm", script.id());
+    while std::fs::read_to_string(&comm).unwrap() != "pgrepdebug697ab\n" {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    ts.ucmd()
+        .arg("--debug")
+        .arg(name)
+        .fails()
+        .code_is(1)
+        .stderr_contains(format!(
+            "pgrep: pid {} (pgrepdebug697ab) not selected: name truncated to 15 chars; try -f\n",
+            script.id()
+        ));
+
+    drop(script.stdin.take());
+    script.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_null() {
+    let mut sleeps: [Child; 2] =
+        array::from_fn(|_| Command::new("sleep").arg("31.712").spawn().unwrap());
+
+    let output = new_ucmd!()
+        .arg("-0")
+        .arg("-f")
+        .arg("^sleep 31.712$")
+        .succeeds()
+        .stdout_move_bytes();
+    assert_eq!(output.iter().filter(|b| **b == 0).count(), 2);
+    assert!(!output.contains(&b'\n'));
+    assert_eq!(output.last(), Some(&0));
+
+    let output = new_ucmd!()
+        .arg("--null")
+        .arg("-a")
+        .arg("-f")
+        .arg("^sleep 31.712$")
+        .succeeds()
+        .stdout_move_str();
+    let expected: String = sleeps
+        .iter()
+        .map(|sleep| format!("{} sleep 31.712\0", sleep.id()))
+        .collect();
+    assert_eq!(output, expected);
+
+    for sleep in &mut sleeps {
+        sleep.kill().unwrap();
+        sleep.wait().unwrap();
+    }
+}
+
+#[test]
+fn test_null_conflicts() {
+    for arg in ["-c", "-d,"] {
+        new_ucmd!()
+            .arg("-0")
+            .arg(arg)
+            .arg("sh")
+            .fails()
+            .stderr_contains("cannot be used with");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_list_start() {
+    let before = chrono::Local::now().timestamp();
+    let mut sleep = Command::new("sleep").arg("31.701").spawn().unwrap();
+
+    let output = new_ucmd!()
+        .arg("-f")
+        .arg("--list-start")
+        .arg("^sleep 31.701$")
+        .succeeds()
+        .stdout_move_str();
+
+    let re = Regex::new(r"^(\d+) (\d{4}-\d\d-\d\dT\d\d:\d\d:\d\d[+-]\d\d:\d\d) sleep\n$").unwrap();
+    let captures = re.captures(&output).unwrap();
+    assert_eq!(captures[1], sleep.id().to_string());
+
+    // The start time is derived from the boot time, which is only precise to the second.
+    let start = chrono::DateTime::parse_from_rfc3339(&captures[2])
+        .unwrap()
+        .timestamp();
+    assert!((start - before).abs() <= 3, "{start} vs {before}");
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+fn test_list_start_conflicts() {
+    for arg in ["-c", "-l", "-a"] {
+        new_ucmd!()
+            .arg("--list-start")
+            .arg(arg)
+            .arg("sh")
+            .fails()
+            .code_is(1)
+            .stderr_contains("cannot be used with");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_list_user() {
+    let mut sleep = Command::new("sleep").arg("31.735").spawn().unwrap();
+    let user = uucore::entries::uid2usr(uucore::process::geteuid()).unwrap();
+
+    new_ucmd!()
+        .arg("-f")
+        .arg("--list-user")
+        .arg("^sleep 31.735$")
+        .succeeds()
+        .stdout_is(format!("{} {user} sleep\n", sleep.id()));
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_supgroup() {
+    let mut sleep = Command::new("sleep").arg("31.737").spawn().unwrap();
+    // `id -G` lists the effective group and the supplementary ones.
+    let output = Command::new("id").arg("-G").output().unwrap();
+    let groups: Vec<_> = String::from_utf8(output.stdout)
+        .unwrap()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    let status = std::fs::read_to_string(format!("/proc/{}/status", sleep.id())).unwrap();
+    let supplementary: Vec<_> = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Groups:"))
+        .unwrap()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    assert!(supplementary.iter().all(|gid| groups.contains(gid)));
+
+    let result = new_ucmd!()
+        .arg("-f")
+        .arg("--supgroup")
+        .arg(groups.join(","))
+        .arg("^sleep 31.737$")
+        .run();
+    if supplementary.is_empty() {
+        result.code_is(1).no_output();
+    } else {
+        result.success().stdout_is(format!("{}\n", sleep.id()));
+    }
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_name_with_newline_and_invalid_utf8() {
+    use std::process::Stdio;
+
+    // The shell renames itself, then waits on its stdin without forking.
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(r"printf 'x739\nnl\377' > /proc/$$/comm; read x739")
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let comm = format!("/proc/{}/comm", child.id());
+    while !std::fs::read(&comm).unwrap().starts_with(b"x739") {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    // Only children of this test, in case an earlier run left one behind.
+    let parent = std::process::id().to_string();
+    new_ucmd!()
+        .arg("-l")
+        .arg("-P")
+        .arg(&parent)
+        .arg(r"^x739\\nnl")
+        .succeeds()
+        .stdout_is(format!("{} x739\\nnl\u{fffd}\n", child.id()));
+    new_ucmd!()
+        .arg("-f")
+        .arg("-P")
+        .arg(&parent)
+        .arg("read x739$")
+        .succeeds()
+        .stdout_is(format!("{}\n", child.id()));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_list_user_conflicts() {
+    for arg in ["-c", "-l", "-a", "--list-start"] {
+        new_ucmd!()
+            .arg("--list-user")
+            .arg(arg)
+            .arg("sh")
+            .fails()
+            .code_is(1)
+            .stderr_contains("cannot be used with");
+    }
+}
+
+// Matching against the fixture tree in tests/fixtures/pgrep/proc, independent of the
+// processes running on the host.
+mod proc_root {
+    use crate::common::util::TestScenario;
+
+    #[test]
+    fn test_pattern() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("fix")
+            .succeeds()
+            .stdout_is("100\n200\n300\n400\n1234\n");
+    }
+
+    #[test]
+    fn test_env() {
+        new_ucmd!()
+            .env("PROCPS_PROC", "proc")
+            .arg("-x")
+            .arg("fixworker")
+            .succeeds()
+            .stdout_is("300\n");
+    }
+
+    #[test]
+    fn test_full() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-f")
+            .arg("fix.conf")
+            .succeeds()
+            .stdout_is("200\n");
+    }
+
+    #[test]
+    fn test_full_exact_trailing_space() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-fx")
+            .arg("fixworker: idle   ")
+            .succeeds()
+            .stdout_is("300\n");
+
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-fx")
+            .arg("fixworker: idle")
+            .fails()
+            .code_is(1);
+    }
+
+    #[test]
+    fn test_cgroup() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--cgroup=/system.slice/fixdaemon.service,/init.scope")
+            .succeeds()
+            .stdout_is("100\n200\n300\n400\n");
+
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--cgroup=/user.slice/user-1000.slice/session-1.scope")
+            .succeeds()
+            .stdout_is("1234\n");
+    }
+
+    #[test]
+    fn test_cgroup_unreadable() {
+        let scene = TestScenario::new(util_name!());
+        scene.fixtures.remove("proc/200/cgroup");
+
+        scene
+            .ucmd()
+            .arg("--proc-root=proc")
+            .arg("--cgroup=/system.slice/fixdaemon.service")
+            .succeeds()
+            .stdout_is("300\n400\n")
+            .no_stderr();
+
+        // Not even with --inverse, as there is nothing to match against.
+        scene
+            .ucmd()
+            .arg("--proc-root=proc")
+            .arg("--cgroup=/system.slice/fixdaemon.service")
+            .arg("--inverse")
+            .arg("--debug")
+            .succeeds()
+            .stdout_is("100\n1234\n")
+            .stderr_is("pgrep: 1 processes skipped due to read errors\n");
+    }
+
+    #[test]
+    fn test_debug_near_misses() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--debug")
+            .arg("-r")
+            .arg("S")
+            .arg("fix")
+            .succeeds()
+            .stdout_is("100\n200\n")
+            .stderr_contains("pid 300 (fixworker) not selected: state not in -r/--runstates\n")
+            .stderr_contains("pid 400 (fixzombie) not selected: state not in -r/--runstates\n");
+
+        // 200 is the only one with the pattern in its command line.
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--debug")
+            .arg("fix.conf")
+            .fails()
+            .code_is(1)
+            .stderr_is(
+                "pgrep: pid 200 (fixdaemon) not selected: \
+                 pattern only found in the command line; try -f\n\
+                 pgrep: 0 processes skipped due to read errors\n",
+            );
+    }
+
+    #[test]
+    fn test_runstates() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--runstates=Z")
+            .succeeds()
+            .stdout_is("400\n");
+    }
+
+    #[test]
+    fn test_parent_inverse() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--parent=200")
+            .succeeds()
+            .stdout_is("300\n400\n1234\n");
+
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--parent=200")
+            .arg("--inverse")
+            .succeeds()
+            .stdout_is("100\n200\n");
+    }
+
+    #[test]
+    fn test_list_full() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-a")
+            .arg("fix")
+            .succeeds()
+            .stdout_is(
+                "100 /sbin/fixinit\n\
+                 200 /usr/sbin/fixdaemon --config /etc/fix.conf\n\
+                 300 fixworker: idle   \n\
+                 400 [fixzombie]\n\
+                 1234 fix daemon\n",
+            );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_list_start() {
+        new_ucmd!()
+            .env("TZ", "UTC")
+            .arg("--proc-root=proc")
+            .arg("--list-start")
+            .arg("-d,")
+            .arg("fixworker|fixinit")
+            .succeeds()
+            .stdout_is(
+                "100 2023-11-14T22:13:31+00:00 fixinit,300 2023-11-14T22:13:33+00:00 fixworker\n",
+            );
+    }
+
+    #[test]
+    fn test_list_user() {
+        // 400 runs with the effective uid 0 of its real uid 1000.
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--list-user")
+            .arg("-d,")
+            .arg("fixinit|fixzombie")
+            .succeeds()
+            .stdout_is("100 root fixinit,400 root fixzombie\n");
+    }
+
+    #[test]
+    fn test_terminal() {
+        // Only 1234 has a controlling terminal, pts/0.
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-t")
+            .arg("pts/0,tty1")
+            .succeeds()
+            .stdout_is("1234\n");
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-t")
+            .arg("?")
+            .arg("fix")
+            .succeeds()
+            .stdout_is("100\n200\n300\n400\n");
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-t")
+            .arg("pts/1")
+            .fails()
+            .code_is(1);
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        // Like procps: 0 matched, 1 no match, 2 syntax error, 3 fatal error.
+        let cases: [(&[&str], i32); 7] = [
+            (&["fixworker"], 0),
+            (&["nomatch"], 1),
+            // Can never match the 15 character process name, but isn't an error either.
+            (&["fixworker-with-a-long-name"], 1),
+            (&["fix("], 2),
+            (&[], 2),
+            (&["--proc-root=nonexistent", "fix"], 3),
+            (&["--patterns-from=nonexistent"], 3),
+        ];
+
+        for (args, code) in cases {
+            new_ucmd!()
+                .arg("--proc-root=proc")
+                .args(args)
+                .run()
+                .code_is(code);
+        }
+    }
+
+    #[test]
+    fn test_unreadable_proc_root() {
+        new_ucmd!()
+            .arg("--proc-root=nonexistent")
+            .arg("fix")
+            .fails()
+            .code_is(3)
+            .stderr_is("pgrep: nonexistent is not mounted or not accessible\n");
+    }
+
+    #[test]
+    fn test_unmounted_proc_root() {
+        let ts = TestScenario::new(util_name!());
+        ts.fixtures.mkdir("empty");
+
+        // Not a single process, as with an empty mount point.
+        for args in [&["fix"][..], &["-c", "fix"], &["-n"]] {
+            ts.ucmd()
+                .arg("--proc-root=empty")
+                .args(args)
+                .fails()
+                .code_is(3)
+                .stdout_is("")
+                .stderr_is("pgrep: empty is not mounted or not accessible\n");
+        }
+
+        ts.ucmd()
+            .arg("--proc-root=proc/stat")
+            .arg("fix")
+            .fails()
+            .code_is(3)
+            .stderr_is("pgrep: proc/stat is not mounted or not accessible\n");
+
+        // The populated fixture tree works as before.
+        ts.ucmd()
+            .arg("--proc-root=proc")
+            .arg("-c")
+            .arg("fix")
+            .succeeds()
+            .stdout_is("5\n");
+    }
+
+    #[test]
+    fn test_clustered_short_options() {
+        let cases: [(&[&str], &[&str], &str); 7] = [
+            (&["-fx"], &["-f", "-x"], "fixworker: idle   "),
+            (&["-fl"], &["-f", "-l"], "fix"),
+            (&["-la"], &["-l", "-a"], "fix"),
+            (&["-ic"], &["-i", "-c"], "FIX"),
+            (&["-vx"], &["-v", "-x"], "fixinit"),
+            (&["-nf"], &["-n", "-f"], "fix"),
+            (&["-fd,"], &["-f", "-d", ","], "fix"),
+        ];
+
+        for (clustered, spaced, pattern) in cases {
+            let expected = new_ucmd!()
+                .arg("--proc-root=proc")
+                .args(spaced)
+                .arg(pattern)
+                .succeeds()
+                .stdout_move_str();
+
+            new_ucmd!()
+                .arg("--proc-root=proc")
+                .args(clustered)
+                .arg(pattern)
+                .succeeds()
+                .stdout_is(expected);
+        }
+
+        // Repeating a flag, clustered or not, is the same as giving it once.
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .args(&["-ff", "-f", "fix.conf"])
+            .succeeds()
+            .stdout_is("200\n");
+    }
+
+    #[test]
+    fn test_end_of_options() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .args(&["-f", "--", "--config"])
+            .succeeds()
+            .stdout_is("200\n");
+
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .args(&["-fl", "--", "-config"])
+            .succeeds()
+            .stdout_is("200 fixdaemon\n");
+    }
+
+    #[test]
+    fn test_ignore_case_classes() {
+        // Lowercasing the pattern would have turned `\D` into `\d`.
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .args(&["-i", r"^FIX\D+$"])
+            .succeeds()
+            .stdout_is("100\n200\n300\n400\n1234\n");
+
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .args(&["-ix", "FIXW[A-Z]+"])
+            .succeeds()
+            .stdout_is("300\n");
+
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .args(&["-if", "/ETC/FIX.CONF"])
+            .succeeds()
+            .stdout_is("200\n");
+    }
+
+    #[test]
+    fn test_ids() {
+        // 300 runs as user 1000 in group 100, 400 is setuid root started by user 1000.
+        let cases = [
+            ("-u0", "100\n200\n400\n1234\n"),
+            ("-U0", "100\n200\n1234\n"),
+            ("-U1000", "300\n400\n"),
+            ("-u1000,100", "300\n"),
+            ("-G100", "300\n"),
+            // 300 is also in groups 27 and 1000, but 100 is only its real group.
+            ("--supgroup=27", "300\n"),
+            ("--supgroup=5,1000", "300\n"),
+        ];
+
+        for (id, expected) in cases {
+            new_ucmd!()
+                .arg("--proc-root=proc")
+                .arg(id)
+                .succeeds()
+                .stdout_is(expected);
+        }
+    }
+
+    #[test]
+    fn test_supgroup_not_real_group() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("--supgroup=100")
+            .fails()
+            .code_is(1)
+            .no_output();
+    }
+
+    #[test]
+    fn test_no_match() {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("sh")
+            .fails()
+            .code_is(1)
+            .no_output();
+    }
+}
@@ -81,3 +81,59 @@ fn test_separator() {
             .stdout_matches(re);
     }
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_match_executable() {
+    use std::process::Command;
+
+    let ts = TestScenario::new(util_name!());
+    let sleep_path = std::fs::canonicalize("/bin/sleep").unwrap();
+    let copy = ts.fixtures.plus("pidof-sleep-729");
+    std::fs::copy(&sleep_path, &copy).unwrap();
+
+    let mut sleep = Command::new(&copy).arg("2.0729").spawn().unwrap();
+    let pid = sleep.id().to_string();
+
+    // By the path of the copy and by its name, which is nowhere in PATH.
+    for program in [copy.to_str().unwrap(), "pidof-sleep-729"] {
+        ts.ucmd()
+            .arg(program)
+            .succeeds()
+            .stdout_is(format!("{pid}\n"));
+    }
+
+    // The original is a different executable, even though the copy is the same program.
+    let result = ts.ucmd().arg(sleep_path.to_str().unwrap()).run();
+    assert!(!result.stdout_str().split_whitespace().any(|it| it == pid));
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_match_symlinked_applet() {
+    use std::process::Command;
+
+    // Like a busybox applet: a link of another name to a multicall binary.
+    let ts = TestScenario::new(util_name!());
+    let multicall = ts.fixtures.plus("pidof-multicall-729");
+    let applet = ts.fixtures.plus("pidof-applet-729");
+    std::fs::copy(std::fs::canonicalize("/bin/sleep").unwrap(), &multicall).unwrap();
+    std::os::unix::fs::symlink(&multicall, &applet).unwrap();
+
+    let mut other = Command::new(&multicall).arg("2.0729").spawn().unwrap();
+    let mut sleep = Command::new(&applet).arg("2.0729").spawn().unwrap();
+
+    // Only the process started as the applet, not everything running the multicall binary.
+    ts.ucmd()
+        .arg(applet.to_str().unwrap())
+        .succeeds()
+        .stdout_is(format!("{}\n", sleep.id()));
+
+    for child in [&mut other, &mut sleep] {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
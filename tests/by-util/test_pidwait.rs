@@ -44,3 +44,158 @@ fn test_too_many_patterns() {
         .no_stdout()
         .stderr_contains("only one pattern can be provided");
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_interrupted() {
+    use std::process::Command;
+    use std::time::Duration;
+
+    let mut sleep = Command::new("sleep").arg("10").spawn().unwrap();
+
+    let mut child = new_ucmd!().arg("-x").arg("sleep").run_no_wait();
+    std::thread::sleep(Duration::from_millis(300));
+    unsafe { libc::kill(child.id() as i32, libc::SIGINT) };
+
+    child
+        .make_assertion_with_delay(300)
+        .is_not_alive()
+        .with_all_output()
+        .code_is(130)
+        .stderr_contains(format!("(pid {}) still running", sleep.id()));
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_echo_exit_order() {
+    use std::process::Command;
+    use std::time::Duration;
+
+    // The shell reaps the sleeps, otherwise they would linger as zombies of the test.
+    // The longer sleep is started first, so termination order differs from pid order.
+    let ts = TestScenario::new(util_name!());
+    let mut sh = Command::new("sh")
+        .current_dir(&ts.fixtures.subdir)
+        .arg("-c")
+        .arg("sleep 0.6707 & echo $! > long.pid; sleep 0.2707 & echo $! > short.pid; wait")
+        .spawn()
+        .unwrap();
+    let pid = |name| loop {
+        let content = std::fs::read_to_string(ts.fixtures.plus(name)).unwrap_or_default();
+        match content.trim().parse::<u32>() {
+            Ok(pid) => break pid,
+            Err(_) => std::thread::sleep(Duration::from_millis(10)),
+        }
+    };
+    let (long, short) = (pid("long.pid"), pid("short.pid"));
+
+    ts.ucmd()
+        .arg("-e")
+        .arg("-f")
+        .arg("^sleep 0.[26]707$")
+        .succeeds()
+        .stdout_contains(format!(
+            "sleep (pid {short}) exited\nsleep (pid {long}) exited\n"
+        ));
+
+    sh.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_older() {
+    use std::process::Command;
+    use std::time::Duration;
+
+    // Just started, so not older than a day no matter how long the system is up.
+    let mut sleep = Command::new("sleep").arg("0.8745").spawn().unwrap();
+    new_ucmd!()
+        .args(&["-c", "-f", "^sleep 0.8745$", "-O", "86400"])
+        .fails()
+        .code_is(1)
+        .stdout_is("0\n");
+    sleep.wait().unwrap();
+
+    // The shell reaps the sleep, which would otherwise linger as a zombie of the test.
+    let ts = TestScenario::new(util_name!());
+    let mut sh = Command::new("sh")
+        .current_dir(&ts.fixtures.subdir)
+        .arg("-c")
+        .arg("sleep 0.4745 & echo $! > sleep.pid; wait")
+        .spawn()
+        .unwrap();
+    while !std::fs::read_to_string(ts.fixtures.plus("sleep.pid"))
+        .unwrap_or_default()
+        .ends_with('\n')
+    {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    ts.ucmd()
+        .args(&["-c", "-f", "^sleep 0.4745$", "-O", "0"])
+        .succeeds()
+        .stdout_is("1\n");
+    sh.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_runstates() {
+    use std::process::Command;
+    use std::time::Duration;
+
+    // Sleeping, so neither running nor a zombie.
+    let mut sleep = Command::new("sleep").arg("0.8745").spawn().unwrap();
+    new_ucmd!()
+        .args(&["-c", "-f", "^sleep 0.8745$", "-r", "RZ"])
+        .fails()
+        .code_is(1)
+        .stdout_is("0\n");
+    sleep.wait().unwrap();
+
+    // The shell reaps the sleep, which would otherwise linger as a zombie of the test.
+    let ts = TestScenario::new(util_name!());
+    let mut sh = Command::new("sh")
+        .current_dir(&ts.fixtures.subdir)
+        .arg("-c")
+        .arg("sleep 0.4745 & echo $! > sleep.pid; wait")
+        .spawn()
+        .unwrap();
+    while !std::fs::read_to_string(ts.fixtures.plus("sleep.pid"))
+        .unwrap_or_default()
+        .ends_with('\n')
+    {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    ts.ucmd()
+        .args(&["-c", "-f", "^sleep 0.4745$", "-r", "S"])
+        .succeeds()
+        .stdout_is("1\n");
+    sh.wait().unwrap();
+}
+
+#[test]
+fn test_clustered_short_options() {
+    for args in [
+        &["-f", "-c", "NO_MATCH_719"][..],
+        &["-fc", "NO_MATCH_719"],
+        &["-cff", "NO_MATCH_719"],
+        &["-fc", "--", "-NO_MATCH_719"],
+    ] {
+        new_ucmd!().args(args).fails().code_is(1).stdout_is("0\n");
+    }
+}
+
+#[test]
+fn test_unreadable_proc_root() {
+    new_ucmd!()
+        .env("PROCPS_PROC", "nonexistent")
+        .arg("sleep")
+        .fails()
+        .code_is(3)
+        .stderr_is("pidwait: nonexistent is not mounted or not accessible\n");
+}
@@ -49,3 +49,402 @@ fn test_invalid_arg() {
 fn test_help() {
     new_ucmd!().arg("--help").succeeds();
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_skips_ancestors() {
+    // The test harness is our parent, match it by its full path.
+    let exe = std::env::current_exe().unwrap();
+    let parent = std::process::id().to_string();
+
+    new_ucmd!()
+        .arg("--dry-run")
+        .arg("-f")
+        .arg(exe.to_str().unwrap())
+        .run()
+        .stdout_does_not_contain(format!("(pid {parent})"));
+
+    new_ucmd!()
+        .arg("--dry-run")
+        .arg("--force")
+        .arg("-f")
+        .arg(exe.to_str().unwrap())
+        .succeeds()
+        .stdout_contains(format!("(pid {parent})"));
+
+    new_ucmd!()
+        .arg("--dry-run")
+        .arg("--force")
+        .arg("--ignore-ancestors")
+        .arg("-f")
+        .arg(exe.to_str().unwrap())
+        .run()
+        .stdout_does_not_contain(format!("(pid {parent})"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_skips_init() {
+    // pid 1 is the only process whose parent is 0, apart from kernel threads.
+    new_ucmd!()
+        .arg("--dry-run")
+        .arg("--parent=0")
+        .run()
+        .stdout_does_not_contain("(pid 1)\n")
+        .stderr_contains("skipping pid 1, use --force to signal it");
+
+    new_ucmd!()
+        .arg("--dry-run")
+        .arg("--force")
+        .arg("--parent=0")
+        .succeeds()
+        .stdout_contains("(pid 1)\n")
+        .no_stderr();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_dry_run() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("2.0672").spawn().unwrap();
+
+    new_ucmd!()
+        .arg("--dry-run")
+        .arg("-f")
+        .arg("^sleep 2.0672$")
+        .succeeds()
+        .stdout_is(format!("sleep would be killed (pid {})\n", sleep.id()));
+
+    // Still running, `try_wait` would have returned the exit status otherwise.
+    assert!(sleep.try_wait().unwrap().is_none());
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_inverse_requires_narrowing() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("2.0750").spawn().unwrap();
+
+    for args in [&["-v", "-f", "^sleep 2.0750$"][..], &["-vf", "--", "-x"]] {
+        new_ucmd!()
+            .args(args)
+            .fails()
+            .code_is(2)
+            .no_stdout()
+            .stderr_contains("refusing to signal all processes not matching");
+    }
+    assert!(sleep.try_wait().unwrap().is_none());
+
+    // Everything of ours but the sleep would be signalled.
+    for narrowing in [&["-u", "self"][..], &["--force"]] {
+        new_ucmd!()
+            .args(&["--dry-run", "-v", "-f", "^sleep 2.0750$"])
+            .args(narrowing)
+            .succeeds()
+            .stdout_contains(" would be killed (pid ")
+            .stdout_does_not_contain(format!("(pid {})\n", sleep.id()));
+    }
+    assert!(sleep.try_wait().unwrap().is_none());
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_euid() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("2.07501").spawn().unwrap();
+    let expected = format!("sleep would be killed (pid {})\n", sleep.id());
+
+    new_ucmd!()
+        .args(&["--dry-run", "-u", "self", "-f", "^sleep 2.07501$"])
+        .succeeds()
+        .stdout_is(&expected);
+    new_ucmd!()
+        .args(&["--dry-run", "-u", "424242", "-f", "^sleep 2.07501$"])
+        .fails()
+        .code_is(1)
+        .no_stdout();
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_ignore_case() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("2.0727").spawn().unwrap();
+    let expected = format!("sleep would be killed (pid {})\n", sleep.id());
+
+    for args in [
+        &["-i", "-f", "^SLEEP 2.0727$"][..],
+        &["-i", "-f", r"^S\D+ 2.0727$"],
+        &["-ixf", "SLEEP 2.0727"],
+    ] {
+        new_ucmd!()
+            .arg("--dry-run")
+            .args(args)
+            .succeeds()
+            .stdout_is(&expected);
+    }
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_older() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("2.0745").spawn().unwrap();
+
+    // Just started, so not older than a day no matter how long the system is up.
+    new_ucmd!()
+        .args(&["--dry-run", "-O", "86400", "-f", "^sleep 2.0745$"])
+        .fails()
+        .code_is(1)
+        .no_stdout();
+    new_ucmd!()
+        .args(&["--dry-run", "-O", "0", "-f", "^sleep 2.0745$"])
+        .succeeds()
+        .stdout_is(format!("sleep would be killed (pid {})\n", sleep.id()));
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_signal_zero() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("2.0725").spawn().unwrap();
+
+    for signal in [&["--signal", "0"][..], &["-0"], &["--signal=EXIT"]] {
+        new_ucmd!()
+            .args(signal)
+            .arg("-e")
+            .arg("-f")
+            .arg("^sleep 2.0725$")
+            .succeeds()
+            .stdout_only(format!("sleep checked (pid {})\n", sleep.id()));
+    }
+
+    new_ucmd!()
+        .args(&["--signal", "0", "--dry-run", "-f", "^sleep 2.0725$"])
+        .succeeds()
+        .stdout_only(format!("sleep would be checked (pid {})\n", sleep.id()));
+
+    // Only probed, so still running.
+    assert!(sleep.try_wait().unwrap().is_none());
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+
+    new_ucmd!()
+        .args(&["--signal", "0", "-f", "^sleep 2.0725$"])
+        .fails()
+        .code_is(1)
+        .no_output();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_signal_zero_not_permitted() {
+    // Root may signal anything.
+    if uucore::process::geteuid() == 0 {
+        return;
+    }
+
+    // pid 1 and kthreadd, the only processes without a parent, belong to root.
+    new_ucmd!()
+        .args(&["--signal", "0", "--force", "-e", "--parent=0"])
+        .fails()
+        .code_is(1)
+        .no_stdout()
+        .stderr_contains("pkill: checking pid 1 failed: Permission denied");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_clustered_short_options() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("2.0719").spawn().unwrap();
+    let expected = format!("sleep would be killed (pid {})\n", sleep.id());
+
+    for args in [
+        &["-f", "-x", "sleep 2.0719"][..],
+        &["-fx", "sleep 2.0719"],
+        &["-xf", "sleep 2.0719"],
+        &["-ffn", "^sleep 2.0719$"],
+        &["-f", "--", "^sleep 2.0719$"],
+    ] {
+        new_ucmd!()
+            .arg("--dry-run")
+            .args(args)
+            .succeeds()
+            .stdout_is(&expected);
+    }
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_max_matches() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    let mut sleeps: Vec<_> = (0..3)
+        .map(|_| Command::new("sleep").arg("31.714").spawn().unwrap())
+        .collect();
+
+    for extra in [None, Some("--dry-run")] {
+        new_ucmd!()
+            .args(&["--max-matches", "2", "-f", "^sleep 31.714$"])
+            .args(&extra.into_iter().collect::<Vec<_>>())
+            .fails()
+            .code_is(4)
+            .no_stdout()
+            .stderr_is("pkill: pattern matched 3 processes, exceeding --max-matches\n");
+    }
+    for sleep in &mut sleeps {
+        assert!(sleep.try_wait().unwrap().is_none());
+    }
+
+    new_ucmd!()
+        .args(&["--max-matches", "5", "-f", "^sleep 31.714$"])
+        .succeeds();
+    for sleep in &mut sleeps {
+        assert_eq!(sleep.wait().unwrap().signal(), Some(libc::SIGTERM));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_full_exact() {
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("31.6").spawn().unwrap();
+
+    new_ucmd!()
+        .arg("--dry-run")
+        .arg("-fx")
+        .arg("sleep 31")
+        .fails()
+        .code_is(1);
+
+    new_ucmd!()
+        .arg("-fx")
+        .arg("sleep 31.6")
+        .succeeds()
+        .no_output();
+    sleep.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_pidfile() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+    use std::time::Duration;
+
+    let ts = TestScenario::new(util_name!());
+    let mut sleep = Command::new("sh")
+        .arg("-c")
+        .arg("echo $$ > sleep.pid; exec sleep 31.7")
+        .current_dir(&ts.fixtures.subdir)
+        .spawn()
+        .unwrap();
+
+    // Wait for the shell to have written its pidfile.
+    let pidfile = ts.fixtures.plus("sleep.pid");
+    while std::fs::read_to_string(&pidfile).unwrap_or_default().trim() != sleep.id().to_string() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    ts.ucmd().arg("-F").arg("sleep.pid").succeeds().no_output();
+
+    assert_eq!(sleep.wait().unwrap().signal(), Some(libc::SIGTERM));
+}
+
+#[test]
+fn test_pidfile_selects_nothing() {
+    let ts = TestScenario::new(util_name!());
+
+    for content in ["-5\n", "0\n"] {
+        ts.fixtures.write("pidfile", content);
+        ts.ucmd()
+            .arg("-F")
+            .arg("pidfile")
+            .fails()
+            .code_is(1)
+            .no_output();
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_signal_rtmin() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    let mut sleep = Command::new("sleep").arg("31.8").spawn().unwrap();
+
+    new_ucmd!()
+        .arg("--signal")
+        .arg("RTMIN+1")
+        .arg("-fx")
+        .arg("sleep 31.8")
+        .succeeds()
+        .no_output();
+
+    assert_eq!(sleep.wait().unwrap().signal(), Some(libc::SIGRTMIN() + 1));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_signal_rt_out_of_range() {
+    new_ucmd!()
+        .arg("--signal")
+        .arg("RTMIN+100")
+        .arg("THIS_PATTERN_DOES_NOT_MATCH")
+        .fails()
+        .code_is(2)
+        .stderr_contains("Unknown signal 'RTMIN+100'");
+}
+
+#[test]
+fn test_unreadable_proc_root() {
+    new_ucmd!()
+        .env("PROCPS_PROC", "nonexistent")
+        .arg("sleep")
+        .fails()
+        .code_is(3)
+        .stderr_is("pkill: nonexistent is not mounted or not accessible\n");
+}
+
+#[test]
+fn test_unmounted_proc_root() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.mkdir("empty");
+
+    ts.ucmd()
+        .env("PROCPS_PROC", "empty")
+        .arg("sleep")
+        .fails()
+        .code_is(3)
+        .stderr_is("pkill: empty is not mounted or not accessible\n");
+}
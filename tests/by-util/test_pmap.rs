@@ -89,6 +89,430 @@ fn test_device() {
     }
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_extended() {
+    let pid = process::id();
+
+    for arg in ["-x", "--extended"] {
+        let result = new_ucmd!()
+            .arg(arg)
+            .arg(pid.to_string())
+            .succeeds()
+            .stdout_move_str();
+
+        assert_extended_format(pid, &result);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_summary() {
+    let pid = process::id();
+    let anon_re = Regex::new(r"^anon total: (\d+)K$").unwrap();
+    let file_re = Regex::new(r"^file total: (\d+)K$").unwrap();
+    let total_re = Regex::new(r"^( total +|total kB +|mapped: )(\d+)").unwrap();
+
+    for args in [
+        vec!["-x", "--summary"],
+        vec!["-X", "--summary"],
+        vec!["--XX", "--summary"],
+    ] {
+        let result = new_ucmd!()
+            .args(&args)
+            .arg(pid.to_string())
+            .succeeds()
+            .stdout_move_str();
+
+        let lines: Vec<_> = result.lines().collect();
+        let line_count = lines.len();
+
+        let anon: u64 = anon_re.captures(lines[line_count - 2]).unwrap()[1]
+            .parse()
+            .unwrap();
+        let file: u64 = file_re.captures(lines[line_count - 1]).unwrap()[1]
+            .parse()
+            .unwrap();
+
+        let total: u64 = total_re.captures(lines[line_count - 3]).unwrap()[2]
+            .parse()
+            .unwrap();
+
+        assert!(anon + file <= total);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_summary_quiet() {
+    let pid = process::id();
+
+    new_ucmd!()
+        .arg("-x")
+        .arg("--summary")
+        .arg("--quiet")
+        .arg(pid.to_string())
+        .succeeds()
+        .stdout_does_not_contain("anon total:")
+        .stdout_does_not_contain("file total:");
+}
+
+#[test]
+fn test_summary_requires_extended_format() {
+    for args in [&["--summary"][..], &["-d", "--summary"]] {
+        new_ucmd!()
+            .args(args)
+            .arg("1")
+            .fails()
+            .code_is(1)
+            .stderr_contains("<--extended|-X|--XX>");
+    }
+}
+
+// The fixture tree in tests/fixtures/pmap/proc makes the output deterministic.
+const FIXTURE_PID: &str = "4242";
+
+#[test]
+fn test_proc_root_default_format() {
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg(FIXTURE_PID)
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+00005579a7a4b000      8K r---- fixture
+00005579a7a4d000     20K r-x-- fixture
+00005579a8a60000    132K rw---   [ anon ]
+00007f3c2a000000    132K rw-s- SYSV00000000 (deleted)
+00007f3c2a200000    160K r---- libc.so.6
+00007f3c2a228000     36K rw---   [ anon ]
+00007ffd0e8e1000    132K rw---   [ stack ]
+ total              620K
+",
+        );
+}
+
+#[test]
+fn test_proc_root_large_mapping() {
+    // A 2 TiB device mapping widens the size column, the total stays aligned with it.
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg("4343")
+        .succeeds()
+        .stdout_is(
+            "4343:   /usr/bin/gpu
+0000555555554000          8K r---- gpu
+00007d0000000000 2147483648K rw-s- card0
+00007ffd0e8e1000        132K rw---   [ stack ]
+ total           2147483788K
+",
+        );
+}
+
+#[test]
+fn test_proc_root_show_path() {
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg("-p")
+        .arg(FIXTURE_PID)
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+00005579a7a4b000      8K r---- /usr/bin/fixture
+00005579a7a4d000     20K r-x-- /usr/bin/fixture
+00005579a8a60000    132K rw---   [ anon ]
+00007f3c2a000000    132K rw-s- /SYSV00000000 (deleted)
+00007f3c2a200000    160K r---- /usr/lib/libc.so.6
+00007f3c2a228000     36K rw---   [ anon ]
+00007ffd0e8e1000    132K rw---   [ stack ]
+ total              620K
+",
+        );
+
+    // Anonymous regions are named the same in every format.
+    for format in ["-x", "-d"] {
+        new_ucmd!()
+            .arg("--proc-root=proc")
+            .arg("-p")
+            .arg(format)
+            .arg(FIXTURE_PID)
+            .succeeds()
+            .stdout_contains(" /usr/lib/libc.so.6\n")
+            .stdout_contains("   [ anon ]\n")
+            .stdout_contains("   [ stack ]\n");
+    }
+}
+
+#[test]
+fn test_proc_root_device_format() {
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg("-d")
+        .arg(FIXTURE_PID)
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+Address           Kbytes Mode  Offset           Device    Mapping
+00005579a7a4b000       8 r---- 0000000000000000 008:00001 fixture
+00005579a7a4d000      20 r-x-- 0000000000002000 008:00001 fixture
+00005579a8a60000     132 rw--- 0000000000000000 000:00000   [ anon ]
+00007f3c2a000000     132 rw-s- 0000000000000000 000:00005 SYSV00000000 (deleted)
+00007f3c2a200000     160 r---- 0000000000000000 008:00001 libc.so.6
+00007f3c2a228000      36 rw--- 0000000000000000 000:00000   [ anon ]
+00007ffd0e8e1000     132 rw--- 0000000000000000 000:00000   [ stack ]
+mapped: 620K    writeable/private: 300K    shared: 132K
+",
+        );
+}
+
+#[test]
+fn test_proc_root_summary() {
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg("-x")
+        .arg("--summary")
+        .arg(FIXTURE_PID)
+        .succeeds()
+        .stdout_contains(
+            "total kB             620     388     200\nanon total: 68K\nfile total: 552K\n",
+        );
+}
+
+#[test]
+fn test_proc_root_extended_format() {
+    // Note the trailing space after the separator line, like procps.
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg("-x")
+        .arg(FIXTURE_PID)
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+Address           Kbytes     RSS   Dirty Mode  Mapping
+00005579a7a4b000       8       8       0 r---- fixture
+00005579a7a4d000      20      20       0 r-x-- fixture
+00005579a8a60000     132      48      40 rw---   [ anon ]
+00007f3c2a000000     132     132     132 rw-s- SYSV00000000 (deleted)
+00007f3c2a200000     160     152       0 r---- libc.so.6
+00007f3c2a228000      36      12      12 rw---   [ anon ]
+00007ffd0e8e1000     132      16      16 rw---   [ stack ]
+---------------- ------- ------- ------- 
+total kB             620     388     200
+",
+        );
+}
+
+#[test]
+fn test_proc_root_extended_format_quiet() {
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg("-x")
+        .arg("-q")
+        .arg(FIXTURE_PID)
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+00005579a7a4b000       8       8       0 r---- fixture
+00005579a7a4d000      20      20       0 r-x-- fixture
+00005579a8a60000     132      48      40 rw---   [ anon ]
+00007f3c2a000000     132     132     132 rw-s- SYSV00000000 (deleted)
+00007f3c2a200000     160     152       0 r---- libc.so.6
+00007f3c2a228000      36      12      12 rw---   [ anon ]
+00007ffd0e8e1000     132      16      16 rw---   [ stack ]
+",
+        );
+}
+
+#[test]
+fn test_proc_root_non_existing_pid() {
+    new_ucmd!()
+        .arg("--proc-root=proc")
+        .arg(NON_EXISTING_PID)
+        .fails()
+        .code_is(42)
+        .no_output();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_pidfile() {
+    let pid = process::id();
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write("daemon.pid", &format!("{pid}\n"));
+
+    for arg in ["-F", "--pidfile"] {
+        let result = ts
+            .ucmd()
+            .arg("-x")
+            .arg(arg)
+            .arg("daemon.pid")
+            .succeeds()
+            .stdout_move_str();
+
+        assert_extended_format(pid, &result);
+    }
+}
+
+#[test]
+fn test_pidfile_errors() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write("garbage.pid", "daemon\n");
+    ts.fixtures.write("zero.pid", "0\n");
+    ts.fixtures
+        .write("gone.pid", &format!("{NON_EXISTING_PID}\n"));
+
+    ts.ucmd()
+        .args(&["-F", "garbage.pid"])
+        .fails()
+        .code_is(1)
+        .stderr_only("pmap: Pidfile garbage.pid not valid\n");
+
+    ts.ucmd()
+        .args(&["-F", "zero.pid"])
+        .fails()
+        .code_is(1)
+        .stderr_only("pmap: Pidfile zero.pid not valid\n");
+
+    ts.ucmd()
+        .args(&["--proc-root=proc", "-F", "gone.pid"])
+        .fails()
+        .code_is(1)
+        .stderr_only(format!(
+            "pmap: Pidfile gone.pid refers to process {NON_EXISTING_PID}, which does not exist\n"
+        ));
+
+    ts.ucmd()
+        .args(&["-F", "missing.pid"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("pmap: Failed to open pidfile 'missing.pid'");
+
+    // Either a pidfile or pids.
+    ts.ucmd()
+        .args(&["-F", "zero.pid", FIXTURE_PID])
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot be used with");
+}
+
+#[test]
+fn test_proc_root_pidfile() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures
+        .write("fixture.pid", &format!("{FIXTURE_PID}\n"));
+
+    let expected = ts
+        .ucmd()
+        .args(&["--proc-root=proc", "-d", FIXTURE_PID])
+        .succeeds()
+        .stdout_move_str();
+    ts.ucmd()
+        .args(&["--proc-root=proc", "-d", "-F", "fixture.pid"])
+        .succeeds()
+        .stdout_is(expected);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_perm() {
+    let pid = process::id();
+    let row = Regex::new(r"^[0-9a-f]{16} +(\d+)K [r-]wx[s-]- ").unwrap();
+
+    let result = new_ucmd!()
+        .arg("--perm")
+        .arg("wx")
+        .arg(pid.to_string())
+        .succeeds()
+        .stdout_move_str();
+
+    let mut lines: Vec<_> = result.lines().skip(1).collect();
+    let total = lines.pop().unwrap();
+    let mut sum = 0;
+    for line in lines {
+        let captures = row.captures(line).unwrap_or_else(|| panic!("{line}"));
+        sum += captures[1].parse::<u64>().unwrap();
+    }
+    assert_eq!(total, format!(" total {:>16}K", sum));
+}
+
+#[test]
+fn test_proc_root_perm() {
+    new_ucmd!()
+        .args(&["--proc-root=proc", "--perm", "wr", FIXTURE_PID])
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+00005579a8a60000    132K rw---   [ anon ]
+00007f3c2a000000    132K rw-s- SYSV00000000 (deleted)
+00007f3c2a228000     36K rw---   [ anon ]
+00007ffd0e8e1000    132K rw---   [ stack ]
+ total              432K
+",
+        );
+
+    new_ucmd!()
+        .args(&[
+            "--proc-root=proc",
+            "-x",
+            "--perm",
+            "w",
+            "--summary",
+            FIXTURE_PID,
+        ])
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+Address           Kbytes     RSS   Dirty Mode  Mapping
+00005579a8a60000     132      48      40 rw---   [ anon ]
+00007f3c2a000000     132     132     132 rw-s- SYSV00000000 (deleted)
+00007f3c2a228000      36      12      12 rw---   [ anon ]
+00007ffd0e8e1000     132      16      16 rw---   [ stack ]
+---------------- ------- ------- ------- 
+total kB             432     208     200
+anon total: 68K
+file total: 364K
+",
+        );
+
+    new_ucmd!()
+        .args(&["--proc-root=proc", "-d", "--perm", "rw", FIXTURE_PID])
+        .succeeds()
+        .stdout_contains("mapped: 432K    writeable/private: 300K    shared: 132K\n");
+
+    new_ucmd!()
+        .args(&["--proc-root=proc", "-x", "--perm", "s", FIXTURE_PID])
+        .succeeds()
+        .stdout_is(
+            "4242:   /usr/bin/fixture --flag
+Address           Kbytes     RSS   Dirty Mode  Mapping
+00007f3c2a000000     132     132     132 rw-s- SYSV00000000 (deleted)
+---------------- ------- ------- ------- 
+total kB             132     132     132
+",
+        );
+
+    // Nothing is executable and writable.
+    new_ucmd!()
+        .args(&["--proc-root=proc", "--perm", "wx", FIXTURE_PID])
+        .succeeds()
+        .stdout_is("4242:   /usr/bin/fixture --flag\n total                0K\n");
+}
+
+#[test]
+fn test_perm_invalid() {
+    new_ucmd!()
+        .args(&["--perm", "rq", FIXTURE_PID])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid permission flag 'q', expected any of rwxs");
+
+    new_ucmd!()
+        .args(&["--perm", "", FIXTURE_PID])
+        .fails()
+        .code_is(1)
+        .stderr_contains("no permission flags given");
+}
+
 #[test]
 fn test_invalid_arg() {
     new_ucmd!().arg("--definitely-invalid").fails().code_is(1);
@@ -153,3 +577,45 @@ fn assert_device_format(pid: u32, s: &str) {
         lines[line_count - 1]
     );
 }
+
+// Ensure `s` has the following extended format (--extended):
+//
+// 1234:   /some/path
+// Address           Kbytes     RSS   Dirty Mode  Mapping
+// 000055b09ef4e000     188     188       0 r---- bash
+// 00007ffd588fc000     132      12      12 rw---   [ stack ]
+// ...
+// ---------------- ------- ------- -------
+// total kB            4080    3040     420
+#[cfg(target_os = "linux")]
+fn assert_extended_format(pid: u32, s: &str) {
+    let lines: Vec<_> = s.lines().collect();
+    let line_count = lines.len();
+
+    let re = Regex::new(&format!("^{pid}:   .+[^ ]$")).unwrap();
+    assert!(re.is_match(lines[0]));
+
+    let expected_header = "Address           Kbytes     RSS   Dirty Mode  Mapping";
+    assert_eq!(expected_header, lines[1]);
+
+    let re = Regex::new(
+        r"^[0-9a-f]{16} +[1-9][0-9]* +[0-9]+ +[0-9]+ (-|r)(-|w)(-|x)(-|s)- (  \[ (anon|stack) \]|[a-zA-Z0-9._-]+)$",
+    )
+    .unwrap();
+
+    for line in lines.iter().take(line_count - 2).skip(2) {
+        assert!(re.is_match(line), "failing line: {line}");
+    }
+
+    assert_eq!(
+        lines[line_count - 2],
+        "---------------- ------- ------- ------- "
+    );
+
+    let re = Regex::new(r"^total kB +[1-9][0-9]* +[0-9]+ +[0-9]+$").unwrap();
+    assert!(
+        re.is_match(lines[line_count - 1]),
+        "failing line: {}",
+        lines[line_count - 1]
+    );
+}
@@ -55,3 +55,57 @@ fn test_code_mapping() {
         .stdout_contains("CMD1")
         .stdout_contains("CMD2");
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_namespace_columns() {
+    let own_ns = |kind: &str| {
+        let link = std::fs::read_link(format!("/proc/self/ns/{kind}")).unwrap();
+        let link = link.to_str().unwrap().to_owned();
+        link[link.find('[').unwrap() + 1..link.len() - 1].to_owned()
+    };
+
+    let result = new_ucmd!()
+        .args(&["-A", "-o", "pid,pidns,netns"])
+        .arg("--ns")
+        .arg(std::process::id().to_string())
+        .succeeds();
+
+    let mut lines = result.stdout_str().lines();
+    let header = lines.next().unwrap().split_whitespace().collect::<Vec<_>>();
+    assert_eq!(header, ["PID", "PIDNS", "NETNS"]);
+
+    // ps itself shares our namespaces, so there is at least one row.
+    let rows = lines.collect::<Vec<_>>();
+    assert!(!rows.is_empty());
+    for row in rows {
+        let row = row.split_whitespace().collect::<Vec<_>>();
+        assert_eq!(row[1..], [own_ns("pid"), own_ns("net")]);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_ns_pid_1() {
+    // Unprivileged users can't read the namespaces of init.
+    let result = new_ucmd!()
+        .args(&["-A", "--ns", "1", "--nslist", "pid,uts", "-o", "pid,utsns"])
+        .run();
+
+    if result.succeeded() {
+        result.stdout_contains("UTSNS");
+    } else {
+        result
+            .code_is(1)
+            .stderr_contains("reading namespaces of pid 1 failed");
+    }
+}
+
+#[test]
+fn test_nslist_invalid() {
+    new_ucmd!()
+        .args(&["--ns", "1", "--nslist", "pid,time"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid value 'time'");
+}
@@ -71,3 +71,22 @@ fn test_once_as_root() {
         }
     }
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_once_summary_lines() {
+    let ts = TestScenario::new(util_name!());
+
+    if let Ok(result) = run_ucmd_as_root(&ts, &["--once"]) {
+        let result = result.success();
+        let lines: Vec<_> = result.stdout_str().lines().take(5).collect();
+
+        assert!(lines[0].starts_with(" Active / Total Objects (% used)"));
+        assert!(lines[1].starts_with(" Active / Total Slabs (% used)"));
+        assert!(lines[2].starts_with(" Active / Total Caches (% used)"));
+        assert!(lines[3].starts_with(" Active / Total Size (% used)"));
+        assert!(lines[4].starts_with(" Minimum / Average / Maximum Object"));
+    } else {
+        print!("Test skipped; requires root user");
+    }
+}
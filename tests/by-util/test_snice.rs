@@ -14,3 +14,47 @@ fn test_no_args() {
 fn test_no_process_selected() {
     new_ucmd!().arg("-u=invalid_user").fails().code_is(1);
 }
+
+#[cfg(target_os = "linux")]
+fn nice_of(pid: u32) -> i32 {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap();
+    // The nice value is the 19th field, counting from the state after the command name.
+    let fields = stat[stat.rfind(')').unwrap() + 2..]
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    fields[16].parse().unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_priority_increase() {
+    let mut sleep = std::process::Command::new("sleep")
+        .arg("32.1")
+        .spawn()
+        .unwrap();
+    let pid = sleep.id();
+    let before = nice_of(pid);
+
+    new_ucmd!()
+        .arg("+5")
+        .arg("-p")
+        .arg(pid.to_string())
+        .succeeds()
+        .no_output();
+    assert_eq!(nice_of(pid), (before + 5).min(19));
+
+    // Without a priority the default is +4, a loose number is a pid.
+    new_ucmd!().arg(pid.to_string()).succeeds().no_output();
+    assert_eq!(nice_of(pid), (before + 9).min(19));
+
+    new_ucmd!()
+        .arg("+30")
+        .arg("-p")
+        .arg(pid.to_string())
+        .succeeds()
+        .no_output();
+    assert_eq!(nice_of(pid), 19);
+
+    sleep.kill().unwrap();
+    sleep.wait().unwrap();
+}
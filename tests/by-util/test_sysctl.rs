@@ -43,6 +43,43 @@ mod linux {
             .stdout_is("kernel.ostype\nfs.overflowuid\n");
     }
 
+    #[test]
+    fn test_get_tabbed_value() {
+        let value = std::fs::read_to_string("/proc/sys/kernel/printk").unwrap();
+        new_ucmd!()
+            .arg("kernel.printk")
+            .succeeds()
+            .stdout_is(format!("kernel.printk = {}\n", value.trim_end()));
+    }
+
+    #[test]
+    fn test_all_modes_line_count() {
+        let count = |arg: Option<&str>| {
+            let mut cmd = new_ucmd!();
+            cmd.arg("-a").arg("-e");
+            if let Some(arg) = arg {
+                cmd.arg(arg);
+            }
+            cmd.succeeds().stdout_str().lines().count()
+        };
+
+        let all = count(None);
+        assert!(all > 0);
+        assert_eq!(count(Some("-N")), all);
+        assert_eq!(count(Some("-n")), all);
+    }
+
+    #[test]
+    fn test_names_conflicts_with_values() {
+        new_ucmd!()
+            .arg("-N")
+            .arg("-n")
+            .arg("kernel.ostype")
+            .fails()
+            .code_is(1)
+            .stderr_contains("cannot be used with");
+    }
+
     #[test]
     fn test_continues_on_error() {
         new_ucmd!()
@@ -64,6 +101,30 @@ mod linux {
             .stdout_is("kernel.ostype = Linux\n")
             .stderr_is("");
     }
+
+    #[test]
+    fn test_dry_run() {
+        // kernel.ostype is read-only, the write being skipped is what keeps this from failing.
+        new_ucmd!()
+            .arg("--dry-run")
+            .arg("kernel.ostype=X")
+            .arg("kernel.ostype=Linux")
+            .succeeds()
+            .stdout_is("kernel.ostype = Linux -> X\nkernel.ostype unchanged\n")
+            .stderr_is("");
+    }
+
+    #[test]
+    fn test_dry_run_missing_key() {
+        new_ucmd!()
+            .arg("--dry-run")
+            .arg("nonexisting=1")
+            .arg("kernel.ostype")
+            .fails()
+            .code_is(1)
+            .stdout_is("kernel.ostype = Linux\n")
+            .stderr_is("sysctl: error reading key 'nonexisting': No such file or directory\n");
+    }
 }
 
 #[cfg(not(target_os = "linux"))]
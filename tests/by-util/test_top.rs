@@ -4,6 +4,7 @@
 // file that was distributed with this source code.
 
 use crate::common::util::TestScenario;
+use regex::Regex;
 
 #[test]
 fn test_invalid_arg() {
@@ -40,6 +41,26 @@ fn test_flag_user() {
     new_ucmd!().arg("-U=19999").succeeds().code_is(0);
 
     new_ucmd!().arg("-U=NOT_EXIST").fails().code_is(1);
+    new_ucmd!().arg("-U=!NOT_EXIST").fails().code_is(1);
+}
+
+#[test]
+fn test_flag_user_negated() {
+    let users = |arg: &str| -> Vec<String> {
+        new_ucmd!()
+            .arg(arg)
+            .succeeds()
+            .stdout_str()
+            .lines()
+            .map(|it| it.split_whitespace().collect::<Vec<_>>())
+            .filter(|it| it.len() >= 2 && it[0].parse::<u32>().is_ok())
+            .map(|it| it[1].to_string())
+            .collect()
+    };
+
+    // The USER column shows the effective user.
+    assert!(users("-u=!0").iter().all(|user| user != "root"));
+    assert!(users("-u=0").iter().all(|user| user == "root"));
 }
 
 #[test]
@@ -53,3 +74,349 @@ fn test_arg_p() {
         .succeeds()
         .code_is(0);
 }
+
+#[test]
+fn test_uptime_line() {
+    let re = Regex::new(
+        r"^top - \d{2}:\d{2}:\d{2} up .+, +\d+ users?,  load average: \d+\.\d{2}, \d+\.\d{2}, \d+\.\d{2}$",
+    )
+    .unwrap();
+
+    let result = new_ucmd!().succeeds();
+    let first_line = result.stdout_str().lines().next().unwrap();
+
+    // Output is not a terminal, so no colors are emitted.
+    assert!(re.is_match(first_line), "failing line: {first_line}");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cpu_line() {
+    let re = Regex::new(
+        r"^%Cpu\(s\): +\d+\.\d us, +\d+\.\d sy, +\d+\.\d ni, +\d+\.\d id, +\d+\.\d wa, +\d+\.\d hi, +\d+\.\d si, +\d+\.\d st$",
+    )
+    .unwrap();
+
+    let result = new_ucmd!().arg("-b").succeeds();
+    let second_line = result.stdout_str().lines().nth(1).unwrap();
+
+    assert!(re.is_match(second_line), "failing line: {second_line}");
+}
+
+#[test]
+fn test_memory_lines() {
+    for (scale, label) in [(None, "MiB"), (Some("-E=g"), "GiB"), (Some("-Ek"), "KiB")] {
+        let re = Regex::new(&format!(
+            "(?m)^{label} Mem : +{n} total, +{n} free, +{n} used, +{n} buff/cache\n\
+             {label} Swap: +{n} total, +{n} free, +{n} used\\. +{n} avail Mem(, {n} si, {n} so)?$",
+            n = r"\d+(\.\d)?"
+        ))
+        .unwrap();
+
+        let result = new_ucmd!()
+            .args(&scale.into_iter().collect::<Vec<_>>())
+            .succeeds();
+        assert!(re.is_match(result.stdout_str()), "{}", result.stdout_str());
+    }
+
+    new_ucmd!().arg("-E=x").fails().code_is(1);
+}
+
+#[test]
+fn test_narrow_width() {
+    new_ucmd!()
+        .arg("-w=10")
+        .succeeds()
+        .stdout_is("terminal t\n");
+
+    let result = new_ucmd!().arg("-w=40").succeeds();
+    assert!(result
+        .stdout_str()
+        .lines()
+        .all(|it| it.chars().count() == 40));
+}
+
+#[test]
+fn test_iterations() {
+    let result = new_ucmd!().arg("-b").arg("-n=2").arg("-d=0.1").succeeds();
+    assert_eq!(result.stdout_str().matches("top - ").count(), 2);
+
+    new_ucmd!().arg("-n=0").fails().code_is(1);
+    new_ucmd!().arg("-d=-1").fails().code_is(1);
+}
+
+#[test]
+fn test_delay_out_of_range() {
+    new_ucmd!()
+        .args(&["-b", "-d", "1e20"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid delay time '1e20'");
+
+    let result = new_ucmd!().env("TOP_DELAY", "1e25").arg("-b").succeeds();
+    result.stderr_contains("ignoring TOP_DELAY: invalid delay time '1e25'");
+    assert_eq!(result.stdout_str().matches("top - ").count(), 1);
+}
+
+#[test]
+fn test_env_iterations_and_delay() {
+    let result = new_ucmd!()
+        .env("TOP_ITERATIONS", "2")
+        .env("TOP_DELAY", "0.1")
+        .arg("-b")
+        .succeeds();
+    assert_eq!(result.stdout_str().matches("top - ").count(), 2);
+
+    // The command line wins over the environment.
+    let result = new_ucmd!()
+        .env("TOP_ITERATIONS", "2")
+        .arg("-b")
+        .arg("-n=1")
+        .succeeds();
+    assert_eq!(result.stdout_str().matches("top - ").count(), 1);
+}
+
+#[test]
+fn test_env_invalid() {
+    let result = new_ucmd!()
+        .env("TOP_ITERATIONS", "many")
+        .arg("-b")
+        .succeeds();
+    result.stderr_contains("ignoring TOP_ITERATIONS: invalid iterations 'many'");
+    assert_eq!(result.stdout_str().matches("top - ").count(), 1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cgroup_column() {
+    let result = new_ucmd!().arg("-b").arg("--cgroup").succeeds();
+    let header = result
+        .stdout_str()
+        .lines()
+        .find(|line| line.contains("PID"))
+        .unwrap();
+    assert!(header.contains("CGROUP"));
+
+    let result = new_ucmd!().arg("-b").succeeds();
+    assert!(!result.stdout_str().contains("CGROUP"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cgroup_width() {
+    let result = new_ucmd!()
+        .args(&["-b", "--cgroup=4", "-o=-CGROUP"])
+        .succeeds();
+    let names: Vec<_> = result
+        .stdout_str()
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|row| row.len() >= 12 && row[0].parse::<u32>().is_ok())
+        .map(|row| row[11].to_string())
+        .collect();
+
+    assert!(!names.is_empty());
+    assert!(names.iter().all(|name| name.chars().count() <= 4));
+    assert!(names.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn test_sort_override_unknown_field() {
+    new_ucmd!()
+        .args(&["-b", "-o", "NOPE"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("unrecognized field name 'NOPE'");
+}
+
+// Return the cells of the named column from the first task table in batch output.
+fn column(output: &str, name: &str) -> Vec<String> {
+    let mut lines = output.lines().skip_while(|line| !line.contains("PID"));
+    let header = lines.next().unwrap();
+    let index = header.split_whitespace().position(|h| h == name).unwrap();
+    lines
+        .take_while(|line| !line.trim().is_empty() && !line.starts_with("top - "))
+        .map(|line| line.split_whitespace().nth(index).unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_sort_override_scaled_memory() {
+    let result = new_ucmd!().args(&["-b", "-e", "m", "-o", "RES"]).succeeds();
+    let kib: Vec<f64> = column(result.stdout_str(), "RES")
+        .iter()
+        .map(|cell| {
+            let (number, scale) = match cell.find(|c: char| c.is_ascii_alphabetic()) {
+                Some(i) => (&cell[..i], "kmgtpe".find(&cell[i..]).unwrap() as i32),
+                None => (cell.as_str(), 0),
+            };
+            number.parse::<f64>().unwrap() * 1024f64.powi(scale)
+        })
+        .collect();
+    // Cells are rounded to the unit they are shown in, so allow a little slack.
+    assert!(kib.windows(2).all(|w| w[0] * 1.1 >= w[1]), "{kib:?}");
+}
+
+#[test]
+fn test_sort_override_time() {
+    let result = new_ucmd!().args(&["-b", "-o", "TIME+"]).succeeds();
+    let hundredths: Vec<u64> = column(result.stdout_str(), "TIME+")
+        .iter()
+        .map(|cell| {
+            let (minutes, seconds) = cell.split_once(':').unwrap();
+            let (seconds, hundredths) = seconds.split_once('.').unwrap();
+            (minutes.parse::<u64>().unwrap() * 60 + seconds.parse::<u64>().unwrap()) * 100
+                + hundredths.parse::<u64>().unwrap()
+        })
+        .collect();
+    // Busy processes, top itself among them, keep running between being sorted and shown.
+    assert!(
+        hundredths.windows(2).all(|w| w[0] + 5 >= w[1]),
+        "{hundredths:?}"
+    );
+}
+
+// Split CSV content into records, honoring quoted cells which may contain ',', '"' and
+// newlines.
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => record.push(std::mem::take(&mut cell)),
+            ('\n', false) => {
+                record.push(std::mem::take(&mut cell));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => cell.push(c),
+        }
+    }
+
+    records
+}
+
+#[test]
+fn test_export() {
+    let scene = TestScenario::new(util_name!());
+
+    scene
+        .ucmd()
+        .args(&["-b", "-n=2", "-d=0.1", "--export", "snapshot.csv"])
+        .succeeds()
+        .stdout_contains("top - ");
+
+    let records = parse_csv(&scene.fixtures.read("snapshot.csv"));
+    let markers: Vec<_> = records
+        .iter()
+        .enumerate()
+        .filter(|(_, record)| record[0].starts_with("# iteration "))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(markers.len(), 2);
+    assert!(records[0][0].starts_with("# iteration 1 "));
+    assert!(records[markers[1]][0].starts_with("# iteration 2 "));
+
+    for marker in markers {
+        let header = &records[marker + 1];
+        assert_eq!(header[0], "PID");
+        for record in records[marker + 1..]
+            .iter()
+            .take_while(|it| !it[0].starts_with('#'))
+        {
+            assert_eq!(record.len(), header.len());
+        }
+    }
+}
+
+#[test]
+fn test_export_unwritable() {
+    new_ucmd!()
+        .args(&["-b", "--export", "missing/dir/snapshot.csv"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("failed to write 'missing/dir/snapshot.csv'");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_pressure_line() {
+    let re =
+        Regex::new(r"^Pressure: cpu \d+\.\d% \| mem \d+\.\d% \| io \d+\.\d% \(avg10\)$").unwrap();
+
+    let result = new_ucmd!().arg("-b").arg("--pressure").succeeds();
+    let second_line = result.stdout_str().lines().nth(1).unwrap();
+
+    // Not every kernel provides pressure stall information.
+    if std::path::Path::new("/proc/pressure/io").exists() {
+        assert!(re.is_match(second_line), "failing line: {second_line}");
+    } else {
+        assert!(second_line.starts_with("%Cpu(s):"));
+    }
+
+    let result = new_ucmd!().arg("-b").succeeds();
+    assert!(!result.stdout_str().contains("Pressure:"));
+}
+
+#[test]
+fn test_task_memory_scale() {
+    let result = new_ucmd!().args(&["-b", "-e", "g"]).succeeds();
+    let rows: Vec<Vec<_>> = result
+        .stdout_str()
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .filter(|row: &Vec<_>| row.len() >= 12 && row[0].parse::<u32>().is_ok())
+        .collect();
+    assert!(!rows.is_empty());
+
+    // Scaled values keep one decimal, larger ones would move to terabytes and beyond.
+    let re = Regex::new(r"^\d{1,4}\.\d[gtp]$").unwrap();
+    for row in rows {
+        assert!(re.is_match(row[5]), "failing RES: {}", row[5]);
+    }
+
+    new_ucmd!().args(&["-b", "-e", "x"]).fails().code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_hide_kernel_threads() {
+    let pids = |args: &[&str]| -> Vec<u32> {
+        new_ucmd!()
+            .args(args)
+            .succeeds()
+            .stdout_str()
+            .lines()
+            .filter_map(|line| line.split_whitespace().next()?.parse().ok())
+            .collect()
+    };
+    // kthreadd and its children, none of them has a command line.
+    let is_kernel_thread = |pid: &u32| {
+        let (Ok(stat), Ok(cmdline)) = (
+            std::fs::read_to_string(format!("/proc/{pid}/stat")),
+            std::fs::read(format!("/proc/{pid}/cmdline")),
+        ) else {
+            return false;
+        };
+        let ppid = stat.rsplit_once(") ").unwrap().1.split(' ').nth(1).unwrap();
+        cmdline.is_empty() && (*pid == 2 || ppid == "2")
+    };
+
+    let shown = pids(&["-b"]);
+    let hidden = pids(&["-b", "--hide-kernel-threads"]);
+    assert!(!hidden.is_empty());
+    assert!(!hidden.iter().any(is_kernel_thread));
+    // Containers usually don't see the kernel threads at all.
+    if is_kernel_thread(&2) {
+        assert!(shown.contains(&2));
+        assert!(!hidden.contains(&2));
+    }
+}
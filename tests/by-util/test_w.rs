@@ -91,3 +91,81 @@ fn test_output_format() {
         assert!(!line_vec[3].is_empty() && !line_vec[4].is_empty());
     }
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_env_userlen() {
+    new_ucmd!()
+        .env("PROCPS_USERLEN", "20")
+        .succeeds()
+        .stdout_contains(format!("{:<20} TTY", "USER"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_env_userlen_out_of_range() {
+    new_ucmd!()
+        .env("PROCPS_USERLEN", "100")
+        .succeeds()
+        .stdout_contains(format!("{:<32} TTY", "USER"))
+        .stderr_contains("PROCPS_USERLEN must be between 8 and 32");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_env_fromlen() {
+    new_ucmd!()
+        .arg("--from")
+        .env("PROCPS_FROMLEN", "30")
+        .succeeds()
+        .stdout_contains(format!("{:<30} LOGIN@", "FROM"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_pids() {
+    for args in [&["--pids"][..], &["-p", "--short"], &["-p", "--old-style"]] {
+        let cmd = new_ucmd!().args(args).succeeds();
+        let mut lines = cmd.stdout_str().lines();
+
+        assert!(lines.next().unwrap().contains("TTY      PID     "));
+        for line in lines {
+            let pid = line.split_whitespace().nth(2).unwrap();
+            assert!(pid == "?" || std::path::Path::new("/proc").join(pid).exists());
+        }
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cpu_columns() {
+    use regex::Regex;
+
+    // Sessions come from utmp, so there may be none to check.
+    let duration = r"(\d+\.\d{2}s|\d+:\d{2}m?|\d+days)";
+    let re = Regex::new(&format!(r" {duration} +{duration} ")).unwrap();
+
+    let cmd = new_ucmd!().arg("--no-header").succeeds();
+    for line in cmd.stdout_str().lines() {
+        assert!(re.is_match(line), "failing line: {line}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_what_column() {
+    let cmd = new_ucmd!().succeeds();
+    let mut lines = cmd
+        .stdout_str()
+        .lines()
+        .skip_while(|line| !line.starts_with("USER"));
+    let header = lines.next().unwrap();
+    assert!(header.ends_with("JCPU   PCPU  WHAT"), "{header}");
+
+    // Sessions come from utmp, so there may be none to check.
+    let what = header.find("WHAT").unwrap();
+    for line in lines {
+        assert!(line[..what].ends_with(' '), "failing line: {line}");
+        assert!(!line[what..].starts_with(' '), "failing line: {line}");
+    }
+}
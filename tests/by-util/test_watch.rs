@@ -62,3 +62,78 @@ fn test_valid_interval_comma() {
         .no_stderr()
         .no_stdout();
 }
+
+#[cfg(not(windows))]
+#[test]
+fn test_max_iterations_condition_not_met() {
+    new_ucmd!()
+        .args(&["-n", "0.05", "-g", "--max-iterations", "5", "echo constant"])
+        .fails()
+        .code_is(1)
+        .stdout_is("constant\n".repeat(5))
+        .stderr_is("watch: condition not met after 5 iterations\n");
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_max_iterations_output_changed() {
+    // Prints "first" on the first run and "changed" from then on.
+    let script = "if [ -e seen ]; then echo changed; else touch seen; echo first; fi";
+
+    new_ucmd!()
+        .args(&["-n", "0.05", "-g", "--max-iterations", "5", script])
+        .succeeds()
+        .stdout_is("first\nchanged\n")
+        .no_stderr();
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_max_iterations_without_condition() {
+    new_ucmd!()
+        .args(&["-n", "0.1", "--max-iterations", "2", "echo constant"])
+        .succeeds()
+        .stdout_is("constant\nconstant\n")
+        .no_stderr();
+}
+
+#[test]
+fn test_max_iterations_zero() {
+    new_ucmd!()
+        .args(&["--max-iterations", "0", TRUE_CMD])
+        .fails()
+        .code_is(1);
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_stderr_merged() {
+    // The pause keeps the arrival order of the two streams deterministic.
+    new_ucmd!()
+        .args(&["-n", "0.1", "--max-iterations", "1"])
+        .arg("echo out; sleep 0.1; echo err 1>&2")
+        .succeeds()
+        .stdout_is("out\nerr\n")
+        .no_stderr();
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_no_stderr() {
+    new_ucmd!()
+        .args(&["-n", "0.1", "--max-iterations", "1", "--no-stderr"])
+        .arg("echo out; echo err 1>&2")
+        .succeeds()
+        .stdout_is("out\n")
+        .no_stderr();
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_stderr_merged_exit_status() {
+    new_ucmd!()
+        .args(&["-n", "0.1", "-e", "echo failing 1>&2; exit 3"])
+        .succeeds()
+        .stdout_is("failing\n")
+        .stderr_contains("command failed");
+}